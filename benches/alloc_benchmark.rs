@@ -0,0 +1,82 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, Machine, Transaction};
+use native_vs_evm::evm_asm;
+use ruint::aliases::U256;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::hint::black_box;
+
+#[global_allocator]
+static ALLOC: dhat::Alloc = dhat::Alloc;
+
+const ITERATIONS: u64 = 1_000;
+const HISTORY: &str = "target/alloc_benchmark.json";
+
+// Average heap blocks and bytes allocated by one run, after a warm-up run so caches that are
+// only filled once don't count
+fn measure(mut run: impl FnMut()) -> (u64, u64) {
+    run();
+    let before = dhat::HeapStats::get();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    let after = dhat::HeapStats::get();
+    ((after.total_blocks - before.total_blocks) / ITERATIONS, (after.total_bytes - before.total_bytes) / ITERATIONS)
+}
+
+fn main() {
+    // full profile for the dhat viewer next to the summary
+    let _profiler = dhat::Profiler::builder().file_name("target/dhat-heap.json").build();
+
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+    let sub: Address = "0x2100000000000000000000000000000000000000".parse().unwrap();
+    let sender: Address = "0x3000000000000000000000000000000000000000".parse().unwrap();
+    let mut results = Vec::new();
+
+    let bytecode = evm_asm! { push 5; push 10; add }.to_vec();
+    results.push(("simple_add", measure(|| {
+        let mut machine = Machine::new(bytecode.clone(), vec![], HashMap::new(), 1_000_000);
+        black_box(machine.run());
+    })));
+
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract, Account::with_code(evm_asm! { push 0x42; push 1; sstore; push 1; sload; pop; stop }.to_vec()));
+    results.push(("sstore_sload", measure(|| {
+        black_box(machine.call(Address::ZERO, contract, vec![], 1_000_000));
+    })));
+
+    // CALL into `sub`, which returns 32 bytes
+    let caller_code = evm_asm! {
+        push 0x20; push 0; push 0; push 0; push 0;
+        push 0x2100000000000000000000000000000000000000;
+        push 0xffff; call; stop;
+    }.to_vec();
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract, Account::with_code(caller_code));
+    machine.accounts.insert(sub, Account::with_code(evm_asm! { push 0xaa; push 0; mstore; push 0x20; push 0; return }.to_vec()));
+    results.push(("nested_call", measure(|| {
+        black_box(machine.call(Address::ZERO, contract, vec![], 1_000_000));
+    })));
+
+    let mut machine = Machine::default();
+    machine.accounts.insert(sender, Account { balance: U256::MAX, ..Default::default() });
+    let tx = Transaction { caller: sender, to: contract, value: U256::from(1), gas_limit: 21_000, ..Default::default() };
+    results.push(("value_transfer", measure(|| {
+        black_box(machine.transact(&tx).unwrap());
+    })));
+
+    // compare against the previous run and keep this one as the new baseline
+    let previous: Value = std::fs::read_to_string(HISTORY).ok().and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default();
+    println!("{:<16} {:>10} {:>12} {:>10}", "scenario", "allocs/run", "bytes/run", "vs last");
+    for (name, (blocks, bytes)) in &results {
+        let delta = match previous[name]["blocks"].as_u64() {
+            Some(last) => format!("{:+}", *blocks as i64 - last as i64),
+            None => "-".to_string(),
+        };
+        println!("{:<16} {:>10} {:>12} {:>10}", name, blocks, bytes, delta);
+    }
+
+    let current: serde_json::Map<String, Value> =
+        results.iter().map(|(name, (blocks, bytes))| (name.to_string(), json!({ "blocks": blocks, "bytes": bytes }))).collect();
+    std::fs::write(HISTORY, serde_json::to_string_pretty(&current).unwrap()).unwrap();
+}
@@ -1,9 +1,10 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use native_vs_evm::evm::Machine;
+use native_vs_evm::evm_asm;
 use std::collections::HashMap;
 
 fn bench_simple_add(c: &mut Criterion) {
-    let bytecode = hex::decode("6005600a01").unwrap(); // PUSH1 0x05, PUSH1 0x0a, ADD
+    let bytecode = evm_asm! { push 5; push 10; add }.to_vec();
 
     c.bench_function("simple_add", |b| {
         b.iter(|| {
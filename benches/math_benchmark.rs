@@ -1,35 +1,38 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use native_vs_evm::evm::Machine;
+use native_vs_evm::compiler::Program;
+use native_vs_evm::scenario::{self, Scenario};
 use ruint::aliases::U256;
-use std::collections::HashMap;
-
-fn bench_math_comparison(c: &mut Criterion) {
-    let mut group = c.benchmark_group("Math: (5 + 10) * 2");
-
-    group.bench_function("Native Rust", |b| {
-        b.iter(|| {
-            let a = U256::from(5);
-            let b = U256::from(10);
-            let multiplier = U256::from(2);
-            let res = (a + b) * multiplier;
-            black_box(res);
-        })
-    });
 
+// Checks that both sides agree before timing either
+fn bench_scenario(c: &mut Criterion, scenario: &Scenario) {
+    if let Err(mismatch) = scenario.verify() {
+        panic!("{}: native and EVM sides disagree: {:?}", scenario.name, mismatch);
+    }
+    let mut group = c.benchmark_group(&scenario.name);
+    let mut storage = scenario.storage.clone();
+    group.bench_function("Native Rust", |b| b.iter(|| black_box(scenario.run_native(&mut storage))));
+    group.bench_function("Tiny EVM", |b| b.iter(|| black_box(scenario.run_evm())));
+    group.finish();
+}
 
-    // PUSH1 0x02, PUSH1 0x0a, PUSH1 0x05, ADD, MUL
-    // [2, 10, 5] -> ADD -> [2, 15] -> MUL -> [30]
-    let bytecode = hex::decode("6002600a60050102").unwrap();
-    group.bench_function("Tiny EVM", |b| {
-        b.iter(|| {
-            let mut machine = Machine::new(bytecode.clone(), vec![], HashMap::new(), 1_000_000);
-            let res = machine.run();
-            black_box(res);
-        })
-    });
+fn bench_builtin_scenarios(c: &mut Criterion) {
+    for scenario in scenario::builtin() {
+        bench_scenario(c, &scenario);
+    }
+}
 
-    group.finish();
+// The same generated program as a native closure and as bytecode, for a handful of seeds
+fn bench_random_programs(c: &mut Criterion) {
+    let inputs = [U256::from(7), U256::from(3), U256::from(11)];
+    for seed in 0..4 {
+        let program = Program::random(seed);
+        let inputs = inputs[..program.inputs].to_vec();
+        let native = program.to_native();
+        let calldata = Program::calldata(&inputs);
+        let scenario = Scenario::new(format!("Random program {}", seed), program.to_bytecode(), move || native(black_box(&inputs)));
+        bench_scenario(c, &scenario.with_calldata(calldata));
+    }
 }
 
-criterion_group!(benches, bench_math_comparison);
-criterion_main!(benches);
\ No newline at end of file
+criterion_group!(benches, bench_builtin_scenarios, bench_random_programs);
+criterion_main!(benches);
@@ -0,0 +1,132 @@
+use native_vs_evm::builder::BytecodeBuilder;
+use native_vs_evm::evm::{ExecutionResult, Hardfork, Machine};
+use native_vs_evm::opcode::Opcode;
+use ruint::aliases::U256;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Instant;
+
+// Loop iterations per run, and copies of the opcode under test per iteration
+const ITERATIONS: u64 = 2_000;
+const UNROLL: u64 = 16;
+// runs per program; the fastest counts
+const REPEATS: usize = 10;
+const GAS_LIMIT: u64 = 1 << 40;
+const HISTORY: &str = "target/opcode_benchmark.json";
+
+// What a loop of one opcode took, net of the loop itself: time and gas per copy of the gadget
+#[derive(Clone, Copy)]
+struct Sample {
+    nanos: f64,
+    gas: f64,
+}
+
+// Opcodes that can't sit in a straight-line loop body: halts and jumps end or leave it, and CALL
+// needs a callee. Everything else in the table is benchmarked
+fn loopable(opcode: Opcode) -> bool {
+    !opcode.is_terminator() && !matches!(opcode, Opcode::JumpI | Opcode::Call)
+}
+
+// The opcode with its operands pushed in front and its results popped after, so the loop body
+// leaves the stack as it found it. PUSHn pushes a value that needs exactly n bytes
+fn gadget(code: &mut BytecodeBuilder, opcode: Opcode, operand: U256) {
+    if opcode.is_push() {
+        code.push(U256::from(1) << (8 * (opcode.immediate_size() - 1))).pop();
+        return;
+    }
+    let (inputs, outputs) = opcode.stack_io();
+    for _ in 0..inputs {
+        code.push(operand);
+    }
+    code.op(opcode);
+    for _ in 0..outputs {
+        code.pop();
+    }
+}
+
+// A counter on the stack, counted down around `UNROLL` copies of the gadget
+fn program(opcode: Option<Opcode>, operand: U256, iterations: u64) -> Vec<u8> {
+    let mut code = BytecodeBuilder::new();
+    code.push(U256::from(iterations)).jumpdest("loop");
+    for _ in 0..UNROLL {
+        if let Some(opcode) = opcode {
+            gadget(&mut code, opcode, operand);
+        }
+    }
+    code.push(U256::from(1)).sub().dup(1).jumpi("loop").pop().stop();
+    code.build().expect("the loop label is defined")
+}
+
+// Fastest of `REPEATS` runs and the gas one run used, or None if the program doesn't finish.
+// Cancun, so every opcode in the table is active
+fn run(code: &[u8]) -> Option<(f64, u64)> {
+    let mut nanos = f64::MAX;
+    let mut gas = 0;
+    for _ in 0..REPEATS {
+        let mut machine = Machine::new(code.to_vec(), vec![], HashMap::new(), GAS_LIMIT);
+        machine.hardfork = Hardfork::Cancun;
+        let started = Instant::now();
+        let result = machine.run();
+        nanos = nanos.min(started.elapsed().as_nanos() as f64);
+        if result != ExecutionResult::Success(vec![]) {
+            return None;
+        }
+        gas = GAS_LIMIT - machine.gas_left();
+    }
+    Some((nanos, gas))
+}
+
+// Operands of 1 keep memory and hashing small; ones that halt that way (RETURNDATACOPY reading
+// past empty return data) get zeros instead
+fn measure(opcode: Opcode, empty_loop: (f64, u64)) -> Option<Sample> {
+    let (nanos, gas) = [U256::from(1), U256::ZERO].into_iter().find_map(|operand| {
+        run(&program(Some(opcode), operand, 1))?;
+        run(&program(Some(opcode), operand, ITERATIONS))
+    })?;
+    let copies = (ITERATIONS * UNROLL) as f64;
+    Some(Sample { nanos: (nanos - empty_loop.0) / copies, gas: (gas - empty_loop.1) as f64 / copies })
+}
+
+fn main() {
+    let empty_loop = run(&program(None, U256::ZERO, ITERATIONS)).expect("the empty loop finishes");
+    // PUSH1 then POP, which every gadget's operands and results cost on top of the opcode. Pushes
+    // and pops are taken to cost the same time, half of the pair
+    let pair = measure(Opcode::Push1, empty_loop).expect("PUSH1 POP finishes");
+
+    let mut results = Vec::new();
+    for &opcode in Opcode::ALL.iter().filter(|opcode| loopable(**opcode)) {
+        let Some(sample) = measure(opcode, empty_loop) else {
+            println!("{}: does not run in a loop, skipped", opcode);
+            continue;
+        };
+        let (inputs, outputs) = opcode.stack_io();
+        let stack_ops = (inputs + outputs) as f64;
+        let stack_gas = inputs as f64 * Opcode::Push1.base_gas() as f64 + outputs as f64 * Opcode::Pop.base_gas() as f64;
+        let own = Sample { nanos: (sample.nanos - stack_ops * pair.nanos / 2.0).max(0.0), gas: sample.gas - stack_gas };
+        results.push((opcode, own, sample));
+    }
+
+    // slowest per unit of gas first; free opcodes at the top, since any time there is unpaid
+    results.sort_by(|a, b| ns_per_gas(&b.1).total_cmp(&ns_per_gas(&a.1)));
+    let previous: Value = std::fs::read_to_string(HISTORY).ok().and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default();
+    println!("{:<16} {:>8} {:>10} {:>10} {:>10} {:>10}", "opcode", "gas", "ns/op", "Mops/s", "ns/gas", "vs last");
+    for (opcode, own, _) in &results {
+        let delta = match previous[opcode.mnemonic()]["nanos"].as_f64() {
+            Some(last) if last > 0.0 => format!("{:+.0}%", (own.nanos / last - 1.0) * 100.0),
+            _ => "-".to_string(),
+        };
+        let throughput = if own.nanos > 0.0 { format!("{:.1}", 1e3 / own.nanos) } else { "-".to_string() };
+        let per_gas = if own.gas > 0.0 { format!("{:.3}", own.nanos / own.gas) } else { "free".to_string() };
+        println!("{:<16} {:>8.0} {:>10.2} {:>10} {:>10} {:>10}", opcode.mnemonic(), own.gas, own.nanos, throughput, per_gas, delta);
+    }
+
+    let current: serde_json::Map<String, Value> = results
+        .iter()
+        .map(|(opcode, own, sample)| (opcode.mnemonic().to_string(), json!({ "nanos": own.nanos, "gas": own.gas, "gadget_nanos": sample.nanos })))
+        .collect();
+    std::fs::write(HISTORY, serde_json::to_string_pretty(&current).unwrap()).unwrap();
+}
+
+fn ns_per_gas(sample: &Sample) -> f64 {
+    if sample.gas > 0.0 { sample.nanos / sample.gas } else { f64::INFINITY }
+}
@@ -0,0 +1,57 @@
+use alloy::primitives::Address;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use native_vs_evm::disk::DiskHost;
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+use native_vs_evm::evm_asm;
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+const SLOTS: u64 = 100_000;
+
+fn bench_sload(c: &mut Criterion) {
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+    let bytecode = evm_asm! { push 0; calldataload; sload; push 0; mstore; push 0x20; push 0; return }.to_vec();
+    let storage: HashMap<U256, U256> = (0..SLOTS).map(|i| (U256::from(i), U256::from(i + 1))).collect();
+
+    let mut account = Account::with_code(bytecode);
+    account.storage = storage.clone();
+    let disk = DiskHost::temporary().unwrap();
+    disk.commit([(&contract, &account)]).unwrap();
+
+    let mut memory = Machine::default();
+    memory.accounts.insert(contract, account);
+
+    // every side has to read the same value before any of them is timed
+    let key = U256::from(SLOTS / 2);
+    let expected = ExecutionResult::Success(storage[&key].to_be_bytes::<32>().to_vec());
+    assert_eq!(memory.call(Address::ZERO, contract, key.to_be_bytes::<32>().to_vec(), 1_000_000), expected);
+    assert_eq!(Machine::with_host(disk.clone()).call(Address::ZERO, contract, key.to_be_bytes::<32>().to_vec(), 1_000_000), expected);
+
+    let mut group = c.benchmark_group("SLOAD from 100k slots");
+    let mut i = 0u64;
+    let mut next_key = move || {
+        i = (i + 7919) % SLOTS;
+        U256::from(i)
+    };
+
+    group.bench_function("Native HashMap", |b| {
+        b.iter(|| black_box(storage.get(&next_key())))
+    });
+
+    group.bench_function("EVM in-memory", |b| {
+        b.iter(|| black_box(memory.call(Address::ZERO, contract, next_key().to_be_bytes::<32>().to_vec(), 1_000_000)))
+    });
+
+    // a fresh machine per call, so both the account and the slot come from disk
+    group.bench_function("EVM sled", |b| {
+        b.iter(|| {
+            let mut machine = Machine::with_host(disk.clone());
+            black_box(machine.call(Address::ZERO, contract, next_key().to_be_bytes::<32>().to_vec(), 1_000_000))
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_sload);
+criterion_main!(benches);
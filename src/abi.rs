@@ -0,0 +1,289 @@
+use alloy::primitives::{keccak256, Address};
+use ruint::aliases::U256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiType {
+    Uint(usize),
+    Address,
+    Bool,
+    FixedBytes(usize),
+    Bytes,
+    String,
+    Array(Box<AbiType>),
+    FixedArray(Box<AbiType>, usize),
+    Tuple(Vec<AbiType>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiValue {
+    Uint(U256),
+    Address(Address),
+    Bool(bool),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<AbiValue>),
+    FixedArray(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AbiError {
+    InvalidType(String),
+    OutOfBounds { offset: usize, len: usize },
+    InvalidUtf8,
+}
+
+impl AbiType {
+    // Solidity type names as they appear in signatures: uint256, bytes32, address[], (uint256,bool)[2]
+    pub fn parse(name: &str) -> Result<Self, AbiError> {
+        let name = name.trim();
+        if let Some(inner) = name.strip_suffix(']') {
+            let open = inner.rfind('[').ok_or_else(|| AbiError::InvalidType(name.to_string()))?;
+            let element = Box::new(Self::parse(&inner[..open])?);
+            return match &inner[open + 1..] {
+                "" => Ok(AbiType::Array(element)),
+                len => len.parse().map(|len| AbiType::FixedArray(element, len)).map_err(|_| AbiError::InvalidType(name.to_string())),
+            };
+        }
+        if let Some(inner) = name.strip_prefix('(').and_then(|inner| inner.strip_suffix(')')) {
+            return split_top_level(inner).into_iter().map(Self::parse).collect::<Result<_, _>>().map(AbiType::Tuple);
+        }
+
+        match name {
+            "address" => Ok(AbiType::Address),
+            "bool" => Ok(AbiType::Bool),
+            "bytes" => Ok(AbiType::Bytes),
+            "string" => Ok(AbiType::String),
+            "uint" => Ok(AbiType::Uint(256)),
+            _ => {
+                let parsed = if let Some(bits) = name.strip_prefix("uint") {
+                    bits.parse().ok().filter(|bits| bits % 8 == 0 && (8..=256).contains(bits)).map(AbiType::Uint)
+                } else if let Some(len) = name.strip_prefix("bytes") {
+                    len.parse().ok().filter(|len| (1..=32).contains(len)).map(AbiType::FixedBytes)
+                } else {
+                    None
+                };
+                parsed.ok_or_else(|| AbiError::InvalidType(name.to_string()))
+            }
+        }
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiType::Bytes | AbiType::String | AbiType::Array(_) => true,
+            AbiType::FixedArray(element, _) => element.is_dynamic(),
+            AbiType::Tuple(types) => types.iter().any(AbiType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    // Bytes taken in the head of the enclosing tuple
+    fn head_size(&self) -> usize {
+        match self {
+            _ if self.is_dynamic() => 32,
+            AbiType::FixedArray(element, len) => element.head_size() * len,
+            AbiType::Tuple(types) => types.iter().map(AbiType::head_size).sum(),
+            _ => 32,
+        }
+    }
+}
+
+impl AbiValue {
+    pub fn uint(value: u64) -> Self {
+        AbiValue::Uint(U256::from(value))
+    }
+
+    pub fn as_uint(&self) -> Option<U256> {
+        match self {
+            AbiValue::Uint(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_address(&self) -> Option<Address> {
+        match self {
+            AbiValue::Address(address) => Some(*address),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            AbiValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            AbiValue::Bytes(bytes) | AbiValue::FixedBytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AbiValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_slice(&self) -> Option<&[AbiValue]> {
+        match self {
+            AbiValue::Array(values) | AbiValue::FixedArray(values) | AbiValue::Tuple(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiValue::Bytes(_) | AbiValue::String(_) | AbiValue::Array(_) => true,
+            AbiValue::FixedArray(values) | AbiValue::Tuple(values) => values.iter().any(AbiValue::is_dynamic),
+            _ => false,
+        }
+    }
+
+    fn head_size(&self) -> usize {
+        match self {
+            AbiValue::FixedArray(values) | AbiValue::Tuple(values) if !self.is_dynamic() => values.iter().map(AbiValue::head_size).sum(),
+            _ => 32,
+        }
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        match self {
+            AbiValue::Uint(value) => out.extend_from_slice(&value.to_be_bytes::<32>()),
+            AbiValue::Address(address) => out.extend_from_slice(address.into_word().as_slice()),
+            AbiValue::Bool(value) => out.extend_from_slice(&U256::from(*value as u8).to_be_bytes::<32>()),
+            AbiValue::FixedBytes(bytes) => extend_padded(out, bytes),
+            AbiValue::Bytes(bytes) => {
+                out.extend_from_slice(&U256::from(bytes.len()).to_be_bytes::<32>());
+                extend_padded(out, bytes);
+            }
+            AbiValue::String(value) => {
+                out.extend_from_slice(&U256::from(value.len()).to_be_bytes::<32>());
+                extend_padded(out, value.as_bytes());
+            }
+            AbiValue::Array(values) => {
+                out.extend_from_slice(&U256::from(values.len()).to_be_bytes::<32>());
+                encode_tuple(values, out);
+            }
+            AbiValue::FixedArray(values) | AbiValue::Tuple(values) => encode_tuple(values, out),
+        }
+    }
+}
+
+pub fn selector(signature: &str) -> [u8; 4] {
+    keccak256(signature.as_bytes())[..4].try_into().unwrap()
+}
+
+// Encodes `values` as a parameter list (the tuple after the selector, or return data)
+pub fn encode(values: &[AbiValue]) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_tuple(values, &mut out);
+    out
+}
+
+pub fn encode_call(signature: &str, args: &[AbiValue]) -> Vec<u8> {
+    let mut calldata = selector(signature).to_vec();
+    encode_tuple(args, &mut calldata);
+    calldata
+}
+
+pub fn decode(types: &[AbiType], data: &[u8]) -> Result<Vec<AbiValue>, AbiError> {
+    decode_tuple(types, data, 0)
+}
+
+fn encode_tuple(values: &[AbiValue], out: &mut Vec<u8>) {
+    let head_size: usize = values.iter().map(AbiValue::head_size).sum();
+    let mut tail = Vec::new();
+
+    for value in values {
+        if value.is_dynamic() {
+            out.extend_from_slice(&U256::from(head_size + tail.len()).to_be_bytes::<32>());
+            value.encode_into(&mut tail);
+        } else {
+            value.encode_into(out);
+        }
+    }
+    out.extend_from_slice(&tail);
+}
+
+fn decode_tuple(types: &[AbiType], data: &[u8], base: usize) -> Result<Vec<AbiValue>, AbiError> {
+    let mut head = base;
+    types.iter().map(|ty| {
+        let value = if ty.is_dynamic() {
+            let offset = read_usize(data, head)?;
+            decode_value(ty, data, base.checked_add(offset).ok_or(AbiError::OutOfBounds { offset, len: data.len() })?)
+        } else {
+            decode_value(ty, data, head)
+        };
+        head += ty.head_size();
+        value
+    }).collect()
+}
+
+fn decode_value(ty: &AbiType, data: &[u8], at: usize) -> Result<AbiValue, AbiError> {
+    match ty {
+        AbiType::Uint(_) => Ok(AbiValue::Uint(U256::from_be_slice(read_word(data, at)?))),
+        AbiType::Address => Ok(AbiValue::Address(Address::from_slice(&read_word(data, at)?[12..]))),
+        AbiType::Bool => Ok(AbiValue::Bool(read_word(data, at)?.iter().any(|byte| *byte != 0))),
+        AbiType::FixedBytes(len) => Ok(AbiValue::FixedBytes(read_word(data, at)?[..*len].to_vec())),
+        AbiType::Bytes => read_bytes(data, at).map(|bytes| AbiValue::Bytes(bytes.to_vec())),
+        AbiType::String => {
+            let bytes = read_bytes(data, at)?;
+            String::from_utf8(bytes.to_vec()).map(AbiValue::String).map_err(|_| AbiError::InvalidUtf8)
+        }
+        AbiType::Array(element) => {
+            let len = read_usize(data, at)?;
+            // every element needs at least one word, which bounds allocations for garbage lengths
+            if len > data.len() / 32 {
+                return Err(AbiError::OutOfBounds { offset: at, len: data.len() });
+            }
+            decode_tuple(&vec![(**element).clone(); len], data, at + 32).map(AbiValue::Array)
+        }
+        AbiType::FixedArray(element, len) => decode_tuple(&vec![(**element).clone(); *len], data, at).map(AbiValue::FixedArray),
+        AbiType::Tuple(types) => decode_tuple(types, data, at).map(AbiValue::Tuple),
+    }
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<&[u8], AbiError> {
+    data.get(at..at.saturating_add(32)).ok_or(AbiError::OutOfBounds { offset: at, len: data.len() })
+}
+
+fn read_usize(data: &[u8], at: usize) -> Result<usize, AbiError> {
+    let value = U256::from_be_slice(read_word(data, at)?);
+    usize::try_from(value).map_err(|_| AbiError::OutOfBounds { offset: at, len: data.len() })
+}
+
+fn read_bytes(data: &[u8], at: usize) -> Result<&[u8], AbiError> {
+    let len = read_usize(data, at)?;
+    let start = at + 32;
+    data.get(start..start.saturating_add(len)).ok_or(AbiError::OutOfBounds { offset: start, len: data.len() })
+}
+
+fn extend_padded(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(bytes);
+    out.resize(out.len() + (32 - bytes.len() % 32) % 32, 0);
+}
+
+fn split_top_level(list: &str) -> Vec<&str> {
+    if list.trim().is_empty() {
+        return Vec::new();
+    }
+    let (mut parts, mut depth, mut start) = (Vec::new(), 0, 0);
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&list[start..]);
+    parts
+}
@@ -0,0 +1,108 @@
+use crate::evm::{Account, Inspector, Machine, Transaction, TransactionError, TransactionOutcome};
+use crate::opcode::Opcode;
+use alloy::primitives::Address;
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+// Records every account and storage slot execution touches, in first-touch order
+#[derive(Debug, Default)]
+pub struct AccessListInspector {
+    accounts: Vec<(Address, Vec<U256>)>,
+}
+
+impl AccessListInspector {
+    pub fn add_address(&mut self, address: Address) -> &mut Vec<U256> {
+        let index = match self.accounts.iter().position(|(touched, _)| *touched == address) {
+            Some(index) => index,
+            None => {
+                self.accounts.push((address, Vec::new()));
+                self.accounts.len() - 1
+            }
+        };
+        &mut self.accounts[index].1
+    }
+
+    pub fn add_slot(&mut self, address: Address, key: U256) {
+        let keys = self.add_address(address);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    pub fn touched(&self) -> &[(Address, Vec<U256>)] {
+        &self.accounts
+    }
+
+    // EIP-2930 list as eth_createAccessList builds it: `excluded` addresses (sender and
+    // recipient, which are warm anyway) only appear when they have storage keys
+    pub fn access_list(&self, excluded: &[Address]) -> Vec<(Address, Vec<U256>)> {
+        self.accounts.iter().filter(|(address, keys)| !keys.is_empty() || !excluded.contains(address)).cloned().collect()
+    }
+}
+
+impl Inspector for AccessListInspector {
+    fn step(&mut self, machine: &Machine) {
+        let frame = machine.call_stack.last().unwrap();
+        let top = |depth: usize| frame.stack.len().checked_sub(depth + 1).map(|i| frame.stack[i]);
+        let opcode = frame.code.get(frame.pc).and_then(|&byte| Opcode::try_from(byte).ok());
+        match (opcode, top(0), top(1)) {
+            (Some(Opcode::SLoad | Opcode::SStore), Some(key), _) => self.add_slot(frame.callee, key),
+            (Some(Opcode::Call), _, Some(to)) => {
+                self.add_address(Address::from_word(to.to_be_bytes().into()));
+            }
+            _ => {}
+        }
+    }
+}
+
+// Pre-state a stateless client needs to re-execute the transaction: every touched account
+// with its code and only the touched slots. There is no trie, so no proofs
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionWitness {
+    pub accounts: HashMap<Address, Account>,
+}
+
+#[derive(Debug)]
+pub struct AccessListOutcome {
+    pub access_list: Vec<(Address, Vec<U256>)>,
+    pub witness: ExecutionWitness,
+    pub outcome: TransactionOutcome,
+}
+
+impl Machine {
+    // Simulates `tx` and reports what it read and wrote. State is left as it was
+    pub fn create_access_list(&mut self, tx: &Transaction) -> Result<AccessListOutcome, TransactionError> {
+        let mut inspector = AccessListInspector::default();
+        inspector.add_address(tx.caller);
+        inspector.add_address(tx.to);
+        let outcome = self.simulate_with_inspector(tx, &mut inspector)?;
+
+        let mut touched = inspector.touched().to_vec();
+        if !touched.iter().any(|(address, _)| *address == self.block.coinbase) {
+            touched.push((self.block.coinbase, Vec::new()));
+        }
+        let witness = self.witness(&touched).map_err(TransactionError::HostError)?;
+
+        Ok(AccessListOutcome { access_list: inspector.access_list(&[tx.caller, tx.to]), witness, outcome })
+    }
+
+    fn witness(&mut self, touched: &[(Address, Vec<U256>)]) -> Result<ExecutionWitness, String> {
+        let mut accounts = HashMap::new();
+        for (address, keys) in touched {
+            let code = self.code(*address)?;
+            let account = self.account(*address)?;
+            let mut witness_account = Account {
+                balance: account.balance,
+                nonce: account.nonce,
+                code,
+                jumpdests: account.jumpdests.clone(),
+                ..Default::default()
+            };
+            for key in keys {
+                witness_account.storage.insert(*key, self.storage(*address, *key)?);
+            }
+            accounts.insert(*address, witness_account);
+        }
+        Ok(ExecutionWitness { accounts })
+    }
+}
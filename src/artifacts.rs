@@ -0,0 +1,159 @@
+use crate::evm::{Account, Machine};
+use crate::metadata::{parse_metadata, strip_metadata, Metadata};
+use alloy::primitives::{keccak256, Address};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum ArtifactError {
+    Io(std::io::Error),
+    Json(String),
+    MissingBytecode(String),
+    InvalidHex(String),
+    UnlinkedLibrary(String),
+}
+
+impl From<std::io::Error> for ArtifactError {
+    fn from(e: std::io::Error) -> Self {
+        ArtifactError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+    pub name: String,
+    pub source: Option<String>,
+    // deployed (runtime) bytecode as hex, library placeholders still in place
+    pub deployed_bytecode: String,
+}
+
+impl Artifact {
+    // "path:Name", the form solc hashes into library placeholders
+    pub fn fully_qualified_name(&self) -> String {
+        match &self.source {
+            Some(source) => format!("{}:{}", source, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    pub fn bytecode(&self) -> Result<Vec<u8>, ArtifactError> {
+        self.link(&HashMap::new())
+    }
+
+    // Libraries are keyed by fully qualified name ("src/Math.sol:Math"). Both the
+    // `__$<keccak prefix>$__` placeholders of solc >= 0.5 and the older `__Name___` ones are replaced
+    pub fn link(&self, libraries: &HashMap<String, Address>) -> Result<Vec<u8>, ArtifactError> {
+        let mut code = self.deployed_bytecode.clone();
+        for (name, address) in libraries {
+            let address = hex::encode(address);
+            code = code.replace(&hashed_placeholder(name), &address);
+            code = code.replace(&legacy_placeholder(name), &address);
+        }
+
+        if let Some(start) = code.find("__") {
+            let placeholder = code[start..(start + 40).min(code.len())].to_string();
+            return Err(ArtifactError::UnlinkedLibrary(placeholder));
+        }
+        hex::decode(&code).map_err(|e| ArtifactError::InvalidHex(format!("{}: {}", self.name, e)))
+    }
+
+    // Linked code without the solc metadata trailer, for code-size comparisons and benchmarks
+    pub fn link_stripped(&self, libraries: &HashMap<String, Address>) -> Result<Vec<u8>, ArtifactError> {
+        Ok(strip_metadata(&self.link(libraries)?).to_vec())
+    }
+
+    pub fn metadata(&self) -> Result<Option<Metadata>, ArtifactError> {
+        Ok(parse_metadata(&self.bytecode()?))
+    }
+
+    pub fn to_account(&self, libraries: &HashMap<String, Address>) -> Result<Account, ArtifactError> {
+        Ok(Account::with_code(self.link(libraries)?))
+    }
+}
+
+impl Machine {
+    pub fn deploy_artifact(&mut self, address: Address, artifact: &Artifact, libraries: &HashMap<String, Address>) -> Result<(), ArtifactError> {
+        self.deploy(address, artifact.link(libraries)?);
+        Ok(())
+    }
+}
+
+// Output of `solc --combined-json bin-runtime,...`
+pub fn load_combined_json(path: impl AsRef<Path>) -> Result<Vec<Artifact>, ArtifactError> {
+    let json = read_json(path.as_ref())?;
+    let contracts = json.get("contracts").and_then(Value::as_object).ok_or_else(|| ArtifactError::Json("missing \"contracts\"".into()))?;
+
+    contracts.iter().map(|(key, contract)| {
+        let (source, name) = match key.rsplit_once(':') {
+            Some((source, name)) => (Some(source.to_string()), name.to_string()),
+            None => (None, key.clone()),
+        };
+        let bytecode = contract.get("bin-runtime").and_then(Value::as_str).ok_or_else(|| ArtifactError::MissingBytecode(key.clone()))?;
+        Ok(Artifact { name, source, deployed_bytecode: strip_hex_prefix(bytecode) })
+    }).collect()
+}
+
+// A single Foundry `out/<File>.sol/<Name>.json` artifact
+pub fn load_foundry_artifact(path: impl AsRef<Path>) -> Result<Artifact, ArtifactError> {
+    let path = path.as_ref();
+    let json = read_json(path)?;
+    let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let bytecode = json
+        .pointer("/deployedBytecode/object")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ArtifactError::MissingBytecode(name.clone()))?;
+
+    // compilationTarget holds the source path solc used, which the placeholder hash depends on
+    let source = json
+        .pointer("/metadata/settings/compilationTarget")
+        .and_then(Value::as_object)
+        .and_then(|target| target.keys().next().cloned());
+
+    Ok(Artifact { name, source, deployed_bytecode: strip_hex_prefix(bytecode) })
+}
+
+// Every contract with runtime code under a Foundry `out/` directory; interfaces and
+// abstract contracts (empty deployed bytecode) and build-info files are skipped
+pub fn load_foundry_out(out_dir: impl AsRef<Path>) -> Result<Vec<Artifact>, ArtifactError> {
+    let mut artifacts = Vec::new();
+    for source_dir in fs::read_dir(out_dir)? {
+        let source_dir = source_dir?.path();
+        if !source_dir.is_dir() || source_dir.file_name().is_some_and(|name| name == "build-info") {
+            continue;
+        }
+        for file in fs::read_dir(&source_dir)? {
+            let file = file?.path();
+            if file.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match load_foundry_artifact(&file) {
+                Ok(artifact) if !artifact.deployed_bytecode.is_empty() => artifacts.push(artifact),
+                Ok(_) | Err(ArtifactError::MissingBytecode(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(artifacts)
+}
+
+fn read_json(path: &Path) -> Result<Value, ArtifactError> {
+    let contents = fs::read_to_string(path)?;
+    serde_json::from_str(&contents).map_err(|e| ArtifactError::Json(format!("{}: {}", path.display(), e)))
+}
+
+fn strip_hex_prefix(code: &str) -> String {
+    code.strip_prefix("0x").unwrap_or(code).to_string()
+}
+
+fn hashed_placeholder(fully_qualified_name: &str) -> String {
+    let hash = hex::encode(keccak256(fully_qualified_name.as_bytes()));
+    format!("__${}$__", &hash[..34])
+}
+
+fn legacy_placeholder(fully_qualified_name: &str) -> String {
+    let name: String = fully_qualified_name.chars().take(36).collect();
+    format!("__{:_<38}", name)
+}
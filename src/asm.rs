@@ -0,0 +1,236 @@
+use crate::opcode::Opcode;
+
+// Assembly written straight into Rust source, assembled while compiling:
+//
+//     let code = evm_asm! {
+//         push 5;
+//         again:
+//         push 1; sub;
+//         dup1; jumpi again;
+//         stop;
+//     };
+//
+// Statements end with `;`. `name:` marks a JUMPDEST, `jump name` and `jumpi name` jump to one and
+// `push name` pushes its pc. `push` takes a decimal or 0x-prefixed literal of up to 256 bits and
+// picks the shortest PUSHn. Any other word is an opcode mnemonic in any case. Unknown mnemonics,
+// undefined or repeated labels and values that do not fit fail the build. Expands to a `[u8; N]`
+//
+// The macro only stringifies tokens; statements are picked apart by the const functions below, so
+// bodies of any length expand without recursing
+#[macro_export]
+macro_rules! evm_asm {
+    ($($token:tt)*) => {{
+        const TOKENS: &[&str] = &[$(stringify!($token)),*];
+        const CODE: [u8; $crate::asm::assembled_len(TOKENS)] = $crate::asm::assemble(TOKENS);
+        CODE
+    }};
+}
+
+// One statement of `evm_asm!`, with names and literals kept as written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Item {
+    Op(&'static str),
+    Push(&'static str),
+    // a JUMPDEST
+    Label(&'static str),
+    // a label's pc, always as a PUSH2
+    PushLabel(&'static str),
+    // PushLabel followed by JUMP or JUMPI
+    Jump(&'static str),
+    JumpI(&'static str),
+}
+
+// The statement starting at or after token `i` and the token after it, or None past the last one
+pub const fn next_item(tokens: &[&'static str], mut i: usize) -> Option<(Item, usize)> {
+    while i < tokens.len() && is(tokens[i], ";") {
+        i += 1;
+    }
+    if i == tokens.len() {
+        return None;
+    }
+    let word = tokens[i];
+    if !is_name(word) {
+        panic!("evm_asm!: expected an instruction or a label");
+    }
+    // the word after this one, if it is a name or literal on the same statement
+    let operand = if i + 1 < tokens.len() && !is(tokens[i + 1], ";") { Some(tokens[i + 1]) } else { None };
+    match operand {
+        Some(colon) if is(colon, ":") => Some((Item::Label(word), i + 2)),
+        Some(value) if is(word, "push") && is_name(value) => Some((Item::PushLabel(value), i + 2)),
+        Some(value) if is(word, "push") && value.as_bytes()[0].is_ascii_digit() => Some((Item::Push(value), i + 2)),
+        _ if is(word, "push") => panic!("evm_asm!: push takes an unsuffixed integer literal or a label"),
+        Some(label) if is(word, "jump") && is_name(label) => Some((Item::Jump(label), i + 2)),
+        Some(label) if is(word, "jumpi") && is_name(label) => Some((Item::JumpI(label), i + 2)),
+        _ => Some((Item::Op(word), i + 1)),
+    }
+}
+
+pub const fn assembled_len(tokens: &[&'static str]) -> usize {
+    let mut len = 0;
+    let mut next = next_item(tokens, 0);
+    while let Some((item, i)) = next {
+        len += item_len(&item);
+        next = next_item(tokens, i);
+    }
+    len
+}
+
+pub const fn assemble<const N: usize>(tokens: &[&'static str]) -> [u8; N] {
+    let mut code = [0u8; N];
+    let mut pc = 0;
+    let mut next = next_item(tokens, 0);
+    while let Some((item, i)) = next {
+        match item {
+            Item::Op(name) => {
+                code[pc] = opcode(name);
+            }
+            Item::Push(literal) => {
+                let word = parse_literal(literal);
+                let size = push_size(&word);
+                code[pc] = Opcode::Push1 as u8 + size as u8 - 1;
+                let mut j = 0;
+                while j < size {
+                    code[pc + 1 + j] = word[32 - size + j];
+                    j += 1;
+                }
+            }
+            Item::Label(label) => {
+                // catches repeated labels nothing jumps to
+                label_pc(tokens, label);
+                code[pc] = Opcode::JumpDest as u8;
+            }
+            Item::PushLabel(label) | Item::Jump(label) | Item::JumpI(label) => {
+                let target = label_pc(tokens, label);
+                if target > u16::MAX as usize {
+                    panic!("evm_asm!: label past what PUSH2 can address");
+                }
+                code[pc] = Opcode::Push2 as u8;
+                code[pc + 1] = (target >> 8) as u8;
+                code[pc + 2] = target as u8;
+                match item {
+                    Item::Jump(_) => code[pc + 3] = Opcode::Jump as u8,
+                    Item::JumpI(_) => code[pc + 3] = Opcode::JumpI as u8,
+                    _ => {}
+                }
+            }
+        }
+        pc += item_len(&item);
+        next = next_item(tokens, i);
+    }
+    code
+}
+
+const fn item_len(item: &Item) -> usize {
+    match *item {
+        Item::Op(_) | Item::Label(_) => 1,
+        Item::Push(literal) => 1 + push_size(&parse_literal(literal)),
+        Item::PushLabel(_) => 3,
+        Item::Jump(_) | Item::JumpI(_) => 4,
+    }
+}
+
+const fn label_pc(tokens: &[&'static str], label: &str) -> usize {
+    let mut found = None;
+    let mut pc = 0;
+    let mut next = next_item(tokens, 0);
+    while let Some((item, i)) = next {
+        if let Item::Label(name) = item
+            && is(name, label)
+        {
+            if found.is_some() {
+                panic!("evm_asm!: label defined twice");
+            }
+            found = Some(pc);
+        }
+        pc += item_len(&item);
+        next = next_item(tokens, i);
+    }
+    match found {
+        Some(pc) => pc,
+        None => panic!("evm_asm!: undefined label"),
+    }
+}
+
+const fn is(token: &str, text: &str) -> bool {
+    bytes_eq(token.as_bytes(), text.as_bytes(), false)
+}
+
+// An identifier: instruction, label or keyword
+const fn is_name(token: &str) -> bool {
+    let first = token.as_bytes()[0];
+    first.is_ascii_alphabetic() || first == b'_'
+}
+
+const fn opcode(name: &str) -> u8 {
+    let mut i = 0;
+    while i < Opcode::ALL.len() {
+        let opcode = Opcode::ALL[i];
+        if bytes_eq(opcode.mnemonic().as_bytes(), name.as_bytes(), true) {
+            if opcode as u8 >= Opcode::Push1 as u8 && opcode as u8 <= Opcode::Push32 as u8 {
+                panic!("evm_asm!: PUSHn takes its value through `push`");
+            }
+            return opcode as u8;
+        }
+        i += 1;
+    }
+    panic!("evm_asm!: unknown instruction")
+}
+
+// Labels compare exactly, mnemonics in any case
+const fn bytes_eq(a: &[u8], b: &[u8], ignore_case: bool) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        let equal = if ignore_case { a[i].eq_ignore_ascii_case(&b[i]) } else { a[i] == b[i] };
+        if !equal {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// A decimal or 0x-prefixed hex literal as a big-endian word; `_` separators are skipped
+const fn parse_literal(literal: &str) -> [u8; 32] {
+    let text = literal.as_bytes();
+    let hex = text.len() > 2 && text[0] == b'0' && (text[1] == b'x' || text[1] == b'X');
+    let radix: u32 = if hex { 16 } else { 10 };
+    let mut word = [0u8; 32];
+    let mut i = if hex { 2 } else { 0 };
+    while i < text.len() {
+        let digit = match text[i] {
+            b'_' => {
+                i += 1;
+                continue;
+            }
+            b'0'..=b'9' => (text[i] - b'0') as u32,
+            b'a'..=b'f' if hex => (text[i] - b'a' + 10) as u32,
+            b'A'..=b'F' if hex => (text[i] - b'A' + 10) as u32,
+            _ => panic!("evm_asm!: push takes an unsuffixed integer literal"),
+        };
+        // word = word * radix + digit
+        let mut carry = digit;
+        let mut j = 32;
+        while j > 0 {
+            j -= 1;
+            let value = word[j] as u32 * radix + carry;
+            word[j] = value as u8;
+            carry = value >> 8;
+        }
+        if carry != 0 {
+            panic!("evm_asm!: push value does not fit in 256 bits");
+        }
+        i += 1;
+    }
+    word
+}
+
+const fn push_size(word: &[u8; 32]) -> usize {
+    let mut leading = 0;
+    while leading < 31 && word[leading] == 0 {
+        leading += 1;
+    }
+    32 - leading
+}
@@ -0,0 +1,98 @@
+use crate::evm::{ExecutionResult, Machine, Transaction, TransactionOutcome};
+use alloy::primitives::{Address, B256};
+use alloy::sol_types::{Revert, SolError};
+use ruint::aliases::U256;
+
+// Fluent checks on the outcome of a call or transaction for integration tests. Each check panics
+// with the whole outcome in its message and hands it back, so checks chain:
+// `machine.expect_call(..).expect_success().expect_storage(..)`
+#[derive(Debug)]
+pub struct Expect<'a> {
+    machine: &'a mut Machine,
+    pub outcome: TransactionOutcome,
+}
+
+impl Machine {
+    pub fn expect_call(&mut self, caller: Address, to: Address, calldata: Vec<u8>, gas_limit: u64) -> Expect<'_> {
+        let result = self.call(caller, to, calldata, gas_limit);
+        let outcome = TransactionOutcome { result, gas_used: gas_limit - self.gas_left(), logs: self.logs.clone(), final_frame: self.final_frame.clone(), stats: self.stats() };
+        Expect { machine: self, outcome }
+    }
+
+    // Panics if the transaction is not valid at all, rather than when it reverts or halts
+    #[track_caller]
+    pub fn expect_transact(&mut self, tx: &Transaction) -> Expect<'_> {
+        let outcome = self.transact(tx).unwrap_or_else(|e| panic!("transaction rejected: {:?}", e));
+        Expect { machine: self, outcome }
+    }
+}
+
+impl Expect<'_> {
+    #[track_caller]
+    pub fn expect_success(self) -> Self {
+        assert!(matches!(self.outcome.result, ExecutionResult::Success(_)), "expected success, got {:?}", self.outcome);
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_return(self, data: &[u8]) -> Self {
+        match &self.outcome.result {
+            ExecutionResult::Success(returned) if returned == data => self,
+            _ => panic!("expected a return of 0x{}, got {:?}", hex::encode(data), self.outcome),
+        }
+    }
+
+    #[track_caller]
+    pub fn expect_revert(self) -> Self {
+        assert!(matches!(self.outcome.result, ExecutionResult::Revert(_)), "expected a revert, got {:?}", self.outcome);
+        self
+    }
+
+    // A revert carrying Error(string) with exactly this message
+    #[track_caller]
+    pub fn expect_revert_with(self, message: &str) -> Self {
+        let reason = match &self.outcome.result {
+            ExecutionResult::Revert(data) => Revert::abi_decode(data).ok().map(|revert| revert.reason),
+            _ => None,
+        };
+        assert_eq!(reason.as_deref(), Some(message), "expected a revert with {:?}, got {:?}", message, self.outcome);
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_halt(self, result: ExecutionResult) -> Self {
+        assert_eq!(self.outcome.result, result, "expected a halt, got {:?}", self.outcome);
+        self
+    }
+
+    // Some log emitted by `address` with this first topic
+    #[track_caller]
+    pub fn expect_log(self, address: Address, topic0: B256) -> Self {
+        let found = self.outcome.logs.iter().any(|log| log.address == address && log.topics().first() == Some(&topic0));
+        assert!(found, "expected a log from {} with topic {}, got {:?}", address, topic0, self.outcome.logs);
+        self
+    }
+
+    #[track_caller]
+    pub fn expect_no_logs(self) -> Self {
+        assert!(self.outcome.logs.is_empty(), "expected no logs, got {:?}", self.outcome.logs);
+        self
+    }
+
+    // Storage as the machine sees it now, falling back to its host for slots execution never
+    // touched; slots nobody wrote read as zero
+    #[track_caller]
+    pub fn expect_storage(self, address: Address, slot: U256, value: U256) -> Self {
+        let stored = self.machine.storage(address, slot).unwrap_or_else(|e| panic!("storage of {} at slot {}: {}", address, slot, e));
+        assert_eq!(stored, value, "storage of {} at slot {}", address, slot);
+        self
+    }
+
+    // Inclusive on both ends
+    #[track_caller]
+    pub fn expect_gas_between(self, min: u64, max: u64) -> Self {
+        let gas_used = self.outcome.gas_used;
+        assert!((min..=max).contains(&gas_used), "expected gas between {} and {}, used {}", min, max, gas_used);
+        self
+    }
+}
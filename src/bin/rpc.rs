@@ -0,0 +1,27 @@
+use native_vs_evm::evm::Machine;
+use native_vs_evm::rpc::RpcServer;
+use std::net::SocketAddr;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    dotenv::dotenv().ok();
+
+    let addr: SocketAddr = std::env::var("RPC_ADDR")
+        .unwrap_or_else(|_| "127.0.0.1:8545".to_string())
+        .parse()
+        .expect("RPC_ADDR must be a socket address");
+    let chain_id = std::env::var("CHAIN_ID").map_or(31337, |id| id.parse().expect("CHAIN_ID must be a number"));
+
+    #[cfg(feature = "metrics")]
+    if let Ok(metrics_addr) = std::env::var("METRICS_ADDR") {
+        let metrics_addr: SocketAddr = metrics_addr.parse().expect("METRICS_ADDR must be a socket address");
+        metrics_exporter_prometheus::PrometheusBuilder::new()
+            .with_http_listener(metrics_addr)
+            .install()
+            .expect("failed to install the Prometheus exporter");
+        println!("Prometheus metrics on http://{}/metrics", metrics_addr);
+    }
+
+    println!("JSON-RPC listening on http://{} (chain id {})", addr, chain_id);
+    RpcServer::new(Machine::default(), chain_id).serve(addr).await
+}
@@ -0,0 +1,206 @@
+use crate::evm::{Inspector, Machine};
+use crate::tracer::StructLog;
+use ruint::aliases::U256;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"EVMT";
+const VERSION: u8 = 1;
+// DUP16 and SWAP16 reach deepest, no instruction touches anything below this
+const MAX_TOUCHED_DEPTH: usize = 17;
+const FRAME_ENDED: u8 = 0x01;
+
+// Binary trace that stores what changed instead of full snapshots. Each record is
+//   pc, opcode, gas delta (zigzag), gas cost, depth, flags, pops, pushes
+// with varints throughout and pushed words as a length byte plus their significant bytes.
+// A record is around a dozen bytes whatever the stack depth, where a JSON line grows with it
+#[derive(Debug)]
+pub struct BinaryTracer<W: Write> {
+    writer: W,
+    gas: u64,
+    pending: Option<PendingStep>,
+    error: Option<io::Error>,
+}
+
+#[derive(Debug)]
+struct PendingStep {
+    pc: usize,
+    op: u8,
+    gas: u64,
+    depth: usize,
+    stack_len: usize,
+    // the top MAX_TOUCHED_DEPTH items before the step, to diff against afterwards
+    stack_top: Vec<U256>,
+}
+
+impl<W: Write> BinaryTracer<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self { writer, gas: 0, pending: None, error: None })
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write(&mut self, step: PendingStep, gas_cost: u64, machine: &Machine) -> io::Result<()> {
+        // a frame that ended leaves only the success flag pushed onto its caller
+        let (flags, pops, pushes) = match machine.call_stack.get(step.depth - 1) {
+            Some(frame) => {
+                let start = step.stack_len - step.stack_top.len();
+                let mut common = start.min(frame.stack.len());
+                while common < step.stack_len && common < frame.stack.len() && frame.stack[common] == step.stack_top[common - start] {
+                    common += 1;
+                }
+                (0, step.stack_len - common, &frame.stack[common..])
+            }
+            None => (FRAME_ENDED, 0, machine.call_stack.last().map_or(&[][..], |frame| &frame.stack[frame.stack.len().saturating_sub(1)..])),
+        };
+
+        write_varint(&mut self.writer, step.pc as u64)?;
+        self.writer.write_all(&[step.op])?;
+        write_varint(&mut self.writer, zigzag(step.gas.wrapping_sub(self.gas) as i64))?;
+        write_varint(&mut self.writer, gas_cost)?;
+        write_varint(&mut self.writer, step.depth as u64)?;
+        self.writer.write_all(&[flags])?;
+        write_varint(&mut self.writer, pops as u64)?;
+        write_varint(&mut self.writer, pushes.len() as u64)?;
+        for value in pushes {
+            let bytes = value.to_be_bytes::<32>();
+            let significant = &bytes[value.leading_zeros() / 8..];
+            self.writer.write_all(&[significant.len() as u8])?;
+            self.writer.write_all(significant)?;
+        }
+        self.gas = step.gas;
+        Ok(())
+    }
+}
+
+impl<W: Write> Inspector for BinaryTracer<W> {
+    fn step(&mut self, machine: &Machine) {
+        if self.error.is_some() {
+            return;
+        }
+        let frame = machine.call_stack.last().unwrap();
+        self.pending = Some(PendingStep {
+            pc: frame.pc,
+            op: frame.code.get(frame.pc).copied().unwrap_or(0x00),
+            gas: frame.gas,
+            depth: machine.call_stack.len(),
+            stack_len: frame.stack.len(),
+            stack_top: frame.stack[frame.stack.len().saturating_sub(MAX_TOUCHED_DEPTH)..].to_vec(),
+        });
+    }
+
+    fn step_end(&mut self, machine: &Machine, gas_cost: u64) {
+        if let Some(step) = self.pending.take() {
+            self.error = self.write(step, gas_cost, machine).err();
+        }
+    }
+}
+
+// Replays a binary trace into struct logs one step at a time, rebuilding every frame's stack
+// from the recorded pops and pushes. Nothing is decoded before it is asked for
+#[derive(Debug)]
+pub struct TraceReader<R: Read> {
+    reader: R,
+    gas: u64,
+    frames: Vec<Vec<U256>>,
+}
+
+impl<R: Read> TraceReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a binary trace"));
+        }
+        Ok(Self { reader, gas: 0, frames: Vec::new() })
+    }
+
+    fn read_step(&mut self, pc: u64) -> io::Result<StructLog> {
+        let op = read_byte(&mut self.reader)?;
+        self.gas = self.gas.wrapping_add(unzigzag(read_varint(&mut self.reader)?) as u64);
+        let gas_cost = read_varint(&mut self.reader)?;
+        let depth = read_varint(&mut self.reader)? as usize;
+        let flags = read_byte(&mut self.reader)?;
+        let pops = read_varint(&mut self.reader)? as usize;
+        let mut pushes = Vec::new();
+        for _ in 0..read_varint(&mut self.reader)? {
+            let mut bytes = [0u8; 32];
+            let len = read_byte(&mut self.reader)? as usize;
+            if len > 32 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "pushed word longer than 32 bytes"));
+            }
+            self.reader.read_exact(&mut bytes[32 - len..])?;
+            pushes.push(U256::from_be_bytes(bytes));
+        }
+        if depth == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "step at depth 0"));
+        }
+
+        // frames entered since the last step start out empty
+        self.frames.resize(depth, Vec::new());
+        let log = StructLog { pc: pc as usize, op, gas: self.gas, gas_cost, depth, stack: self.frames[depth - 1].clone() };
+
+        if flags & FRAME_ENDED != 0 {
+            self.frames.pop();
+        }
+        if let Some(stack) = self.frames.last_mut() {
+            stack.truncate(stack.len().saturating_sub(pops));
+            stack.extend(pushes);
+        }
+        Ok(log)
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = io::Result<StructLog>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // running out of input is only clean between records
+        let first = match read_byte(&mut self.reader) {
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return None,
+            first => first,
+        };
+        Some(first.and_then(|first| read_varint(&mut [first].as_slice().chain(&mut self.reader))).and_then(|pc| self.read_step(pc)))
+    }
+}
+
+fn write_varint(writer: &mut impl Write, mut value: u64) -> io::Result<()> {
+    while value >= 0x80 {
+        writer.write_all(&[value as u8 | 0x80])?;
+        value >>= 7;
+    }
+    writer.write_all(&[value as u8])
+}
+
+fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = read_byte(reader)?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "varint longer than 64 bits"))
+}
+
+fn read_byte(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
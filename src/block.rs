@@ -0,0 +1,121 @@
+use crate::evm::{Machine, Transaction, TransactionError};
+use crate::receipt::Receipt;
+use alloy::primitives::{Address, B256};
+use ruint::aliases::U256;
+
+// EIP-4844 parameters as of Cancun
+pub const BLOB_GAS_PER_BLOB: u64 = 1 << 17;
+pub const MAX_BLOBS_PER_TRANSACTION: usize = 6;
+pub const TARGET_BLOB_GAS_PER_BLOCK: u64 = 3 * BLOB_GAS_PER_BLOB;
+pub const MAX_BLOB_GAS_PER_BLOCK: u64 = 6 * BLOB_GAS_PER_BLOB;
+const MIN_BLOB_BASE_FEE: u64 = 1;
+const BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 3_338_477;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Header {
+    pub parent_hash: B256,
+    pub number: u64,
+    pub timestamp: u64,
+    pub coinbase: Address,
+    pub gas_limit: u64,
+    pub base_fee: U256,
+    pub excess_blob_gas: u64,
+}
+
+impl Header {
+    // Hash of the consensus header with these fields set and every root left empty.
+    // Stable and parent-linked, but not comparable to real chain hashes
+    pub fn hash(&self) -> B256 {
+        alloy::consensus::Header {
+            parent_hash: self.parent_hash,
+            beneficiary: self.coinbase,
+            number: self.number,
+            gas_limit: self.gas_limit,
+            timestamp: self.timestamp,
+            base_fee_per_gas: Some(self.base_fee.saturating_to()),
+            ..Default::default()
+        }.hash_slow()
+    }
+
+    pub fn blob_base_fee(&self) -> U256 {
+        fake_exponential(U256::from(MIN_BLOB_BASE_FEE), U256::from(self.excess_blob_gas), U256::from(BLOB_BASE_FEE_UPDATE_FRACTION))
+    }
+
+    // Excess blob gas of the block built on top of this one
+    pub fn next_excess_blob_gas(&self, blob_gas_used: u64) -> u64 {
+        (self.excess_blob_gas + blob_gas_used).saturating_sub(TARGET_BLOB_GAS_PER_BLOCK)
+    }
+}
+
+// factor * e^(numerator / denominator), approximated with the integer Taylor expansion from EIP-4844
+fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    let mut output = U256::ZERO;
+    let mut accum = factor * denominator;
+    let mut i = U256::from(1);
+    while !accum.is_zero() {
+        output += accum;
+        accum = accum * numerator / (denominator * i);
+        i += U256::from(1);
+    }
+    output / denominator
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Block {
+    pub header: Header,
+    pub transactions: Vec<Transaction>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockOutcome {
+    pub receipts: Vec<Receipt>,
+    pub gas_used: u64,
+    pub blob_gas_used: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BlockError {
+    GasLimitExceeded { index: usize, gas_limit: u64, gas_available: u64 },
+    BlobGasLimitExceeded { index: usize, blob_gas: u64, blob_gas_available: u64 },
+    InvalidTransaction { index: usize, error: TransactionError },
+}
+
+impl Machine {
+    // Applies the transactions in order under post-merge rules: no block reward, the coinbase
+    // earns the priority fees. An invalid transaction rejects the whole block and leaves
+    // the state untouched; reverted transactions are fine and just get a failed receipt
+    pub fn execute_block(&mut self, block: &Block) -> Result<BlockOutcome, BlockError> {
+        let snapshot = self.accounts.clone();
+        let previous_header = std::mem::replace(&mut self.block, block.header.clone());
+
+        let result = self.apply_transactions(&block.transactions);
+        if result.is_err() {
+            self.accounts = snapshot;
+            self.block = previous_header;
+        }
+        result
+    }
+
+    fn apply_transactions(&mut self, transactions: &[Transaction]) -> Result<BlockOutcome, BlockError> {
+        let mut receipts = Vec::with_capacity(transactions.len());
+        let (mut gas_used, mut blob_gas_used) = (0, 0);
+
+        for (index, tx) in transactions.iter().enumerate() {
+            let gas_available = self.block.gas_limit - gas_used;
+            if tx.gas_limit > gas_available {
+                return Err(BlockError::GasLimitExceeded { index, gas_limit: tx.gas_limit, gas_available });
+            }
+            let blob_gas_available = MAX_BLOB_GAS_PER_BLOCK - blob_gas_used;
+            if tx.blob_gas() > blob_gas_available {
+                return Err(BlockError::BlobGasLimitExceeded { index, blob_gas: tx.blob_gas(), blob_gas_available });
+            }
+
+            let outcome = self.transact(tx).map_err(|error| BlockError::InvalidTransaction { index, error })?;
+            receipts.push(outcome.receipt(gas_used));
+            gas_used += outcome.gas_used;
+            blob_gas_used += tx.blob_gas();
+        }
+
+        Ok(BlockOutcome { receipts, gas_used, blob_gas_used })
+    }
+}
@@ -0,0 +1,180 @@
+use crate::opcode::Opcode;
+use alloy::primitives::Address;
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    UndefinedLabel(String),
+    DuplicateLabel(String),
+    // a label past what the PUSH2 in front of a jump can address
+    LabelOutOfRange(String),
+}
+
+// Operands of a CALL, pushed in the order the instruction pops them
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CallParams {
+    pub gas: u64,
+    pub to: Address,
+    pub value: U256,
+    pub args_offset: usize,
+    pub args_size: usize,
+    pub ret_offset: usize,
+    pub ret_size: usize,
+}
+
+// Emits code instruction by instruction. Jumps name their target and are patched by `build`,
+// so a label can be used before the JUMPDEST that defines it
+#[derive(Debug, Clone, Default)]
+pub struct BytecodeBuilder {
+    code: Vec<u8>,
+    labels: HashMap<String, usize>,
+    // pc of each PUSH2 immediate waiting for a label
+    fixups: Vec<(usize, String)>,
+    duplicate: Option<String>,
+}
+
+macro_rules! simple_ops {
+    ($($name:ident => $opcode:ident),* $(,)?) => {
+        impl BytecodeBuilder {
+            $(
+                pub fn $name(&mut self) -> &mut Self {
+                    self.op(Opcode::$opcode)
+                }
+            )*
+        }
+    };
+}
+
+simple_ops! {
+    stop => Stop,
+    add => Add,
+    mul => Mul,
+    sub => Sub,
+    div => Div,
+    lt => Lt,
+    gt => Gt,
+    eq => Eq,
+    iszero => IsZero,
+    sha3 => Sha3,
+    caller => Caller,
+    calldataload => CallDataLoad,
+    returndatasize => ReturnDataSize,
+    returndatacopy => ReturnDataCopy,
+    pop => Pop,
+    mload => MLoad,
+    mstore => MStore,
+    sload => SLoad,
+    sstore => SStore,
+    tload => TLoad,
+    tstore => TStore,
+    ret => Return,
+    revert => Revert,
+    invalid => Invalid,
+}
+
+impl BytecodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    // Any opcode without immediate data; use `push` for PUSHn
+    pub fn op(&mut self, opcode: Opcode) -> &mut Self {
+        assert!(!opcode.is_push(), "{} takes its data through push", opcode);
+        self.code.push(opcode as u8);
+        self
+    }
+
+    // The shortest PUSHn holding `value`; zero still takes a PUSH1
+    pub fn push(&mut self, value: U256) -> &mut Self {
+        let bytes = value.to_be_bytes::<32>();
+        let size = (32 - value.leading_zeros() / 8).max(1);
+        self.code.push(Opcode::Push1 as u8 + size as u8 - 1);
+        self.code.extend_from_slice(&bytes[32 - size..]);
+        self
+    }
+
+    pub fn push_address(&mut self, address: Address) -> &mut Self {
+        self.push(U256::from_be_slice(address.as_slice()))
+    }
+
+    // DUP1..DUP16
+    pub fn dup(&mut self, n: u8) -> &mut Self {
+        assert!((1..=16).contains(&n), "DUP{} does not exist", n);
+        self.code.push(Opcode::Dup1 as u8 + n - 1);
+        self
+    }
+
+    // SWAP1..SWAP16
+    pub fn swap(&mut self, n: u8) -> &mut Self {
+        assert!((1..=16).contains(&n), "SWAP{} does not exist", n);
+        self.code.push(Opcode::Swap1 as u8 + n - 1);
+        self
+    }
+
+    // LOG0..LOG4, with offset, size and the topics already on the stack
+    pub fn log(&mut self, topics: u8) -> &mut Self {
+        assert!(topics <= 4, "LOG{} does not exist", topics);
+        self.code.push(Opcode::Log0 as u8 + topics);
+        self
+    }
+
+    // Defines `label` here and emits its JUMPDEST
+    pub fn jumpdest(&mut self, label: &str) -> &mut Self {
+        if self.labels.insert(label.to_string(), self.code.len()).is_some() && self.duplicate.is_none() {
+            self.duplicate = Some(label.to_string());
+        }
+        self.op(Opcode::JumpDest)
+    }
+
+    // The label's pc as a PUSH2, for computed jumps
+    pub fn push_label(&mut self, label: &str) -> &mut Self {
+        self.code.push(Opcode::Push2 as u8);
+        self.fixups.push((self.code.len(), label.to_string()));
+        self.code.extend_from_slice(&[0, 0]);
+        self
+    }
+
+    pub fn jump(&mut self, label: &str) -> &mut Self {
+        self.push_label(label).op(Opcode::Jump)
+    }
+
+    // Jumps to `label` when the top of the stack is nonzero
+    pub fn jumpi(&mut self, label: &str) -> &mut Self {
+        self.push_label(label).op(Opcode::JumpI)
+    }
+
+    // CALL with every operand pushed as a constant; leaves the success flag on the stack
+    pub fn call(&mut self, params: CallParams) -> &mut Self {
+        self.push(U256::from(params.ret_size))
+            .push(U256::from(params.ret_offset))
+            .push(U256::from(params.args_size))
+            .push(U256::from(params.args_offset))
+            .push(params.value)
+            .push_address(params.to)
+            .push(U256::from(params.gas))
+            .op(Opcode::Call)
+    }
+
+    // The code with every jump pointing at its label
+    pub fn build(&self) -> Result<Vec<u8>, BuildError> {
+        if let Some(label) = &self.duplicate {
+            return Err(BuildError::DuplicateLabel(label.clone()));
+        }
+        let mut code = self.code.clone();
+        for (at, label) in &self.fixups {
+            let &target = self.labels.get(label).ok_or_else(|| BuildError::UndefinedLabel(label.clone()))?;
+            let target = u16::try_from(target).map_err(|_| BuildError::LabelOutOfRange(label.clone()))?;
+            code[*at..*at + 2].copy_from_slice(&target.to_be_bytes());
+        }
+        Ok(code)
+    }
+}
@@ -0,0 +1,66 @@
+use crate::evm::{Account, Machine, Transaction, TransactionError, TransactionOutcome};
+use alloy::primitives::Address;
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountDiff {
+    pub balance: Option<Change<U256>>,
+    pub nonce: Option<Change<u64>>,
+    pub storage: HashMap<U256, Change<U256>>,
+}
+
+pub type StateDiff = HashMap<Address, AccountDiff>;
+
+#[derive(Debug, PartialEq)]
+pub struct BundleOutcome {
+    // invalid transactions are skipped and the rest of the bundle still runs
+    pub results: Vec<Result<TransactionOutcome, TransactionError>>,
+    pub state_diff: StateDiff,
+}
+
+impl Machine {
+    // Executes the transactions in order on top of the current state, each one seeing the
+    // previous ones' writes, then rolls everything back
+    pub fn simulate_bundle(&mut self, transactions: &[Transaction]) -> Result<BundleOutcome, TransactionError> {
+        let snapshot = self.accounts.clone();
+        let results = transactions.iter().map(|tx| self.transact(tx)).collect();
+        let after = std::mem::replace(&mut self.accounts, snapshot);
+
+        let state_diff = self.diff_against(&after).map_err(TransactionError::HostError)?;
+        Ok(BundleOutcome { results, state_diff })
+    }
+
+    // Compares `after` with the current state, loading whatever the current state lacks
+    fn diff_against(&mut self, after: &HashMap<Address, Account>) -> Result<StateDiff, String> {
+        let mut state_diff = StateDiff::new();
+        for (address, account) in after {
+            let before = self.account(*address)?;
+            let mut diff = AccountDiff {
+                balance: changed(before.balance, account.balance),
+                nonce: changed(before.nonce, account.nonce),
+                ..Default::default()
+            };
+            for (key, value) in &account.storage {
+                if let Some(change) = changed(self.storage(*address, *key)?, *value) {
+                    diff.storage.insert(*key, change);
+                }
+            }
+
+            if diff != AccountDiff::default() {
+                state_diff.insert(*address, diff);
+            }
+        }
+        Ok(state_diff)
+    }
+}
+
+fn changed<T: PartialEq>(before: T, after: T) -> Option<Change<T>> {
+    (before != after).then_some(Change { before, after })
+}
@@ -0,0 +1,124 @@
+use crate::opcode::{decode, Opcode};
+use crate::tracer::opcode_name;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub start: usize,
+    // one past the last byte, immediates included
+    pub end: usize,
+    // pc and opcode of every instruction, with PUSH immediates
+    pub instructions: Vec<(usize, u8, Vec<u8>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    Jump,
+    // JUMPI with a nonzero condition
+    Taken,
+    Fallthrough,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+// Basic blocks keyed by their first pc. Jump targets are resolved when a PUSH feeds the jump
+// directly, which is how solc emits static jumps; the rest land in `dynamic_jumps`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cfg {
+    pub blocks: BTreeMap<usize, BasicBlock>,
+    pub edges: Vec<Edge>,
+    // pcs of jumps whose target comes from computation
+    pub dynamic_jumps: Vec<usize>,
+    // pcs of static jumps that do not land on a JUMPDEST
+    pub invalid_jumps: Vec<usize>,
+}
+
+impl Cfg {
+    pub fn build(code: &[u8]) -> Self {
+        let mut cfg = Cfg::default();
+        let mut block = BasicBlock { start: 0, end: 0, instructions: Vec::new() };
+        for instruction in decode(code) {
+            let opcode = instruction.opcode;
+            if opcode == Some(Opcode::JumpDest) && !block.instructions.is_empty() {
+                let next = BasicBlock { start: instruction.pc, end: instruction.pc, instructions: Vec::new() };
+                cfg.blocks.insert(block.start, std::mem::replace(&mut block, next));
+            }
+            block.instructions.push((instruction.pc, instruction.byte, instruction.immediate.to_vec()));
+            let pc = instruction.pc + instruction.size();
+            block.end = pc.min(code.len());
+
+            if opcode.is_some_and(|op| op.is_terminator() || op == Opcode::JumpI) {
+                let next = BasicBlock { start: pc, end: pc, instructions: Vec::new() };
+                cfg.blocks.insert(block.start, std::mem::replace(&mut block, next));
+            }
+        }
+        if !block.instructions.is_empty() {
+            cfg.blocks.insert(block.start, block);
+        }
+
+        let starts: Vec<usize> = cfg.blocks.keys().copied().collect();
+        for (index, block) in cfg.blocks.values().enumerate() {
+            let &(pc, byte, _) = block.instructions.last().unwrap();
+            let op = Opcode::try_from(byte).ok();
+            if let Some(op @ (Opcode::Jump | Opcode::JumpI)) = op {
+                let kind = if op == Opcode::Jump { EdgeKind::Jump } else { EdgeKind::Taken };
+                match static_target(&block.instructions) {
+                    Some(target) if code.get(target) == Some(&(Opcode::JumpDest as u8)) => cfg.edges.push(Edge { from: block.start, to: target, kind }),
+                    Some(_) => cfg.invalid_jumps.push(pc),
+                    None => cfg.dynamic_jumps.push(pc),
+                }
+            }
+            if !op.is_some_and(Opcode::is_terminator) && let Some(&next) = starts.get(index + 1) {
+                cfg.edges.push(Edge { from: block.start, to: next, kind: EdgeKind::Fallthrough });
+            }
+        }
+        cfg
+    }
+
+    // Graphviz source, one box per block with its disassembly
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cfg {\n    node [shape=box fontname=monospace];\n");
+        for block in self.blocks.values() {
+            let mut label = String::new();
+            for (pc, op, immediate) in &block.instructions {
+                if immediate.is_empty() {
+                    write!(label, "{}: {}\\l", pc, opcode_name(*op)).unwrap();
+                } else {
+                    write!(label, "{}: {} 0x{}\\l", pc, opcode_name(*op), hex::encode(immediate)).unwrap();
+                }
+            }
+            writeln!(dot, "    b{} [label=\"{}\"];", block.start, label).unwrap();
+        }
+        for edge in &self.edges {
+            let label = match edge.kind {
+                EdgeKind::Jump => "jump",
+                EdgeKind::Taken => "true",
+                EdgeKind::Fallthrough => "",
+            };
+            writeln!(dot, "    b{} -> b{} [label=\"{}\"];", edge.from, edge.to, label).unwrap();
+        }
+        for pc in &self.dynamic_jumps {
+            let block = self.blocks.range(..=pc).next_back().unwrap().0;
+            writeln!(dot, "    dynamic{} [label=\"?\" shape=circle];\n    b{} -> dynamic{} [style=dashed];", pc, block, pc).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// The target of a jump whose destination was pushed right before it
+fn static_target(instructions: &[(usize, u8, Vec<u8>)]) -> Option<usize> {
+    let [.., (_, push, immediate), _] = instructions else {
+        return None;
+    };
+    if !Opcode::try_from(*push).is_ok_and(Opcode::is_push) || immediate.len() > 8 {
+        return None;
+    }
+    Some(immediate.iter().fold(0usize, |target, byte| target << 8 | *byte as usize))
+}
@@ -0,0 +1,114 @@
+use crate::block::{Block, BlockError, Header};
+use crate::evm::{Machine, Transaction};
+use crate::receipt::Receipt;
+use alloy::primitives::{Address, B256};
+use ruint::aliases::U256;
+
+const BLOCKHASH_WINDOW: u64 = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinedBlock {
+    pub header: Header,
+    pub hash: B256,
+    pub transactions: Vec<Transaction>,
+    pub receipts: Vec<Receipt>,
+    pub gas_used: u64,
+    pub blob_gas_used: u64,
+}
+
+// In-memory chain that mines a block whenever asked. Numbers and timestamps advance on their
+// own and every header links to its parent's hash, which also feeds BLOCKHASH
+#[derive(Debug)]
+pub struct SimpleChain {
+    pub machine: Machine,
+    pub blocks: Vec<MinedBlock>,
+    pub block_time: u64,
+    pub gas_limit: u64,
+    pub coinbase: Address,
+    pub base_fee: U256,
+    time_offset: u64,
+}
+
+impl SimpleChain {
+    pub fn new(machine: Machine) -> Self {
+        Self::with_genesis(machine, Header { gas_limit: 30_000_000, ..Default::default() })
+    }
+
+    pub fn with_genesis(mut machine: Machine, genesis: Header) -> Self {
+        let hash = genesis.hash();
+        machine.block = genesis.clone();
+        machine.block_hashes.insert(genesis.number, hash);
+
+        Self {
+            machine,
+            block_time: 12,
+            gas_limit: genesis.gas_limit,
+            coinbase: genesis.coinbase,
+            base_fee: genesis.base_fee,
+            blocks: vec![MinedBlock {
+                header: genesis,
+                hash,
+                transactions: Vec::new(),
+                receipts: Vec::new(),
+                gas_used: 0,
+                blob_gas_used: 0,
+            }],
+            time_offset: 0,
+        }
+    }
+
+    pub fn latest(&self) -> &MinedBlock {
+        self.blocks.last().unwrap()
+    }
+
+    pub fn block(&self, number: u64) -> Option<&MinedBlock> {
+        let first = self.blocks[0].header.number;
+        self.blocks.get(number.checked_sub(first)? as usize)
+    }
+
+    // Pushes the timestamp of the next mined block forward, for time-locked contracts
+    pub fn advance_time(&mut self, seconds: u64) {
+        self.time_offset += seconds;
+    }
+
+    // Mines the transactions into the next block. A rejected block leaves the chain as it was
+    pub fn mine(&mut self, transactions: Vec<Transaction>) -> Result<&MinedBlock, BlockError> {
+        let parent = &self.latest().header;
+        let excess_blob_gas = parent.next_excess_blob_gas(self.latest().blob_gas_used);
+        let header = Header {
+            parent_hash: self.latest().hash,
+            number: parent.number + 1,
+            timestamp: parent.timestamp + self.block_time + self.time_offset,
+            coinbase: self.coinbase,
+            gas_limit: self.gas_limit,
+            base_fee: self.base_fee,
+            excess_blob_gas,
+        };
+        let block = Block { header, transactions };
+        let outcome = self.machine.execute_block(&block)?;
+
+        let hash = block.header.hash();
+        let number = block.header.number;
+        self.machine.block_hashes.insert(number, hash);
+        if let Some(expired) = number.checked_sub(BLOCKHASH_WINDOW) {
+            self.machine.block_hashes.remove(&expired);
+        }
+        self.time_offset = 0;
+
+        self.blocks.push(MinedBlock {
+            header: block.header,
+            hash,
+            transactions: block.transactions,
+            receipts: outcome.receipts,
+            gas_used: outcome.gas_used,
+            blob_gas_used: outcome.blob_gas_used,
+        });
+        Ok(self.latest())
+    }
+
+    pub fn mine_empty(&mut self, count: u64) {
+        for _ in 0..count {
+            self.mine(Vec::new()).unwrap();
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use crate::builder::BytecodeBuilder;
+use ruint::aliases::U256;
+
+// A tiny language compiled two ways, to EVM bytecode and to a native closure, so the same
+// computation can be timed on both sides. Values are 256-bit words with EVM semantics: arithmetic
+// wraps, division by zero gives zero and comparisons give 0 or 1
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Lt,
+    Gt,
+    Eq,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Const(U256),
+    // variables are numbered; the first `Program::inputs` hold the inputs, the rest start at zero
+    Var(usize),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    // 1 for zero, 0 for anything else
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Assign(usize, Expr),
+    If(Expr, Vec<Stmt>),
+    // runs while the condition is nonzero; a loop that never ends hangs the native side
+    While(Expr, Vec<Stmt>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub inputs: usize,
+    pub body: Vec<Stmt>,
+    pub result: Expr,
+}
+
+pub type NativeFn = Box<dyn Fn(&[U256]) -> U256>;
+
+type ExprFn = Box<dyn Fn(&[U256]) -> U256>;
+type StmtFn = Box<dyn Fn(&mut [U256])>;
+
+impl Expr {
+    pub fn constant(value: u64) -> Self {
+        Expr::Const(U256::from(value))
+    }
+
+    pub fn binary(op: BinOp, a: Expr, b: Expr) -> Self {
+        Expr::Binary(op, Box::new(a), Box::new(b))
+    }
+
+    fn max_var(&self) -> Option<usize> {
+        match self {
+            Expr::Const(_) => None,
+            Expr::Var(var) => Some(*var),
+            Expr::Binary(_, a, b) => a.max_var().max(b.max_var()),
+            Expr::Not(a) => a.max_var(),
+        }
+    }
+
+    // Leaves the value on the stack
+    fn emit(&self, code: &mut BytecodeBuilder) {
+        match self {
+            Expr::Const(value) => {
+                code.push(*value);
+            }
+            Expr::Var(var) => {
+                code.push(slot(*var)).mload();
+            }
+            Expr::Binary(op, a, b) => {
+                // a ends up below b, which is the operand order SUB, DIV, LT and GT read
+                a.emit(code);
+                b.emit(code);
+                match op {
+                    BinOp::Add => code.add(),
+                    BinOp::Sub => code.sub(),
+                    BinOp::Mul => code.mul(),
+                    BinOp::Div => code.div(),
+                    BinOp::Lt => code.lt(),
+                    BinOp::Gt => code.gt(),
+                    BinOp::Eq => code.eq(),
+                };
+            }
+            Expr::Not(a) => {
+                a.emit(code);
+                code.iszero();
+            }
+        }
+    }
+
+    fn native(&self) -> ExprFn {
+        match self {
+            Expr::Const(value) => {
+                let value = *value;
+                Box::new(move |_| value)
+            }
+            Expr::Var(var) => {
+                let var = *var;
+                Box::new(move |vars| vars[var])
+            }
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.native(), b.native());
+                let flag = |condition: bool| if condition { U256::from(1) } else { U256::ZERO };
+                match op {
+                    BinOp::Add => Box::new(move |vars| a(vars).wrapping_add(b(vars))),
+                    BinOp::Sub => Box::new(move |vars| a(vars).wrapping_sub(b(vars))),
+                    BinOp::Mul => Box::new(move |vars| a(vars).wrapping_mul(b(vars))),
+                    BinOp::Div => Box::new(move |vars| a(vars).checked_div(b(vars)).unwrap_or_default()),
+                    BinOp::Lt => Box::new(move |vars| flag(a(vars) < b(vars))),
+                    BinOp::Gt => Box::new(move |vars| flag(a(vars) > b(vars))),
+                    BinOp::Eq => Box::new(move |vars| flag(a(vars) == b(vars))),
+                }
+            }
+            Expr::Not(a) => {
+                let a = a.native();
+                Box::new(move |vars| if a(vars).is_zero() { U256::from(1) } else { U256::ZERO })
+            }
+        }
+    }
+}
+
+impl Stmt {
+    fn max_var(&self) -> Option<usize> {
+        match self {
+            Stmt::Assign(var, value) => Some(*var).max(value.max_var()),
+            Stmt::If(condition, body) | Stmt::While(condition, body) => body.iter().map(Stmt::max_var).fold(condition.max_var(), Option::max),
+        }
+    }
+
+    fn emit(&self, code: &mut BytecodeBuilder, labels: &mut usize) {
+        match self {
+            Stmt::Assign(var, value) => {
+                value.emit(code);
+                code.push(slot(*var)).mstore();
+            }
+            Stmt::If(condition, body) => {
+                *labels += 1;
+                let end = format!("end{}", labels);
+                condition.emit(code);
+                code.iszero().jumpi(&end);
+                body.iter().for_each(|stmt| stmt.emit(code, labels));
+                code.jumpdest(&end);
+            }
+            Stmt::While(condition, body) => {
+                *labels += 1;
+                let (start, end) = (format!("loop{}", labels), format!("end{}", labels));
+                code.jumpdest(&start);
+                condition.emit(code);
+                code.iszero().jumpi(&end);
+                body.iter().for_each(|stmt| stmt.emit(code, labels));
+                code.jump(&start).jumpdest(&end);
+            }
+        }
+    }
+
+    fn native(&self) -> StmtFn {
+        match self {
+            Stmt::Assign(var, value) => {
+                let (var, value) = (*var, value.native());
+                Box::new(move |vars| vars[var] = value(vars))
+            }
+            Stmt::If(condition, body) => {
+                let (condition, body) = (condition.native(), native_block(body));
+                Box::new(move |vars| {
+                    if !condition(vars).is_zero() {
+                        body(vars);
+                    }
+                })
+            }
+            Stmt::While(condition, body) => {
+                let (condition, body) = (condition.native(), native_block(body));
+                Box::new(move |vars| {
+                    while !condition(vars).is_zero() {
+                        body(vars);
+                    }
+                })
+            }
+        }
+    }
+}
+
+fn native_block(body: &[Stmt]) -> StmtFn {
+    let stmts: Vec<StmtFn> = body.iter().map(Stmt::native).collect();
+    Box::new(move |vars| stmts.iter().for_each(|stmt| stmt(vars)))
+}
+
+// Every variable lives in its own memory word
+fn slot(var: usize) -> U256 {
+    U256::from(var * 32)
+}
+
+impl Program {
+    // Inputs plus every variable the program touches
+    pub fn variables(&self) -> usize {
+        let used = self.body.iter().map(Stmt::max_var).fold(self.result.max_var(), Option::max);
+        used.map_or(0, |var| var + 1).max(self.inputs)
+    }
+
+    // Inputs as the bytecode reads them, one word each
+    pub fn calldata(inputs: &[U256]) -> Vec<u8> {
+        inputs.iter().flat_map(|input| input.to_be_bytes::<32>()).collect()
+    }
+
+    // Copies the inputs from calldata into memory, runs the body and returns the result as one word
+    pub fn to_bytecode(&self) -> Vec<u8> {
+        let mut code = BytecodeBuilder::new();
+        for var in 0..self.inputs {
+            code.push(slot(var)).calldataload().push(slot(var)).mstore();
+        }
+        let mut labels = 0;
+        self.body.iter().for_each(|stmt| stmt.emit(&mut code, &mut labels));
+        self.result.emit(&mut code);
+        code.push(U256::ZERO).mstore().push(U256::from(32)).push(U256::ZERO).ret();
+        code.build().expect("labels are generated")
+    }
+
+    // The same computation as a closure over the inputs; missing inputs read as zero
+    pub fn to_native(&self) -> NativeFn {
+        let (inputs, variables) = (self.inputs, self.variables());
+        let body = native_block(&self.body);
+        let result = self.result.native();
+        Box::new(move |values| {
+            let mut vars = vec![U256::ZERO; variables];
+            let count = values.len().min(inputs);
+            vars[..count].copy_from_slice(&values[..count]);
+            body(&mut vars);
+            result(&vars)
+        })
+    }
+
+    // A program from `seed` that always terminates: two or three inputs, a few assignments and
+    // conditionals, and a loop counting a dedicated variable down from a small constant
+    pub fn random(seed: u64) -> Self {
+        let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1);
+        let inputs = 2 + rng.below(2);
+        let counter = inputs + 2;
+        let mut generator = Generator { rng, variables: counter };
+
+        let mut body = vec![Stmt::Assign(counter, Expr::constant(1 + generator.rng.below(8) as u64))];
+        body.extend((0..1 + generator.rng.below(3)).map(|_| generator.stmt()));
+        let mut loop_body: Vec<Stmt> = (0..1 + generator.rng.below(3)).map(|_| generator.stmt()).collect();
+        loop_body.push(Stmt::Assign(counter, Expr::binary(BinOp::Sub, Expr::Var(counter), Expr::constant(1))));
+        body.push(Stmt::While(Expr::Var(counter), loop_body));
+        let result = generator.expr(3);
+        Program { inputs, body, result }
+    }
+}
+
+// xorshift64*, enough to spread programs around without a dependency
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+struct Generator {
+    rng: Rng,
+    // variables below this one may be read and written; the loop counter sits right above
+    variables: usize,
+}
+
+impl Generator {
+    fn expr(&mut self, depth: usize) -> Expr {
+        const OPS: [BinOp; 7] = [BinOp::Add, BinOp::Sub, BinOp::Mul, BinOp::Div, BinOp::Lt, BinOp::Gt, BinOp::Eq];
+        match self.rng.below(if depth == 0 { 2 } else { 5 }) {
+            0 => Expr::constant(self.rng.next() % 1000),
+            1 => Expr::Var(self.rng.below(self.variables)),
+            2 => Expr::Not(Box::new(self.expr(depth - 1))),
+            _ => Expr::binary(OPS[self.rng.below(OPS.len())], self.expr(depth - 1), self.expr(depth - 1)),
+        }
+    }
+
+    fn stmt(&mut self) -> Stmt {
+        let var = self.rng.below(self.variables);
+        if self.rng.below(4) == 0 {
+            Stmt::If(self.expr(2), vec![Stmt::Assign(var, self.expr(2))])
+        } else {
+            Stmt::Assign(var, self.expr(3))
+        }
+    }
+}
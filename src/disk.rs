@@ -0,0 +1,108 @@
+use crate::evm::{Account, Host};
+use alloy::primitives::Address;
+use ruint::aliases::U256;
+use std::path::Path;
+
+// Host backed by a sled database, for prestates too large to keep in a HashMap.
+// Accounts are stored as balance (32 bytes) || nonce (8 bytes), code separately so it is
+// only read when the account runs, storage slots under address || key. Clones share the same database
+#[derive(Debug, Clone)]
+pub struct DiskHost {
+    db: sled::Db,
+    accounts: sled::Tree,
+    code: sled::Tree,
+    storage: sled::Tree,
+}
+
+impl DiskHost {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        Self::from_db(sled::open(path).map_err(|e| e.to_string())?)
+    }
+
+    // Database in a temporary directory that is removed on drop
+    pub fn temporary() -> Result<Self, String> {
+        Self::from_db(sled::Config::new().temporary(true).open().map_err(|e| e.to_string())?)
+    }
+
+    fn from_db(db: sled::Db) -> Result<Self, String> {
+        let accounts = db.open_tree("accounts").map_err(|e| e.to_string())?;
+        let code = db.open_tree("code").map_err(|e| e.to_string())?;
+        let storage = db.open_tree("storage").map_err(|e| e.to_string())?;
+        Ok(Self { db, accounts, code, storage })
+    }
+
+    // Writes the accounts with the storage slots they hold, e.g. `machine.accounts` after
+    // execution. Slots set to zero are deleted. Slots an account doesn't hold, and code
+    // that was never loaded, are left as they are
+    pub fn commit<'a>(&self, accounts: impl IntoIterator<Item = (&'a Address, &'a Account)>) -> Result<(), String> {
+        let mut account_batch = sled::Batch::default();
+        let mut code_batch = sled::Batch::default();
+        let mut storage_batch = sled::Batch::default();
+
+        for (address, account) in accounts {
+            account_batch.insert(address.as_slice(), encode_account(account));
+            if !account.lazy_code {
+                code_batch.insert(address.as_slice(), account.code.as_slice());
+            }
+            for (key, value) in &account.storage {
+                let slot = storage_key(address, key);
+                if value.is_zero() {
+                    storage_batch.remove(slot.as_slice());
+                } else {
+                    storage_batch.insert(slot.as_slice(), value.to_be_bytes::<32>().as_slice());
+                }
+            }
+        }
+
+        self.accounts.apply_batch(account_batch).map_err(|e| e.to_string())?;
+        self.code.apply_batch(code_batch).map_err(|e| e.to_string())?;
+        self.storage.apply_batch(storage_batch).map_err(|e| e.to_string())
+    }
+
+    pub fn flush(&self) -> Result<(), String> {
+        self.db.flush().map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+impl Host for DiskHost {
+    fn basic(&mut self, address: Address) -> Result<Account, String> {
+        match self.accounts.get(address).map_err(|e| e.to_string())? {
+            Some(bytes) => decode_account(&bytes).ok_or_else(|| format!("corrupt account record for {}", address)),
+            None => Ok(Account::default()),
+        }
+    }
+
+    fn storage(&mut self, address: Address, key: U256) -> Result<U256, String> {
+        let value = self.storage.get(storage_key(&address, &key)).map_err(|e| e.to_string())?;
+        Ok(value.map(|bytes| U256::from_be_slice(&bytes)).unwrap_or_default())
+    }
+
+    fn code(&mut self, address: Address) -> Result<Vec<u8>, String> {
+        let code = self.code.get(address).map_err(|e| e.to_string())?;
+        Ok(code.map(|bytes| bytes.to_vec()).unwrap_or_default())
+    }
+}
+
+fn storage_key(address: &Address, key: &U256) -> [u8; 52] {
+    let mut slot = [0u8; 52];
+    slot[..20].copy_from_slice(address.as_slice());
+    slot[20..].copy_from_slice(&key.to_be_bytes::<32>());
+    slot
+}
+
+fn encode_account(account: &Account) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(40);
+    bytes.extend_from_slice(&account.balance.to_be_bytes::<32>());
+    bytes.extend_from_slice(&account.nonce.to_be_bytes());
+    bytes
+}
+
+fn decode_account(bytes: &[u8]) -> Option<Account> {
+    let nonce = bytes.get(32..40)?.try_into().ok()?;
+    Some(Account {
+        balance: U256::from_be_slice(&bytes[..32]),
+        nonce: u64::from_be_bytes(nonce),
+        lazy_code: true,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,138 @@
+use crate::evm::{ExecutionResult, Inspector, Machine};
+use crate::opcode::Opcode;
+use alloy::primitives::{Address, Log};
+use ruint::aliases::U256;
+use std::sync::mpsc::{Sender, SyncSender};
+
+// What an `EventStream` reports while a machine runs. Events own their data, so they can cross
+// threads and outlive the machine
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionEvent {
+    // depth counts from 1 for the outermost frame
+    FrameEntered { depth: usize, caller: Address, address: Address },
+    // frames still open when execution halts exceptionally exit unsuccessfully in `finish`
+    FrameExited { depth: usize, address: Address, success: bool },
+    LogEmitted(Log),
+    // every SSTORE that went through, including ones a revert later undoes
+    StorageWritten { address: Address, key: U256, value: U256 },
+    // total instructions so far, sent every `step_interval` of them
+    StepsExecuted(u64),
+    Finished(ExecutionResult),
+}
+
+// Where events go. A channel hands them to another thread; a bounded one makes execution wait
+// for a slow consumer. A receiver that went away just stops receiving
+pub trait EventSink {
+    fn emit(&mut self, event: ExecutionEvent);
+}
+
+impl EventSink for Sender<ExecutionEvent> {
+    fn emit(&mut self, event: ExecutionEvent) {
+        let _ = self.send(event);
+    }
+}
+
+impl EventSink for SyncSender<ExecutionEvent> {
+    fn emit(&mut self, event: ExecutionEvent) {
+        let _ = self.send(event);
+    }
+}
+
+impl<F: FnMut(ExecutionEvent)> EventSink for F {
+    fn emit(&mut self, event: ExecutionEvent) {
+        self(event)
+    }
+}
+
+// An inspector turning single steps into coarser events for a sink
+pub struct EventStream<S: EventSink> {
+    sink: S,
+    step_interval: u64,
+    steps: u64,
+    // addresses of the frames entered and not yet exited, outermost first
+    frames: Vec<Address>,
+    logs: usize,
+    // the SSTORE about to run and whether the instruction is a REVERT, read before the step
+    store: Option<(Address, U256, U256)>,
+    reverting: bool,
+}
+
+impl<S: EventSink> EventStream<S> {
+    pub fn new(sink: S) -> Self {
+        Self { sink, step_interval: 1000, steps: 0, frames: Vec::new(), logs: 0, store: None, reverting: false }
+    }
+
+    // 0 turns step counts off
+    pub fn with_step_interval(mut self, step_interval: u64) -> Self {
+        self.step_interval = step_interval;
+        self
+    }
+
+    fn enter_frames(&mut self, machine: &Machine) {
+        for frame in machine.call_stack.iter().skip(self.frames.len()) {
+            self.frames.push(frame.callee);
+            self.sink.emit(ExecutionEvent::FrameEntered { depth: self.frames.len(), caller: frame.caller, address: frame.callee });
+        }
+    }
+
+    // Closes the frames an exceptional halt left open and reports the result
+    pub fn finish(mut self, result: &ExecutionResult) -> S {
+        while let Some(address) = self.frames.pop() {
+            let depth = self.frames.len() + 1;
+            self.sink.emit(ExecutionEvent::FrameExited { depth, address, success: false });
+        }
+        self.sink.emit(ExecutionEvent::Finished(result.clone()));
+        self.sink
+    }
+}
+
+impl<S: EventSink> Inspector for EventStream<S> {
+    fn step(&mut self, machine: &Machine) {
+        // the outermost frame is already there on the first step
+        self.enter_frames(machine);
+        self.logs = machine.logs.len();
+
+        let frame = machine.call_stack.last().unwrap();
+        let top = |depth: usize| frame.stack.len().checked_sub(depth + 1).map(|i| frame.stack[i]);
+        let opcode = frame.code.get(frame.pc).and_then(|&byte| Opcode::try_from(byte).ok());
+        self.store = match (opcode, top(0), top(1)) {
+            (Some(Opcode::SStore), Some(key), Some(value)) => Some((frame.callee, key, value)),
+            _ => None,
+        };
+        self.reverting = opcode == Some(Opcode::Revert);
+    }
+
+    fn step_end(&mut self, machine: &Machine, _gas_cost: u64) {
+        self.steps += 1;
+
+        if let Some((address, key, value)) = self.store.take()
+            && machine.accounts.get(&address).and_then(|account| account.storage.get(&key)) == Some(&value)
+        {
+            self.sink.emit(ExecutionEvent::StorageWritten { address, key, value });
+        }
+        // a revert can drop logs, which were never new to begin with
+        for log in machine.logs.iter().skip(self.logs) {
+            self.sink.emit(ExecutionEvent::LogEmitted(log.clone()));
+        }
+
+        while self.frames.len() > machine.call_stack.len() {
+            let address = self.frames.pop().unwrap();
+            self.sink.emit(ExecutionEvent::FrameExited { depth: self.frames.len() + 1, address, success: !self.reverting });
+        }
+        self.enter_frames(machine);
+
+        if self.step_interval > 0 && self.steps.is_multiple_of(self.step_interval) {
+            self.sink.emit(ExecutionEvent::StepsExecuted(self.steps));
+        }
+    }
+}
+
+impl Machine {
+    // Runs a call with its events going to `sink`, ending with `Finished`
+    pub fn call_with_events<S: EventSink>(&mut self, caller: Address, to: Address, calldata: Vec<u8>, gas_limit: u64, sink: S) -> ExecutionResult {
+        let mut stream = EventStream::new(sink);
+        let result = self.call_with_inspector(caller, to, calldata, gas_limit, &mut stream);
+        stream.finish(&result);
+        result
+    }
+}
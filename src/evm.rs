@@ -1,58 +1,144 @@
+use crate::block::{Header, BLOB_GAS_PER_BLOB, MAX_BLOBS_PER_TRANSACTION};
+use crate::opcode::Opcode;
 use ruint::aliases::U256;
-use alloy::primitives::{keccak256, Address};
+use alloy::primitives::{keccak256, Address, Log, B256};
+use std::collections::hash_map::Entry;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt::Debug;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 use std::vec::Vec;
 
-const STOP: u8 = 0x00;
-const ADD: u8 = 0x01;
-const MUL: u8 = 0x02;
-const SUB: u8 = 0x03;
-const DIV: u8 = 0x04;
-const LT: u8 = 0x10;
-const GT: u8 = 0x11;
-const EQ: u8 = 0x14;
-const ISZERO: u8 = 0x15;
-const SHA3: u8 = 0x20;
-const CALLDATALOAD: u8 = 0x35;
-const MLOAD: u8 = 0x51;
-const MSTORE: u8 = 0x52;
-const POP: u8 = 0x50;
-const SLOAD: u8 = 0x54;
-const SSTORE: u8 = 0x55;
-const JUMP: u8 = 0x56;
-const JUMPI: u8 = 0x57;
-const JUMPDEST: u8 = 0x5b;
-const PUSH1: u8 = 0x60;
-const PUSH32: u8 = 0x7f;
-const DUP1: u8 = 0x80;
-const DUP16: u8 = 0x8f;
-const SWAP1: u8 = 0x90;
-const SWAP16: u8 = 0x9f;
-const CALL: u8 = 0xf1;
-const RETURNDATASIZE: u8 = 0x3d;
-const RETURNDATACOPY: u8 = 0x3e;
-const RETURN: u8 = 0xf3;
-const REVERT: u8 = 0xfd;
-
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ExecutionResult {
     Success(Vec<u8>),
     Revert(Vec<u8>),
     OutOfGas,
     InvalidOpcode,
     InvalidJump,
-    StackUnderflow
+    StackUnderflow,
+    StackOverflow,
+    Timeout,
+    HostError(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transaction {
+    pub caller: Address,
+    pub to: Address,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub gas_limit: u64,
+    // max fee per gas for EIP-1559 transactions
+    pub gas_price: U256,
+    pub gas_priority_fee: Option<U256>,
+    // `None` skips the nonce check, as eth_call does
+    pub nonce: Option<u64>,
+    pub access_list: Vec<(Address, Vec<U256>)>,
+    // EIP-4844: a transaction carrying blob hashes is a type-3 blob transaction
+    pub blob_versioned_hashes: Vec<B256>,
+    pub max_fee_per_blob_gas: U256,
+}
+
+impl Transaction {
+    pub fn blob_gas(&self) -> u64 {
+        self.blob_versioned_hashes.len() as u64 * BLOB_GAS_PER_BLOB
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, PartialEq)]
+pub struct TransactionOutcome {
+    pub result: ExecutionResult,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    // only filled in when `Machine::capture_final_frame` is set
+    pub final_frame: Option<FinalFrame>,
+    pub stats: ExecutionStats,
+}
+
+// What an execution used besides gas, to explain where its time went. Counted as it runs, for
+// every frame together
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExecutionStats {
+    pub steps: u64,
+    // most items a frame's stack held
+    pub max_stack_height: usize,
+    // most memory a frame expanded to, in bytes
+    pub max_memory: usize,
+    // frames created: the one entered plus one per CALL
+    pub frames: u64,
+    pub max_call_depth: usize,
+}
+
+// Stack and memory as execution ended. The root frame when it stops, returns, reverts or runs off
+// the end; the frame an exceptional halt happened in; for a nested REVERT, the caller the reverting
+// frame was popped back to, with the call's 0 already pushed. A timeout captures nothing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FinalFrame {
+    pub stack: Vec<U256>,
+    pub memory: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TransactionError {
+    NonceMismatch { expected: u64, got: u64 },
+    InsufficientFunds,
+    IntrinsicGasTooLow,
+    FeeCapTooLow,
+    BlobsNotActive,
+    TooManyBlobs,
+    InvalidBlobHash,
+    BlobFeeCapTooLow,
+    HostError(String),
+}
+
+#[derive(Clone, Default)]
 pub struct Account {
     pub balance: U256,
     pub code: Rc<Vec<u8>>,
     pub jumpdests: Rc<HashSet<usize>>,
     pub storage: HashMap<U256, U256>,
-    pub nonce: u64
+    pub nonce: u64,
+    // set by hosts that leave `code` out of `basic`; it is fetched through `Host::code` the first time it runs
+    pub lazy_code: bool,
+    // `storage` holds every nonzero slot, so missing ones read as zero without asking the host
+    pub storage_complete: bool,
+}
+
+// Storage and jump destinations sorted, so printing an account gives the same text every run
+impl Debug for Account {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut jumpdests: Vec<&usize> = self.jumpdests.iter().collect();
+        jumpdests.sort();
+        f.debug_struct("Account")
+            .field("balance", &self.balance)
+            .field("code", &self.code)
+            .field("jumpdests", &jumpdests)
+            .field("storage", &self.storage.iter().collect::<BTreeMap<_, _>>())
+            .field("nonce", &self.nonce)
+            .field("lazy_code", &self.lazy_code)
+            .field("storage_complete", &self.storage_complete)
+            .finish()
+    }
+}
+
+impl Account {
+    pub fn with_code(code: Vec<u8>) -> Self {
+        let jumpdests = Machine::analyze_jumpdests(&code);
+        Self {
+            code: Rc::new(code),
+            jumpdests: Rc::new(jumpdests),
+            ..Default::default()
+        }
+    }
+
+    fn set_code(&mut self, code: Vec<u8>) {
+        self.jumpdests = Rc::new(Machine::analyze_jumpdests(&code));
+        self.code = Rc::new(code);
+        self.lazy_code = false;
+    }
 }
 
 #[derive(Debug)]
@@ -70,16 +156,147 @@ pub struct Frame {
     pub callee: Address,
 }
 
-#[derive(Debug)]
+// Backing state consulted when an account or storage slot is not in `Machine::accounts` yet.
+// Whatever it returns is cached in `accounts`, so each key is fetched at most once per Machine
+pub trait Host: Debug {
+    fn basic(&mut self, address: Address) -> Result<Account, String>;
+    fn storage(&mut self, address: Address, key: U256) -> Result<U256, String>;
+
+    // Only asked for accounts `basic` returned with `lazy_code` set
+    fn code(&mut self, address: Address) -> Result<Vec<u8>, String> {
+        Ok(self.basic(address)?.code.to_vec())
+    }
+}
+
+// Async counterpart of Host for state behind network or database IO, driven by `call_async`.
+// Only awaited between instructions; the interpreter itself stays synchronous
+pub trait AsyncHost {
+    fn basic(&mut self, address: Address) -> impl Future<Output = Result<Account, String>>;
+    fn storage(&mut self, address: Address, key: U256) -> impl Future<Output = Result<U256, String>>;
+
+    fn code(&mut self, address: Address) -> impl Future<Output = Result<Vec<u8>, String>> {
+        async move { Ok(self.basic(address).await?.code.to_vec()) }
+    }
+}
+
+// Rule sets that change gas accounting. Only the forks that matter to the implemented opcodes are listed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Hardfork {
+    // flat SLOAD/SSTORE costs and no access lists to track
+    #[default]
+    Istanbul,
+    // EIP-2929 warm/cold access costs
+    Berlin,
+    London,
+    // EIP-3651 warm coinbase
+    Shanghai,
+    Cancun,
+}
+
+impl Hardfork {
+    // Addresses of the precompiles active in this fork, warm from the start of every transaction from Berlin on
+    pub fn precompiles(self) -> impl Iterator<Item = Address> {
+        let last = match self {
+            Hardfork::Cancun => 10,
+            _ => 9,
+        };
+        (1..=last).map(|n: u8| Address::with_last_byte(n))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    // calls nested deeper than this below the root frame fail and push 0, as in the EVM
+    pub max_call_depth: usize,
+    // a frame holding more items than this halts with StackOverflow
+    pub max_stack_height: usize,
+    // wall-clock budget per execution, checked every TIMEOUT_CHECK_INTERVAL instructions
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self { max_call_depth: 1024, max_stack_height: 1024, timeout: None }
+    }
+}
+
+#[derive(Default)]
 pub struct Machine {
     pub accounts: HashMap<Address, Account>,
     pub call_stack: Vec<Frame>,
     pub return_data: Vec<u8>,
+    pub logs: Vec<Log>,
+    pub block: Header,
+    // hashes of the last 256 blocks, served by BLOCKHASH
+    pub block_hashes: HashMap<u64, B256>,
+    pub host: Option<Box<dyn Host>>,
+    pub hardfork: Hardfork,
+    pub limits: Limits,
+    pub capture_final_frame: bool,
+    pub final_frame: Option<FinalFrame>,
+    // versioned hashes of the running blob transaction, served by BLOBHASH
+    pub blob_hashes: Vec<B256>,
+    // EIP-2929 access sets of the current transaction
+    pub accessed_addresses: HashSet<Address>,
+    pub accessed_storage: HashSet<(Address, U256)>,
+    // EIP-1153 storage that lives for a single transaction
+    pub transient_storage: HashMap<(Address, U256), U256>,
 
     #[doc(hidden)]
     last_call_return: (usize, usize),
+    gas_left: u64,
+    // storage values as of the start of the transaction, recorded on first SSTORE for EIP-2200 metering
+    original_storage: HashMap<(Address, U256), U256>,
+    // SSTORE refunds accumulated by the current transaction, may dip below zero mid-way
+    refund: i64,
+    // counters of everything run since the last call was entered
+    stats: ExecutionStats,
+    // when the first of those instructions ran, only tracked with a timeout set
+    started: Option<Instant>,
 }
 
+// Hash-keyed fields print sorted, so the same machine always prints the same text
+impl Debug for Machine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut accessed_addresses: Vec<&Address> = self.accessed_addresses.iter().collect();
+        accessed_addresses.sort();
+        let mut accessed_storage: Vec<&(Address, U256)> = self.accessed_storage.iter().collect();
+        accessed_storage.sort();
+        f.debug_struct("Machine")
+            .field("accounts", &self.accounts.iter().collect::<BTreeMap<_, _>>())
+            .field("call_stack", &self.call_stack)
+            .field("return_data", &self.return_data)
+            .field("logs", &self.logs)
+            .field("block", &self.block)
+            .field("block_hashes", &self.block_hashes.iter().collect::<BTreeMap<_, _>>())
+            .field("host", &self.host)
+            .field("hardfork", &self.hardfork)
+            .field("limits", &self.limits)
+            .field("capture_final_frame", &self.capture_final_frame)
+            .field("final_frame", &self.final_frame)
+            .field("blob_hashes", &self.blob_hashes)
+            .field("accessed_addresses", &accessed_addresses)
+            .field("accessed_storage", &accessed_storage)
+            .field("transient_storage", &self.transient_storage.iter().collect::<BTreeMap<_, _>>())
+            .field("last_call_return", &self.last_call_return)
+            .field("gas_left", &self.gas_left)
+            .field("original_storage", &self.original_storage.iter().collect::<BTreeMap<_, _>>())
+            .field("refund", &self.refund)
+            .field("stats", &self.stats)
+            .field("started", &self.started)
+            .finish()
+    }
+}
+
+pub trait Inspector {
+    fn step(&mut self, _machine: &Machine) {}
+    fn step_end(&mut self, _machine: &Machine, _gas_cost: u64) {}
+}
+
+pub struct NoopInspector;
+
+impl Inspector for NoopInspector {}
+
 impl Machine {
     pub fn new(code: Vec<u8>, calldata: Vec<u8>, storage: HashMap<U256, U256>, gas_limit: u64) -> Self {
         let caller = Address::ZERO;
@@ -94,7 +311,9 @@ impl Machine {
             code: code_rc.clone(),
             jumpdests: jumpdests_rc.clone(),
             storage,
-            nonce: 0
+            nonce: 0,
+            lazy_code: false,
+            storage_complete: false,
         });
 
         let initial_frame = Frame {
@@ -114,19 +333,315 @@ impl Machine {
             accounts,
             call_stack: vec![initial_frame],
             return_data: Vec::new(),
+            logs: Vec::new(),
+            block: Header::default(),
+            block_hashes: HashMap::new(),
+            host: None,
+            hardfork: Hardfork::default(),
+            limits: Limits::default(),
+            capture_final_frame: false,
+            final_frame: None,
+            blob_hashes: Vec::new(),
+            accessed_addresses: HashSet::new(),
+            accessed_storage: HashSet::new(),
             last_call_return: (0, 0),
+            gas_left: 0,
+            original_storage: HashMap::new(),
+            transient_storage: HashMap::new(),
+            refund: 0,
+            stats: ExecutionStats { frames: 1, max_call_depth: 1, ..Default::default() },
+            started: None,
+        }
+    }
+
+    pub fn with_host(host: impl Host + 'static) -> Self {
+        Self {
+            host: Some(Box::new(host)),
+            ..Default::default()
         }
     }
 
+    // Installs runtime code at `address`, keeping its balance, nonce and storage
+    pub fn deploy(&mut self, address: Address, code: Vec<u8>) {
+        let Account { code, jumpdests, .. } = Account::with_code(code);
+        let account = self.accounts.entry(address).or_default();
+        account.code = code;
+        account.jumpdests = jumpdests;
+        account.lazy_code = false;
+    }
+
+    // Resource counters of the last call or transaction
+    pub fn stats(&self) -> ExecutionStats {
+        self.stats
+    }
+
+    // Gas left over when the last call or transaction finished
+    pub fn gas_left(&self) -> u64 {
+        self.gas_left
+    }
+
+    pub fn account(&mut self, address: Address) -> Result<&mut Account, String> {
+        Self::load_account(&mut self.accounts, &mut self.host, address)
+    }
+
+    pub fn storage(&mut self, address: Address, key: U256) -> Result<U256, String> {
+        Self::load_storage(&mut self.accounts, &mut self.host, address, key)
+    }
+
+    pub fn code(&mut self, address: Address) -> Result<Rc<Vec<u8>>, String> {
+        Ok(Self::load_code(&mut self.accounts, &mut self.host, address)?.code.clone())
+    }
+
+    fn load_account<'a>(accounts: &'a mut HashMap<Address, Account>, host: &mut Option<Box<dyn Host>>, address: Address) -> Result<&'a mut Account, String> {
+        match accounts.entry(address) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let mut account = match host {
+                    Some(host) => host.basic(address)?,
+                    None => Account::default(),
+                };
+                account.jumpdests = Rc::new(Self::analyze_jumpdests(&account.code));
+                Ok(entry.insert(account))
+            }
+        }
+    }
+
+    fn load_code<'a>(accounts: &'a mut HashMap<Address, Account>, host: &mut Option<Box<dyn Host>>, address: Address) -> Result<&'a mut Account, String> {
+        let account = Self::load_account(accounts, host, address)?;
+        if account.lazy_code {
+            let code = match host {
+                Some(host) => host.code(address)?,
+                None => Vec::new(),
+            };
+            account.set_code(code);
+        }
+        Ok(account)
+    }
+
+    fn load_storage(accounts: &mut HashMap<Address, Account>, host: &mut Option<Box<dyn Host>>, address: Address, key: U256) -> Result<U256, String> {
+        if let Some(account) = accounts.get(&address) {
+            match account.storage.get(&key) {
+                Some(value) => return Ok(*value),
+                None if account.storage_complete => return Ok(U256::ZERO),
+                None => {}
+            }
+        }
+        let Some(backend) = host else {
+            return Ok(U256::ZERO);
+        };
+        let value = backend.storage(address, key)?;
+        Self::load_account(accounts, host, address)?.storage.insert(key, value);
+        Ok(value)
+    }
+
+    pub fn call(&mut self, caller: Address, to: Address, calldata: Vec<u8>, gas_limit: u64) -> ExecutionResult {
+        self.call_with_inspector(caller, to, calldata, gas_limit, &mut NoopInspector)
+    }
+
+    pub fn call_with_inspector<I: Inspector>(&mut self, caller: Address, to: Address, calldata: Vec<u8>, gas_limit: u64, inspector: &mut I) -> ExecutionResult {
+        self.begin_transaction(caller, to, &[]);
+        self.execute_call(caller, to, calldata, gas_limit, inspector)
+    }
+
+    fn execute_call<I: Inspector>(&mut self, caller: Address, to: Address, calldata: Vec<u8>, gas_limit: u64, inspector: &mut I) -> ExecutionResult {
+        if let Err(e) = self.enter_call(caller, to, calldata, gas_limit) {
+            return e;
+        }
+        let result = self.run_with_inspector(inspector);
+        let result = self.finish_call(result);
+        self.record_execution(&result, gas_limit);
+        result
+    }
+
+    pub async fn call_async<H: AsyncHost>(&mut self, host: &mut H, caller: Address, to: Address, calldata: Vec<u8>, gas_limit: u64) -> ExecutionResult {
+        if let Err(e) = Self::fetch_code(&mut self.accounts, host, to).await {
+            return ExecutionResult::HostError(e);
+        }
+        self.begin_transaction(caller, to, &[]);
+        if let Err(e) = self.enter_call(caller, to, calldata, gas_limit) {
+            return e;
+        }
+        let result = self.run_async(host).await;
+        let result = self.finish_call(result);
+        self.record_execution(&result, gas_limit);
+        result
+    }
+
+    fn enter_call(&mut self, caller: Address, to: Address, calldata: Vec<u8>, gas_limit: u64) -> Result<(), ExecutionResult> {
+        let target = Self::load_code(&mut self.accounts, &mut self.host, to).map_err(ExecutionResult::HostError)?;
+        let (code, jumpdests) = (target.code.clone(), target.jumpdests.clone());
+
+        self.call_stack.clear();
+        self.return_data.clear();
+        self.logs.clear();
+        self.gas_left = 0;
+        self.final_frame = None;
+        self.stats = ExecutionStats::default();
+        self.record_frame_depth(1);
+        self.call_stack.push(Frame {
+            pc: 0,
+            stack: Vec::with_capacity(1024),
+            memory: Vec::new(),
+            memory_size_words: 0,
+            calldata,
+            gas: gas_limit,
+            code,
+            jumpdests,
+            caller,
+            callee: to,
+        });
+        Ok(())
+    }
+
+    // Resets the per-transaction access sets and warms what the fork considers accessed up front
+    // Everything scoped to a single transaction starts over here; accounts and nonces persist
+    fn begin_transaction(&mut self, caller: Address, to: Address, access_list: &[(Address, Vec<U256>)]) {
+        self.accessed_addresses.clear();
+        self.accessed_storage.clear();
+        self.original_storage.clear();
+        self.transient_storage.clear();
+        self.blob_hashes.clear();
+        self.return_data.clear();
+        self.refund = 0;
+        if self.hardfork < Hardfork::Berlin {
+            return;
+        }
+
+        self.accessed_addresses.extend([caller, to]);
+        self.accessed_addresses.extend(self.hardfork.precompiles());
+        if self.hardfork >= Hardfork::Shanghai {
+            self.accessed_addresses.insert(self.block.coinbase);
+        }
+        for (address, keys) in access_list {
+            self.accessed_addresses.insert(*address);
+            self.accessed_storage.extend(keys.iter().map(|key| (*address, *key)));
+        }
+    }
+
+    fn finish_call(&mut self, result: ExecutionResult) -> ExecutionResult {
+        if !self.call_stack.is_empty() || !matches!(result, ExecutionResult::Success(_) | ExecutionResult::Revert(_)) {
+            // exceptional halts (and reverts surfacing from a nested frame) consume all gas
+            self.gas_left = 0;
+            self.call_stack.clear();
+        }
+        result
+    }
+
+    pub fn transact(&mut self, tx: &Transaction) -> Result<TransactionOutcome, TransactionError> {
+        self.transact_with_inspector(tx, &mut NoopInspector)
+    }
+
+    pub fn transact_with_inspector<I: Inspector>(&mut self, tx: &Transaction, inspector: &mut I) -> Result<TransactionOutcome, TransactionError> {
+        let intrinsic_gas = Self::intrinsic_gas(tx);
+        if tx.gas_limit < intrinsic_gas {
+            return Err(TransactionError::IntrinsicGasTooLow);
+        }
+
+        if tx.gas_price < self.block.base_fee {
+            return Err(TransactionError::FeeCapTooLow);
+        }
+        // EIP-1559: the base fee is burned and the coinbase gets whatever the sender pays on top
+        let effective_gas_price = match tx.gas_priority_fee {
+            Some(priority_fee) => tx.gas_price.min(self.block.base_fee.saturating_add(priority_fee)),
+            None => tx.gas_price,
+        };
+
+        // EIP-4844: blob gas is paid up front at the block's blob base fee and burned, even on revert
+        let blob_fee = if tx.blob_versioned_hashes.is_empty() {
+            U256::ZERO
+        } else {
+            if self.hardfork < Hardfork::Cancun {
+                return Err(TransactionError::BlobsNotActive);
+            }
+            if tx.blob_versioned_hashes.len() > MAX_BLOBS_PER_TRANSACTION {
+                return Err(TransactionError::TooManyBlobs);
+            }
+            if tx.blob_versioned_hashes.iter().any(|hash| hash[0] != VERSIONED_HASH_VERSION_KZG) {
+                return Err(TransactionError::InvalidBlobHash);
+            }
+            let blob_base_fee = self.block.blob_base_fee();
+            if tx.max_fee_per_blob_gas < blob_base_fee {
+                return Err(TransactionError::BlobFeeCapTooLow);
+            }
+            U256::from(tx.blob_gas()) * blob_base_fee
+        };
+
+        let max_fee = U256::from(tx.gas_limit).saturating_mul(tx.gas_price).saturating_add(U256::from(tx.blob_gas()).saturating_mul(tx.max_fee_per_blob_gas));
+        let upfront_fee = U256::from(tx.gas_limit) * effective_gas_price + blob_fee;
+        self.account(tx.to).map_err(TransactionError::HostError)?;
+        let sender = self.account(tx.caller).map_err(TransactionError::HostError)?;
+        if let Some(nonce) = tx.nonce && nonce != sender.nonce {
+            return Err(TransactionError::NonceMismatch { expected: sender.nonce, got: nonce });
+        }
+        if sender.balance < max_fee.saturating_add(tx.value) {
+            return Err(TransactionError::InsufficientFunds);
+        }
+        sender.balance -= upfront_fee;
+        sender.nonce += 1;
+
+        let snapshot = self.accounts.clone();
+        self.accounts.get_mut(&tx.caller).unwrap().balance -= tx.value;
+        self.accounts.get_mut(&tx.to).unwrap().balance += tx.value;
+
+        self.begin_transaction(tx.caller, tx.to, &tx.access_list);
+        self.blob_hashes = tx.blob_versioned_hashes.clone();
+        let result = self.execute_call(tx.caller, tx.to, tx.data.clone(), tx.gas_limit - intrinsic_gas, inspector);
+        if !matches!(result, ExecutionResult::Success(_)) {
+            self.accounts = snapshot;
+            self.logs.clear();
+        }
+
+        // refunds are capped at a fraction of the gas used and lost on revert
+        if matches!(result, ExecutionResult::Success(_)) {
+            let quotient = if self.hardfork >= Hardfork::London { 5 } else { 2 };
+            self.gas_left += (self.refund.max(0) as u64).min((tx.gas_limit - self.gas_left) / quotient);
+        }
+        let gas_used = tx.gas_limit - self.gas_left;
+        self.accounts.get_mut(&tx.caller).unwrap().balance += U256::from(self.gas_left) * effective_gas_price;
+
+        let tip = U256::from(gas_used) * (effective_gas_price - self.block.base_fee);
+        if !tip.is_zero() {
+            let coinbase = self.block.coinbase;
+            self.account(coinbase).map_err(TransactionError::HostError)?.balance += tip;
+        }
+
+        Ok(TransactionOutcome { result, gas_used, logs: self.logs.clone(), final_frame: self.final_frame.clone(), stats: self.stats })
+    }
+
+    // Runs the transaction and throws away every state change, including the nonce bump and fees
+    pub fn simulate(&mut self, tx: &Transaction) -> Result<TransactionOutcome, TransactionError> {
+        self.simulate_with_inspector(tx, &mut NoopInspector)
+    }
+
+    pub fn simulate_with_inspector<I: Inspector>(&mut self, tx: &Transaction, inspector: &mut I) -> Result<TransactionOutcome, TransactionError> {
+        let snapshot = self.accounts.clone();
+        let outcome = self.transact_with_inspector(tx, inspector);
+        self.accounts = snapshot;
+        outcome
+    }
+
+    fn intrinsic_gas(tx: &Transaction) -> u64 {
+        const G_TRANSACTION: u64 = 21000;
+        const G_TXDATA_ZERO: u64 = 4;
+        const G_TXDATA_NONZERO: u64 = 16;
+        const G_ACCESS_LIST_ADDRESS: u64 = 2400;
+        const G_ACCESS_LIST_STORAGE: u64 = 1900;
+
+        let data_gas = tx.data.iter().fold(0, |gas, &byte| gas + if byte == 0 { G_TXDATA_ZERO } else { G_TXDATA_NONZERO });
+        let access_list_gas = tx.access_list.iter().fold(0, |gas, (_, keys)| gas + G_ACCESS_LIST_ADDRESS + G_ACCESS_LIST_STORAGE * keys.len() as u64);
+        G_TRANSACTION + data_gas + access_list_gas
+    }
+
     fn analyze_jumpdests(code: &[u8]) -> HashSet<usize> {
         let mut dests = HashSet::new();
         let mut i = 0;
         while i < code.len() {
-            let opcode = code[i];
-            if opcode == JUMPDEST {
-                dests.insert(i);
-            } else if (PUSH1..=PUSH32).contains(&opcode) {
-                i += (opcode - PUSH1 + 1) as usize;
+            match Opcode::try_from(code[i]) {
+                Ok(Opcode::JumpDest) => {
+                    dests.insert(i);
+                }
+                Ok(opcode) => i += opcode.immediate_size(),
+                Err(_) => {}
             }
             i += 1;
         }
@@ -144,15 +659,99 @@ impl Machine {
         }
     }
 
+    pub fn run_with_inspector<I: Inspector>(&mut self, inspector: &mut I) -> ExecutionResult {
+        loop {
+            let Some(frame) = self.call_stack.last() else {
+                return ExecutionResult::Success(std::mem::take(&mut self.return_data));
+            };
+            let depth = self.call_stack.len();
+            let gas_before = frame.gas;
+
+            inspector.step(self);
+            let step_result = self.step();
+
+            // a CALL leaves its frame below the new one, a RETURN/STOP pops it
+            let gas_after = match step_result {
+                Ok(()) | Err(ExecutionResult::Revert(_)) => self.call_stack.get(depth - 1).map_or(self.gas_left, |frame| frame.gas),
+                Err(_) => 0,
+            };
+            inspector.step_end(self, gas_before.saturating_sub(gas_after));
+
+            if let Err(e) = step_result {
+                return e;
+            }
+        }
+    }
+
+    // Like `run`, but whatever state the next instruction touches is awaited from `host` first,
+    // so the synchronous step only ever hits the `accounts` cache
+    pub async fn run_async<H: AsyncHost>(&mut self, host: &mut H) -> ExecutionResult {
+        loop {
+            if self.call_stack.is_empty() {
+                return ExecutionResult::Success(std::mem::take(&mut self.return_data));
+            }
+            if let Err(e) = self.prefetch(host).await {
+                return ExecutionResult::HostError(e);
+            }
+            if let Err(e) = self.step() {
+                return e;
+            }
+        }
+    }
+
+    async fn prefetch<H: AsyncHost>(&mut self, host: &mut H) -> Result<(), String> {
+        let frame = self.call_stack.last().unwrap();
+        let top = |depth: usize| frame.stack.len().checked_sub(depth + 1).map(|i| frame.stack[i]);
+        let (address, key) = match frame.code.get(frame.pc).map(|&byte| Opcode::try_from(byte)) {
+            Some(Ok(Opcode::SLoad | Opcode::SStore)) => (frame.callee, top(0)),
+            Some(Ok(Opcode::Call)) => match top(1) {
+                Some(to) => return Self::fetch_code(&mut self.accounts, host, Address::from_word(to.to_be_bytes().into())).await,
+                None => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+
+        Self::fetch_account(&mut self.accounts, host, address).await?;
+        let account = &self.accounts[&address];
+        if let Some(key) = key && !account.storage_complete && !account.storage.contains_key(&key) {
+            let value = host.storage(address, key).await?;
+            self.accounts.get_mut(&address).unwrap().storage.insert(key, value);
+        }
+        Ok(())
+    }
+
+    async fn fetch_account<H: AsyncHost>(accounts: &mut HashMap<Address, Account>, host: &mut H, address: Address) -> Result<(), String> {
+        if let Entry::Vacant(entry) = accounts.entry(address) {
+            let mut account = host.basic(address).await?;
+            account.jumpdests = Rc::new(Self::analyze_jumpdests(&account.code));
+            entry.insert(account);
+        }
+        Ok(())
+    }
+
+    async fn fetch_code<H: AsyncHost>(accounts: &mut HashMap<Address, Account>, host: &mut H, address: Address) -> Result<(), String> {
+        Self::fetch_account(accounts, host, address).await?;
+        let account = accounts.get_mut(&address).unwrap();
+        if account.lazy_code {
+            account.set_code(host.code(address).await?);
+        }
+        Ok(())
+    }
+
     fn handle_frame_end(&mut self, success: bool, offset: usize, size: usize) {
         let ended_frame = self.call_stack.pop().unwrap();
+        self.gas_left = ended_frame.gas;
+        // RETURN and REVERT can still expand memory on the way out
+        self.stats.max_memory = self.stats.max_memory.max(ended_frame.memory_size_words as usize * 32);
         if size > 0 {
             self.return_data = ended_frame.memory.get(offset..offset + size).unwrap_or_default().to_vec();
         } else {
             self.return_data.clear();
         }
 
-        if let Some(caller_frame) = self.call_stack.last_mut() {
+        if self.call_stack.is_empty() && self.capture_final_frame {
+            self.final_frame = Some(FinalFrame { stack: ended_frame.stack, memory: ended_frame.memory });
+        } else if let Some(caller_frame) = self.call_stack.last_mut() {
             caller_frame.gas += ended_frame.gas;
             caller_frame.stack.push(if success { U256::from(1) } else { U256::ZERO });
 
@@ -166,15 +765,34 @@ impl Machine {
     }
 
     fn step(&mut self) -> Result<(), ExecutionResult> {
+        self.stats.steps += 1;
+        if let Some(timeout) = self.limits.timeout {
+            if self.stats.steps == 1 {
+                self.started = Some(Instant::now());
+            } else if self.stats.steps.is_multiple_of(TIMEOUT_CHECK_INTERVAL) && self.started.is_some_and(|started| started.elapsed() > timeout) {
+                return Err(ExecutionResult::Timeout);
+            }
+        }
+        let result = self.execute_instruction();
+        if result.is_err() && self.capture_final_frame && let Some(frame) = self.call_stack.last() {
+            self.final_frame = Some(FinalFrame { stack: frame.stack.clone(), memory: frame.memory.clone() });
+        }
+        result
+    }
+
+    fn execute_instruction(&mut self) -> Result<(), ExecutionResult> {
+        let depth = self.call_stack.len();
         let frame = self.call_stack.last_mut().unwrap();
         if frame.pc >= frame.code.len() {
             self.handle_frame_end(true, 0, 0);
             return Ok(());
         }
 
-        let opcode = frame.read_opcode();
+        let Ok(opcode) = Opcode::try_from(frame.read_opcode()) else {
+            return Err(ExecutionResult::InvalidOpcode);
+        };
 
-        let cost = Self::get_opcode_cost(opcode);
+        let cost = Self::get_opcode_cost(opcode, self.hardfork);
         if frame.gas < cost {
             frame.gas = 0;
             return Err(ExecutionResult::OutOfGas);
@@ -182,39 +800,39 @@ impl Machine {
         frame.gas -= cost;
 
         match opcode {
-            STOP => self.handle_frame_end(true, 0, 0),
-            RETURN => {
-                let offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
-                let size = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
+            Opcode::Stop => self.handle_frame_end(true, 0, 0),
+            Opcode::Return => {
+                let (offset, size) = memory_range(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?, frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?)?;
                 frame.charge_memory_expansion_gas(offset, size)?;
+                frame.memory_resize(offset + size);
                 self.handle_frame_end(true, offset, size);
             }
-            REVERT => {
-                let offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
-                let size = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
+            Opcode::Revert => {
+                let (offset, size) = memory_range(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?, frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?)?;
                 frame.charge_memory_expansion_gas(offset, size)?;
+                frame.memory_resize(offset + size);
                 self.handle_frame_end(false, offset, size);
                 return Err(ExecutionResult::Revert(self.return_data.clone()));
             }
-            ADD => {
+            Opcode::Add => {
                 let a = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let b = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let (res, _) = a.overflowing_add(b);
                 frame.stack.push(res);
             }
-            MUL => {
+            Opcode::Mul => {
                 let a = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let b = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let (res, _) = a.overflowing_mul(b);
                 frame.stack.push(res);
             }
-            SUB => {
+            Opcode::Sub => {
                 let b = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let a = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let (res, _) = a.overflowing_sub(b);
                 frame.stack.push(res);
             }
-            DIV => {
+            Opcode::Div => {
                 let b = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let a = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 if b.is_zero() {
@@ -223,28 +841,27 @@ impl Machine {
                     frame.stack.push(a / b);
                 }
             }
-            LT => {
+            Opcode::Lt => {
                 let b = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let a = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 frame.stack.push(if a < b { U256::from(1) } else { U256::ZERO });
             }
-            GT => {
+            Opcode::Gt => {
                 let b = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let a = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 frame.stack.push(if a > b { U256::from(1) } else { U256::ZERO });
             }
-            EQ => {
+            Opcode::Eq => {
                 let b = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let a = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 frame.stack.push(if a == b { U256::from(1) } else { U256::ZERO });
             }
-            ISZERO => {
+            Opcode::IsZero => {
                 let a = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 frame.stack.push(if a.is_zero() { U256::from(1) } else { U256::ZERO });
             }
-            SHA3 => {
-                let offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
-                let size = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
+            Opcode::Sha3 => {
+                let (offset, size) = memory_range(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?, frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?)?;
 
                 frame.charge_memory_expansion_gas(offset, size)?;
                 frame.memory_resize(offset + size);
@@ -254,56 +871,132 @@ impl Machine {
                 frame.stack.push(U256::from_be_bytes(hash.0));
 
             }
-            CALLDATALOAD => {
-                let offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
+            Opcode::BlockHash => {
+                let number = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
+                let current = self.block.number;
+                let hash = match u64::try_from(number) {
+                    Ok(number) if number < current && current - number <= 256 => self.block_hashes.get(&number).copied().unwrap_or_default(),
+                    _ => B256::ZERO,
+                };
+                frame.stack.push(U256::from_be_bytes(hash.0));
+            }
+            Opcode::Caller => {
+                frame.stack.push(U256::from_be_bytes(frame.caller.into_word().0));
+            }
+            Opcode::Coinbase => {
+                frame.stack.push(U256::from_be_bytes(self.block.coinbase.into_word().0));
+            }
+            Opcode::Timestamp => {
+                frame.stack.push(U256::from(self.block.timestamp));
+            }
+            Opcode::Number => {
+                frame.stack.push(U256::from(self.block.number));
+            }
+            Opcode::GasLimit => {
+                frame.stack.push(U256::from(self.block.gas_limit));
+            }
+            Opcode::BaseFee => {
+                frame.stack.push(self.block.base_fee);
+            }
+            Opcode::BlobHash if self.hardfork >= Hardfork::Cancun => {
+                let index = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
+                let hash = usize::try_from(index).ok().and_then(|index| self.blob_hashes.get(index)).copied().unwrap_or_default();
+                frame.stack.push(U256::from_be_bytes(hash.0));
+            }
+            Opcode::CallDataLoad => {
+                let offset = saturating_usize(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?);
                 let mut data = [0u8; 32];
 
                 if offset < frame.calldata.len() {
-                    let end = (offset + 32).min(frame.calldata.len());
+                    let end = offset.saturating_add(32).min(frame.calldata.len());
                     let slice = &frame.calldata[offset..end];
                     data[..slice.len()].copy_from_slice(slice);
                 }
 
                 frame.stack.push(U256::from_be_bytes(data));
             }
-            MLOAD => {
-                let offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
+            Opcode::MLoad => {
+                let (offset, _) = memory_range(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?, U256::from(32))?;
                 frame.charge_memory_expansion_gas(offset, 32)?;
                 frame.memory_resize(offset + 32);
                 let mut data = [0u8; 32];
                 data.copy_from_slice(&frame.memory[offset..offset + 32]);
                 frame.stack.push(U256::from_be_bytes(data));
             }
-            MSTORE => {
-                let offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
+            Opcode::MStore => {
+                let (offset, _) = memory_range(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?, U256::from(32))?;
                 let value = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 frame.charge_memory_expansion_gas(offset, 32)?;
                 frame.memory_resize(offset + 32);
                 frame.memory[offset..offset + 32].copy_from_slice(&value.to_be_bytes::<32>());
             }
-            SLOAD => {
+            Opcode::SLoad => {
                 let key = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
-                let value = self.accounts.get(&frame.callee).map_or(U256::ZERO, |acc| acc.storage.get(&key).cloned().unwrap_or_default());
+                if self.hardfork >= Hardfork::Berlin {
+                    frame.charge_gas(access_cost(&mut self.accessed_storage, (frame.callee, key), WARM_STORAGE_READ_COST, COLD_SLOAD_COST))?;
+                }
+                let value = Self::load_storage(&mut self.accounts, &mut self.host, frame.callee, key).map_err(ExecutionResult::HostError)?;
                 frame.stack.push(value);
             }
-            SSTORE => {
+            Opcode::SStore => {
                 let key = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let value = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
-                self.accounts
-                        .entry(frame.callee)
-                        .or_default()
+                if self.hardfork >= Hardfork::Berlin {
+                    // EIP-2200 net metering on top of the EIP-2929 cold surcharge
+                    if frame.gas <= SSTORE_STIPEND {
+                        return Err(ExecutionResult::OutOfGas);
+                    }
+                    let current = Self::load_storage(&mut self.accounts, &mut self.host, frame.callee, key).map_err(ExecutionResult::HostError)?;
+                    let original = *self.original_storage.entry((frame.callee, key)).or_insert(current);
+                    let cost = if current == value || original != current {
+                        WARM_STORAGE_READ_COST
+                    } else if original.is_zero() {
+                        SSTORE_SET_COST
+                    } else {
+                        SSTORE_RESET_COST
+                    };
+                    frame.charge_gas(cost + access_cost(&mut self.accessed_storage, (frame.callee, key), 0, COLD_SLOAD_COST))?;
+
+                    let clear_refund = if self.hardfork >= Hardfork::London { SSTORE_CLEARS_REFUND } else { SSTORE_CLEARS_REFUND_BERLIN };
+                    if current != value && original == current {
+                        if !original.is_zero() && value.is_zero() {
+                            self.refund += clear_refund;
+                        }
+                    } else if current != value {
+                        if !original.is_zero() && current.is_zero() {
+                            self.refund -= clear_refund;
+                        } else if !original.is_zero() && value.is_zero() {
+                            self.refund += clear_refund;
+                        }
+                        if original == value {
+                            let cost = if original.is_zero() { SSTORE_SET_COST } else { SSTORE_RESET_COST };
+                            self.refund += (cost - WARM_STORAGE_READ_COST) as i64;
+                        }
+                    }
+                }
+                Self::load_account(&mut self.accounts, &mut self.host, frame.callee)
+                        .map_err(ExecutionResult::HostError)?
                         .storage
                         .insert(key, value);
             }
-            JUMP => {
-                let dest = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
+            Opcode::TLoad if self.hardfork >= Hardfork::Cancun => {
+                let key = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
+                frame.stack.push(self.transient_storage.get(&(frame.callee, key)).copied().unwrap_or_default());
+            }
+            Opcode::TStore if self.hardfork >= Hardfork::Cancun => {
+                let key = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
+                let value = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
+                self.transient_storage.insert((frame.callee, key), value);
+            }
+            Opcode::Jump => {
+                let dest = saturating_usize(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?);
                 if !frame.jumpdests.contains(&dest) {
                     return Err(ExecutionResult::InvalidJump);
                 }
                 frame.pc = dest;
             }
-            JUMPI => {
-                let dest = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
+            Opcode::JumpI => {
+                let dest = saturating_usize(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?);
                 let cond = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
 
                 if !frame.jumpdests.contains(&dest) {
@@ -312,11 +1005,11 @@ impl Machine {
                     frame.pc = dest;
                 }
             }
-            JUMPDEST => {
+            Opcode::JumpDest => {
                 //
             }
-            op if (PUSH1..=PUSH32).contains(&op) => {
-                let num_bytes_to_push = (op - PUSH1 + 1) as usize;
+            op if op.is_push() => {
+                let num_bytes_to_push = op.immediate_size();
                 let start = frame.pc;
                 let end = frame.pc + num_bytes_to_push;
 
@@ -332,19 +1025,19 @@ impl Machine {
                     frame.pc = end;
                 }
             }
-            POP => {
+            Opcode::Pop => {
                 frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
             }
-            op if (DUP1..=DUP16).contains(&op) => {
-                let index = (op - DUP1) as usize;
+            op if op.is_dup() => {
+                let index = (op as u8 - Opcode::Dup1 as u8) as usize;
                  if frame.stack.len() <= index {
                      return Err(ExecutionResult::StackUnderflow);
                  }
-                let val = frame.stack[frame.stack.len() - 1 - index].clone();
+                let val = frame.stack[frame.stack.len() - 1 - index];
                 frame.stack.push(val);
             }
-            op if (SWAP1..=SWAP16).contains(&op) => {
-                let index = (op - SWAP1 + 1) as usize;
+            op if op.is_swap() => {
+                let index = (op as u8 - Opcode::Swap1 as u8 + 1) as usize;
                  if frame.stack.len() <= index {
                      return Err(ExecutionResult::StackUnderflow);
                  }
@@ -352,27 +1045,56 @@ impl Machine {
                 let b = frame.stack.len() - 1 - index;
                 frame.stack.swap(a, b);
             }
-            CALL => {
+            op if op.is_log() => {
+                let (offset, size) = memory_range(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?, frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?)?;
+                let mut topics = Vec::with_capacity((op as u8 - Opcode::Log0 as u8) as usize);
+                for _ in Opcode::Log0 as u8..op as u8 {
+                    let topic = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
+                    topics.push(B256::from(topic.to_be_bytes::<32>()));
+                }
+
+                const G_LOGDATA: u64 = 8;
+                let data_cost = (size as u64).saturating_mul(G_LOGDATA);
+                if frame.gas < data_cost {
+                    return Err(ExecutionResult::OutOfGas);
+                }
+                frame.gas -= data_cost;
+                frame.charge_memory_expansion_gas(offset, size)?;
+                frame.memory_resize(offset + size);
+
+                let data = frame.memory[offset..offset + size].to_vec();
+                self.logs.push(Log::new_unchecked(frame.callee, topics, data.into()));
+            }
+            Opcode::Call => {
                 let gas_limit_u256 = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let to_address_u256 = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
                 let to_address = Address::from_word(to_address_u256.to_be_bytes().into());
                 let _value = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
-                let args_offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
-                let args_size = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
-                let ret_offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
-                let ret_size = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
+                let (args_offset, args_size) = memory_range(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?, frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?)?;
+                let (ret_offset, ret_size) = memory_range(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?, frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?)?;
 
+                if self.hardfork >= Hardfork::Berlin {
+                    frame.charge_gas(access_cost(&mut self.accessed_addresses, to_address, WARM_STORAGE_READ_COST, COLD_ACCOUNT_ACCESS_COST))?;
+                }
                 frame.charge_memory_expansion_gas(args_offset, args_size)?;
                 frame.charge_memory_expansion_gas(ret_offset, ret_size)?;
+                frame.memory_resize(args_offset + args_size);
                 self.last_call_return = (ret_offset, ret_size);
 
+                if depth > self.limits.max_call_depth {
+                    self.return_data.clear();
+                    frame.stack.push(U256::ZERO);
+                    return Ok(());
+                }
+
                 // 1/64
                 let gas_limit = if gas_limit_u256 > U256::from(u64::MAX) { frame.gas } else { gas_limit_u256.as_limbs()[0] };
                 let gas_to_send = (frame.gas - (frame.gas / 64)).min(gas_limit);
                 frame.gas -= gas_to_send;
 
-                let target_account = self.accounts.get(&to_address).cloned().unwrap_or_default();
+                let target_account = Self::load_code(&mut self.accounts, &mut self.host, to_address).map_err(ExecutionResult::HostError)?;
                 let target_code = target_account.code.clone();
+                let target_jumpdests = target_account.jumpdests.clone();
                 let new_calldata = if args_size > 0 {
                     frame.memory[args_offset..args_offset + args_size].to_vec()
                 } else {
@@ -384,7 +1106,7 @@ impl Machine {
                     gas: gas_to_send,
                     calldata: new_calldata,
                     code: target_code,
-                    jumpdests: target_account.jumpdests,
+                    jumpdests: target_jumpdests,
                     caller: frame.callee,
                     callee: to_address,
                     stack: vec![],
@@ -393,18 +1115,19 @@ impl Machine {
                 };
 
                 self.call_stack.push(new_frame);
+                self.record_frame_depth(self.call_stack.len());
             }
-            RETURNDATASIZE => {
+            Opcode::ReturnDataSize => {
                 frame.stack.push(U256::from(self.return_data.len()));
             }
-            RETURNDATACOPY => {
-                let mem_offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
-                let return_offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
-                let size = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?.as_limbs()[0] as usize;
-
-                if return_offset.saturating_add(size) > self.return_data.len() {
+            Opcode::ReturnDataCopy => {
+                let mem_offset = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
+                let return_offset = saturating_usize(frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?);
+                let size = frame.stack.pop().ok_or(ExecutionResult::StackUnderflow)?;
+                if return_offset.saturating_add(saturating_usize(size)) > self.return_data.len() {
                     return Err(ExecutionResult::InvalidOpcode);
                 }
+                let (mem_offset, size) = memory_range(mem_offset, size)?;
 
                 frame.charge_memory_expansion_gas(mem_offset, size)?;
                 frame.memory_resize(mem_offset + size);
@@ -414,29 +1137,102 @@ impl Machine {
                 return Err(ExecutionResult::InvalidOpcode);
             }
         }
+        if let Some(frame) = self.call_stack.last() {
+            self.stats.max_stack_height = self.stats.max_stack_height.max(frame.stack.len());
+            self.stats.max_memory = self.stats.max_memory.max(frame.memory_size_words as usize * 32);
+            if frame.stack.len() > self.limits.max_stack_height {
+                return Err(ExecutionResult::StackOverflow);
+            }
+        }
         Ok(())
     }
 
-    fn get_opcode_cost(opcode: u8) -> u64 {
+    // Prometheus-style metrics through the `metrics` facade; a no-op without the feature or
+    // without an installed recorder
+    #[cfg(feature = "metrics")]
+    fn record_execution(&self, result: &ExecutionResult, gas_limit: u64) {
+        let label = match result {
+            ExecutionResult::Success(_) => "success",
+            ExecutionResult::Revert(_) => "revert",
+            ExecutionResult::OutOfGas => "out_of_gas",
+            ExecutionResult::InvalidOpcode => "invalid_opcode",
+            ExecutionResult::InvalidJump => "invalid_jump",
+            ExecutionResult::StackUnderflow => "stack_underflow",
+            ExecutionResult::StackOverflow => "stack_overflow",
+            ExecutionResult::Timeout => "timeout",
+            ExecutionResult::HostError(_) => "host_error",
+        };
+        metrics::counter!("evm_executions_total", "result" => label).increment(1);
+        metrics::counter!("evm_opcodes_executed_total").increment(self.stats.steps);
+        metrics::counter!("evm_gas_consumed_total").increment(gas_limit - self.gas_left);
+    }
+
+    fn record_frame_depth(&mut self, depth: usize) {
+        self.stats.frames += 1;
+        self.stats.max_call_depth = self.stats.max_call_depth.max(depth);
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("evm_frame_depth").record(depth as f64);
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn record_execution(&self, _result: &ExecutionResult, _gas_limit: u64) {}
+
+    fn get_opcode_cost(opcode: Opcode, hardfork: Hardfork) -> u64 {
         match opcode {
-            STOP | JUMPDEST => 0,
-            ADD | SUB | POP | LT | GT | EQ | ISZERO => 3,
-            MUL | DIV => 5,
-            PUSH1..=PUSH32 => 3,
-            DUP1..=DUP16 => 3,
-            SWAP1..=SWAP16 => 3,
-            MLOAD | MSTORE => 3,
-            SSTORE => 20000,
-            SLOAD => 800,
-            JUMP => 8,
-            JUMPI => 10,
-            SHA3 => 30,
-            _ => 0,
+            // charged dynamically once access costs apply
+            Opcode::SLoad | Opcode::SStore | Opcode::Call if hardfork >= Hardfork::Berlin => 0,
+            _ => opcode.base_gas(),
         }
     }
 }
 
+const WARM_STORAGE_READ_COST: u64 = 100;
+const COLD_SLOAD_COST: u64 = 2100;
+const COLD_ACCOUNT_ACCESS_COST: u64 = 2600;
+const SSTORE_SET_COST: u64 = 20000;
+const SSTORE_RESET_COST: u64 = 5000 - COLD_SLOAD_COST;
+const SSTORE_STIPEND: u64 = 2300;
+const TIMEOUT_CHECK_INTERVAL: u64 = 1024;
+const SSTORE_CLEARS_REFUND_BERLIN: i64 = 15000;
+const SSTORE_CLEARS_REFUND: i64 = 4800;
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+// Memory never grows past this; reaching it would cost over 10^13 gas
+const MAX_MEMORY_BYTES: u64 = u32::MAX as u64;
+
+// Offset and size of a memory region as stack words. A zero-sized region touches nothing wherever
+// it points. Regions ending past MAX_MEMORY_BYTES run out of gas on their true size instead of
+// being truncated to their low 64 bits
+fn memory_range(offset: U256, size: U256) -> Result<(usize, usize), ExecutionResult> {
+    if size.is_zero() {
+        return Ok((0, 0));
+    }
+    match offset.checked_add(size) {
+        Some(end) if end <= U256::from(MAX_MEMORY_BYTES) => Ok((offset.to::<usize>(), size.to::<usize>())),
+        _ => Err(ExecutionResult::OutOfGas),
+    }
+}
+
+// Jump targets and calldata or return data offsets, where anything too large is simply out of range
+fn saturating_usize(value: U256) -> usize {
+    usize::try_from(value).unwrap_or(usize::MAX)
+}
+
+// Marks `key` as accessed and prices the access by whether it already was
+fn access_cost<T: std::hash::Hash + Eq>(accessed: &mut HashSet<T>, key: T, warm: u64, cold: u64) -> u64 {
+    if accessed.insert(key) { cold } else { warm }
+}
+
 impl Frame {
+    fn charge_gas(&mut self, cost: u64) -> Result<(), ExecutionResult> {
+        if self.gas < cost {
+            self.gas = 0;
+            return Err(ExecutionResult::OutOfGas);
+        }
+        self.gas -= cost;
+        Ok(())
+    }
+
     fn charge_memory_expansion_gas(&mut self, offset: usize, size: usize) -> Result<(), ExecutionResult> {
         let new_size_bytes = offset.saturating_add(size);
         if new_size_bytes == 0 {
@@ -465,7 +1261,7 @@ impl Frame {
 
     fn read_opcode(&mut self) -> u8 {
         if self.pc >= self.code.len() {
-            return STOP;
+            return Opcode::Stop as u8;
         }
         let opcode = self.code[self.pc];
         self.pc += 1;
@@ -0,0 +1,131 @@
+use crate::evm::{Account, AsyncHost, Host};
+use alloy::eips::BlockId;
+use alloy::primitives::Address;
+use alloy::providers::{Provider, RootProvider};
+use ruint::aliases::U256;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tokio::runtime::Runtime;
+use url::Url;
+
+// Host that pulls missing state from a JSON-RPC node at a pinned block. As a sync Host,
+// requests are driven on a private runtime, so inside a tokio application create it with
+// `connect` and run it through `Machine::call_async` instead
+#[derive(Debug)]
+pub struct ForkHost {
+    provider: RootProvider,
+    block: BlockId,
+    runtime: Option<Rc<Runtime>>,
+    accounts: HashMap<Address, Account>,
+    storage: HashMap<(Address, U256), U256>,
+    code: HashMap<Address, Vec<u8>>,
+}
+
+impl ForkHost {
+    // Pins `block_number`, or the node's latest block when None
+    pub fn new(rpc_url: Url, block_number: Option<u64>) -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|e| e.to_string())?;
+        let mut host = runtime.block_on(Self::connect(rpc_url, block_number))?;
+        host.runtime = Some(Rc::new(runtime));
+        Ok(host)
+    }
+
+    pub async fn connect(rpc_url: Url, block_number: Option<u64>) -> Result<Self, String> {
+        let provider = RootProvider::new_http(rpc_url);
+        let block_number = match block_number {
+            Some(number) => number,
+            None => provider.get_block_number().await.map_err(|e| e.to_string())?,
+        };
+
+        Ok(Self {
+            provider,
+            block: BlockId::number(block_number),
+            runtime: None,
+            accounts: HashMap::new(),
+            storage: HashMap::new(),
+            code: HashMap::new(),
+        })
+    }
+
+    // Reads FORK_URL and the optional FORK_BLOCK from the environment (or .env)
+    pub fn from_env() -> Result<Self, String> {
+        dotenv::dotenv().ok();
+        let rpc_url = std::env::var("FORK_URL").map_err(|_| "FORK_URL is not set".to_string())?;
+        let rpc_url = Url::parse(&rpc_url).map_err(|e| e.to_string())?;
+        let block_number = match std::env::var("FORK_BLOCK") {
+            Ok(number) => Some(number.parse().map_err(|_| format!("invalid FORK_BLOCK {}", number))?),
+            Err(_) => None,
+        };
+        Self::new(rpc_url, block_number)
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.block.as_u64().unwrap()
+    }
+
+    // Built on first sync use when the host came from `connect`
+    fn runtime(&mut self) -> Result<Rc<Runtime>, String> {
+        if self.runtime.is_none() {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|e| e.to_string())?;
+            self.runtime = Some(Rc::new(runtime));
+        }
+        Ok(self.runtime.clone().unwrap())
+    }
+}
+
+impl Host for ForkHost {
+    fn basic(&mut self, address: Address) -> Result<Account, String> {
+        self.runtime()?.block_on(AsyncHost::basic(self, address))
+    }
+
+    fn storage(&mut self, address: Address, key: U256) -> Result<U256, String> {
+        self.runtime()?.block_on(AsyncHost::storage(self, address, key))
+    }
+
+    fn code(&mut self, address: Address) -> Result<Vec<u8>, String> {
+        self.runtime()?.block_on(AsyncHost::code(self, address))
+    }
+}
+
+impl AsyncHost for ForkHost {
+    async fn basic(&mut self, address: Address) -> Result<Account, String> {
+        if let Some(account) = self.accounts.get(&address) {
+            return Ok(account.clone());
+        }
+
+        // code is only downloaded once the account is actually called
+        let (balance, nonce) = tokio::try_join!(
+            self.provider.get_balance(address).block_id(self.block),
+            self.provider.get_transaction_count(address).block_id(self.block),
+        ).map_err(|e| e.to_string())?;
+
+        let account = Account {
+            balance,
+            nonce,
+            lazy_code: true,
+            ..Default::default()
+        };
+        self.accounts.insert(address, account.clone());
+        Ok(account)
+    }
+
+    async fn storage(&mut self, address: Address, key: U256) -> Result<U256, String> {
+        if let Some(value) = self.storage.get(&(address, key)) {
+            return Ok(*value);
+        }
+
+        let value = self.provider.get_storage_at(address, key).block_id(self.block).await.map_err(|e| e.to_string())?;
+        self.storage.insert((address, key), value);
+        Ok(value)
+    }
+
+    async fn code(&mut self, address: Address) -> Result<Vec<u8>, String> {
+        if let Some(code) = self.code.get(&address) {
+            return Ok(code.clone());
+        }
+
+        let code = self.provider.get_code_at(address).block_id(self.block).await.map_err(|e| e.to_string())?.to_vec();
+        self.code.insert(address, code.clone());
+        Ok(code)
+    }
+}
@@ -0,0 +1,179 @@
+use crate::builder::BytecodeBuilder;
+use crate::compiler::Rng;
+use crate::evm::{ExecutionResult, Machine};
+use crate::opcode::Opcode;
+use ruint::aliases::U256;
+use std::collections::HashMap;
+use std::time::Instant;
+
+// Opcodes candidates are built from. Control flow, calls and halts are left to the surrounding
+// loop, and hardfork-gated opcodes are left out so every candidate runs on the default machine
+const CANDIDATE_OPCODES: &[Opcode] = &[
+    Opcode::Add, Opcode::Mul, Opcode::Sub, Opcode::Div, Opcode::Lt, Opcode::Gt, Opcode::Eq, Opcode::IsZero,
+    Opcode::Sha3, Opcode::Caller, Opcode::CallDataLoad, Opcode::ReturnDataSize, Opcode::BlockHash, Opcode::Coinbase,
+    Opcode::Timestamp, Opcode::Number, Opcode::GasLimit, Opcode::Pop, Opcode::MLoad, Opcode::MStore, Opcode::SLoad,
+    Opcode::SStore, Opcode::JumpDest, Opcode::Dup1, Opcode::Dup8, Opcode::Dup16, Opcode::Swap1, Opcode::Swap8,
+    Opcode::Swap16, Opcode::Log0, Opcode::Log2, Opcode::Log4,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchConfig {
+    pub seed: u64,
+    // candidates evaluated after the initial population
+    pub iterations: usize,
+    pub population: usize,
+    // gadgets in a random candidate
+    pub gadgets: usize,
+    // every candidate runs an endless loop until this much gas is gone
+    pub gas_limit: u64,
+    // runs per candidate; the fastest counts, to keep scheduler noise out
+    pub repeats: usize,
+    // findings reported
+    pub keep: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self { seed: 1, iterations: 500, population: 16, gadgets: 8, gas_limit: 200_000, repeats: 3, keep: 5 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub code: Vec<u8>,
+    pub nanos: u64,
+    pub gas: u64,
+    pub ns_per_gas: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchReport {
+    // slowest per unit of gas first
+    pub findings: Vec<Finding>,
+    pub evaluated: usize,
+}
+
+// One opcode with constant operands pushed in front and its results popped after, so gadgets
+// leave the stack as they found it and can be mutated independently
+#[derive(Debug, Clone, PartialEq)]
+struct Gadget {
+    opcode: Opcode,
+    operands: Vec<U256>,
+}
+
+impl Gadget {
+    fn random(rng: &mut Rng) -> Self {
+        let opcode = CANDIDATE_OPCODES[rng.below(CANDIDATE_OPCODES.len())];
+        let operands = (0..opcode.stack_io().0).map(|_| operand(rng, takes_offsets(opcode))).collect();
+        Gadget { opcode, operands }
+    }
+
+    fn emit(&self, code: &mut BytecodeBuilder) {
+        for operand in self.operands.iter().rev() {
+            code.push(*operand);
+        }
+        code.op(self.opcode);
+        for _ in 0..self.opcode.stack_io().1 {
+            code.pop();
+        }
+    }
+}
+
+// Opcodes whose operands are offsets and sizes. They only get small ones, so a candidate burns its
+// gas over many iterations instead of in one huge memory expansion
+fn takes_offsets(opcode: Opcode) -> bool {
+    matches!(opcode, Opcode::Sha3 | Opcode::CallDataLoad | Opcode::MLoad | Opcode::MStore) || opcode.is_log()
+}
+
+// Mostly small values, with some full words for everything but offsets
+fn operand(rng: &mut Rng, small: bool) -> U256 {
+    match rng.below(4) {
+        0 => U256::ZERO,
+        3 if !small => U256::from_limbs([rng.next(), rng.next(), rng.next(), rng.next()]),
+        _ => U256::from(rng.below(1024)),
+    }
+}
+
+fn assemble(gadgets: &[Gadget]) -> Vec<u8> {
+    let mut code = BytecodeBuilder::new();
+    code.jumpdest("start");
+    gadgets.iter().for_each(|gadget| gadget.emit(&mut code));
+    code.jump("start");
+    code.build().expect("the loop label is defined")
+}
+
+// Replaces, inserts or drops a gadget, or rerolls one operand
+fn mutate(gadgets: &[Gadget], rng: &mut Rng) -> Vec<Gadget> {
+    let mut gadgets = gadgets.to_vec();
+    let at = rng.below(gadgets.len());
+    match rng.below(4) {
+        0 => gadgets[at] = Gadget::random(rng),
+        1 => gadgets.insert(at, Gadget::random(rng)),
+        2 if gadgets.len() > 1 => {
+            gadgets.remove(at);
+        }
+        _ => {
+            let operands = gadgets[at].operands.len();
+            if operands > 0 {
+                gadgets[at].operands[rng.below(operands)] = operand(rng, takes_offsets(gadgets[at].opcode));
+            }
+        }
+    }
+    gadgets
+}
+
+// Time to burn the whole gas limit, or None when the candidate stops some other way
+fn measure(code: &[u8], config: &SearchConfig) -> Option<Finding> {
+    let mut nanos = u64::MAX;
+    for _ in 0..config.repeats.max(1) {
+        let mut machine = Machine::new(code.to_vec(), vec![], HashMap::new(), config.gas_limit);
+        let started = Instant::now();
+        let result = machine.run();
+        let elapsed = started.elapsed().as_nanos() as u64;
+        if result != ExecutionResult::OutOfGas {
+            return None;
+        }
+        nanos = nanos.min(elapsed);
+    }
+    Some(Finding { code: code.to_vec(), nanos, gas: config.gas_limit, ns_per_gas: nanos as f64 / config.gas_limit as f64 })
+}
+
+// Looks for code this interpreter runs slowest relative to the gas it charges, which is where
+// metering is too cheap. Starts from random loops of stack-neutral gadgets, then keeps mutating
+// the slower candidates and replacing the fastest one whenever a mutant beats it
+pub fn search_worst_case(config: SearchConfig) -> SearchReport {
+    let mut rng = Rng(config.seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1);
+    let mut report = SearchReport::default();
+    let mut population: Vec<(Vec<Gadget>, Finding)> = Vec::new();
+
+    let mut attempts = 0;
+    while population.len() < config.population.max(1) && attempts < config.population.max(1) * 10 {
+        attempts += 1;
+        let gadgets: Vec<Gadget> = (0..config.gadgets.max(1)).map(|_| Gadget::random(&mut rng)).collect();
+        report.evaluated += 1;
+        if let Some(finding) = measure(&assemble(&gadgets), &config) {
+            population.push((gadgets, finding));
+        }
+    }
+
+    for _ in 0..config.iterations {
+        if population.is_empty() {
+            break;
+        }
+        population.sort_by(|a, b| b.1.ns_per_gas.total_cmp(&a.1.ns_per_gas));
+        let parent = rng.below(population.len().div_ceil(2));
+        let child = mutate(&population[parent].0, &mut rng);
+        report.evaluated += 1;
+        let Some(finding) = measure(&assemble(&child), &config) else {
+            continue;
+        };
+        let last = population.len() - 1;
+        if finding.ns_per_gas > population[last].1.ns_per_gas {
+            population[last] = (child, finding);
+        }
+    }
+
+    population.sort_by(|a, b| b.1.ns_per_gas.total_cmp(&a.1.ns_per_gas));
+    report.findings = population.into_iter().map(|(_, finding)| finding).take(config.keep).collect();
+    report
+}
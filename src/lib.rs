@@ -1 +1,36 @@
+pub mod abi;
+pub mod access_list;
+pub mod artifacts;
+pub mod asm;
+pub mod assertions;
+pub mod binary_trace;
+pub mod block;
+pub mod builder;
+pub mod bundle;
+pub mod cfg;
+pub mod chain;
+pub mod compiler;
+pub mod events;
 pub mod evm;
+pub mod fork;
+pub mod gas_search;
+pub mod metadata;
+pub mod opcode;
+pub mod overrides;
+pub mod profiler;
+pub mod receipt;
+pub mod replay;
+pub mod scenario;
+pub mod shared;
+pub mod signed_tx;
+pub mod sol;
+pub mod state_dump;
+pub mod storage_layout;
+pub mod symbolic;
+pub mod tokens;
+pub mod tracer;
+pub mod validate;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "disk")]
+pub mod disk;
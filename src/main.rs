@@ -1,30 +1,128 @@
-use native_vs_evm::evm::{ExecutionResult, Machine};
-use std::collections::HashMap;
+use native_vs_evm::evm::ExecutionResult;
+use native_vs_evm::gas_search::{search_worst_case, SearchConfig};
+use native_vs_evm::opcode::disassemble;
+use native_vs_evm::scenario::{self, Scenario};
+use native_vs_evm::validate::validate_bytecode;
+use std::hint::black_box;
+use std::process;
+use std::time::{Duration, Instant};
 
-fn main() {
-    // PUSH1 0x05, PUSH1 0x0a, ADD, PUSH1 0x00, MSTORE, PUSH1 0x20, PUSH1 0x00, RETURN
-    let bytecode = hex::decode("6005600a0160005260206000f3").unwrap();
+const DEFAULT_ITERATIONS: u32 = 10_000;
 
-    let mut machine = Machine::new(bytecode, vec![], HashMap::new(), 1_000_000);
-    let result = machine.run();
+const USAGE: &str = "usage: native-vs-evm [list]
+       native-vs-evm run <scenario> [--iterations N] [--validate]
+       native-vs-evm --search-gas";
 
-    println!("EVM execution finished.");
-    println!("Final state: {:?}", machine);
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    match result {
-        ExecutionResult::Success(return_data) => {
-            if return_data.is_empty() {
-                println!("Success! No data returned.");
-            } else {
-                println!("Success! Return data (hex): 0x{}", hex::encode(&return_data));
-            }
+    // programs this interpreter runs slowest per unit of gas, to spot underpriced opcodes
+    if args.iter().any(|arg| arg == "--search-gas") {
+        let report = search_worst_case(SearchConfig::default());
+        println!("Evaluated {} candidates, slowest per unit of gas first:", report.evaluated);
+        for finding in &report.findings {
+            println!("\n{:.2} ns/gas ({} ns for {} gas)", finding.ns_per_gas, finding.nanos, finding.gas);
+            print!("{}", disassemble(&finding.code));
         }
-        ExecutionResult::Revert(return_data) => {
-            println!("Execution reverted! Return data (hex): 0x{}", hex::encode(&return_data));
+        return;
+    }
+
+    let scenarios = scenario::builtin();
+    match args.first().map(String::as_str) {
+        None | Some("list") => list(&scenarios),
+        Some("run") => {
+            let Some(scenario) = args.get(1).and_then(|name| scenarios.iter().find(|scenario| &scenario.name == name)) else {
+                eprintln!("unknown or missing scenario\n");
+                list(&scenarios);
+                process::exit(2);
+            };
+            let iterations = match args.iter().position(|arg| arg == "--iterations").map(|at| args.get(at + 1).and_then(|n| n.parse().ok())) {
+                None => DEFAULT_ITERATIONS,
+                Some(Some(iterations)) if iterations > 0 => iterations,
+                Some(_) => fail("--iterations takes a positive number"),
+            };
+            if args.iter().any(|arg| arg == "--validate") {
+                validate(scenario);
+            }
+            run(scenario, iterations);
         }
-        ExecutionResult::OutOfGas => println!("Error: Out of Gas!"),
-        ExecutionResult::InvalidOpcode => println!("Error: Invalid Opcode!"),
-        ExecutionResult::InvalidJump => println!("Error: Invalid Jump Destination!"),
-        ExecutionResult::StackUnderflow => println!("Error: Stack Underflow!"),
+        Some(_) => fail(USAGE),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(2);
+}
+
+fn list(scenarios: &[Scenario]) {
+    println!("Scenarios (run one with `native-vs-evm run <name>`):");
+    for scenario in scenarios {
+        println!("  {:<10} {}", scenario.name, scenario.description);
+    }
+}
+
+fn validate(scenario: &Scenario) {
+    let diagnostics = validate_bytecode(&scenario.code);
+    for diagnostic in &diagnostics {
+        println!("Validation: {:?}", diagnostic);
+    }
+    if !diagnostics.is_empty() {
+        process::exit(1);
+    }
+}
+
+// Both sides once to check they agree, then each timed over `iterations` runs
+fn run(scenario: &Scenario, iterations: u32) {
+    println!("{}: {}", scenario.name, scenario.description);
+    if let Err(mismatch) = scenario.verify() {
+        println!("Native and EVM sides disagree: {:?}", mismatch);
+        process::exit(1);
+    }
+
+    let mut storage = scenario.storage.clone();
+    let native = scenario.run_native(&mut storage);
+    let native_time = time(iterations, || {
+        black_box(scenario.run_native(&mut storage));
+    });
+
+    let (result, machine) = scenario.run_evm();
+    let gas_used = scenario.gas_limit - machine.gas_left();
+    let evm_time = time(iterations, || {
+        black_box(scenario.run_evm());
+    });
+
+    println!("Native output: {:#x}", native);
+    println!("EVM output:    {}", describe(&result));
+    println!("EVM gas used:  {}", gas_used);
+    let stats = machine.stats();
+    println!(
+        "EVM resources: {} steps, stack peak {}, memory peak {} bytes, {} frames",
+        stats.steps, stats.max_stack_height, stats.max_memory, stats.frames
+    );
+    println!("Native:        {:?} per run", native_time);
+    println!("EVM:           {:?} per run", evm_time);
+    println!("Slowdown:      {:.0}x", evm_time.as_secs_f64() / native_time.as_secs_f64().max(f64::MIN_POSITIVE));
+}
+
+fn time(iterations: u32, mut f: impl FnMut()) -> Duration {
+    let started = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    started.elapsed() / iterations
+}
+
+fn describe(result: &ExecutionResult) -> String {
+    match result {
+        ExecutionResult::Success(return_data) => format!("0x{}", hex::encode(return_data)),
+        ExecutionResult::Revert(return_data) => format!("reverted with 0x{}", hex::encode(return_data)),
+        ExecutionResult::OutOfGas => "Error: Out of Gas!".into(),
+        ExecutionResult::InvalidOpcode => "Error: Invalid Opcode!".into(),
+        ExecutionResult::InvalidJump => "Error: Invalid Jump Destination!".into(),
+        ExecutionResult::StackUnderflow => "Error: Stack Underflow!".into(),
+        ExecutionResult::StackOverflow => "Error: Stack Overflow!".into(),
+        ExecutionResult::Timeout => "Error: Timed Out!".into(),
+        ExecutionResult::HostError(e) => format!("Error: Host failed to load state: {}", e),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,173 @@
+use alloy::primitives::B256;
+use std::fmt;
+
+// solc appends a CBOR map to deployed code, followed by its length as two big-endian bytes:
+// hashes of the metadata JSON (`ipfs`, or `bzzr0`/`bzzr1` in older compilers), the compiler
+// version and an `experimental` flag. None of it is ever executed
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolcVersion {
+    Release(u8, u8, u8),
+    // prereleases carry the full version string instead
+    Other(String),
+}
+
+impl fmt::Display for SolcVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolcVersion::Release(major, minor, patch) => write!(f, "{}.{}.{}", major, minor, patch),
+            SolcVersion::Other(version) => write!(f, "{}", version),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    // multihash of the metadata JSON, 0x1220 followed by its sha256
+    pub ipfs: Option<Vec<u8>>,
+    pub bzzr0: Option<B256>,
+    pub bzzr1: Option<B256>,
+    pub solc: Option<SolcVersion>,
+    pub experimental: bool,
+    // bytes at the end of the code the trailer takes, length included
+    pub size: usize,
+}
+
+impl Metadata {
+    // The CIDv0 ipfs resolves the metadata JSON by, "Qm..."
+    pub fn ipfs_cid(&self) -> Option<String> {
+        self.ipfs.as_deref().map(base58)
+    }
+}
+
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fields = Vec::new();
+        if let Some(solc) = &self.solc {
+            fields.push(format!("solc {}", solc));
+        }
+        if let Some(cid) = self.ipfs_cid() {
+            fields.push(format!("ipfs {}", cid));
+        }
+        if let Some(hash) = self.bzzr0 {
+            fields.push(format!("bzzr0 {}", hash));
+        }
+        if let Some(hash) = self.bzzr1 {
+            fields.push(format!("bzzr1 {}", hash));
+        }
+        if self.experimental {
+            fields.push("experimental".into());
+        }
+        write!(f, "{}", fields.join(", "))
+    }
+}
+
+// The trailer, if the code ends in a well-formed one with at least one key solc writes
+pub fn parse_metadata(code: &[u8]) -> Option<Metadata> {
+    let [.., high, low] = *code else {
+        return None;
+    };
+    let length = u16::from_be_bytes([high, low]) as usize;
+    let start = code.len().checked_sub(length + 2)?;
+    let mut cbor = Cbor { bytes: &code[start..code.len() - 2], pos: 0 };
+
+    let mut metadata = Metadata { size: length + 2, ..Default::default() };
+    let mut known = false;
+    let Some((5, entries)) = cbor.header() else {
+        return None;
+    };
+    for _ in 0..entries {
+        let Item::Text(key) = cbor.item()? else {
+            return None;
+        };
+        let value = cbor.item()?;
+        match (key, value) {
+            ("ipfs", Item::Bytes(hash)) => metadata.ipfs = Some(hash.to_vec()),
+            ("bzzr0", Item::Bytes(hash)) if hash.len() == 32 => metadata.bzzr0 = Some(B256::from_slice(hash)),
+            ("bzzr1", Item::Bytes(hash)) if hash.len() == 32 => metadata.bzzr1 = Some(B256::from_slice(hash)),
+            ("solc", Item::Bytes(&[major, minor, patch])) => metadata.solc = Some(SolcVersion::Release(major, minor, patch)),
+            ("solc", Item::Text(version)) => metadata.solc = Some(SolcVersion::Other(version.to_string())),
+            ("experimental", Item::Bool(experimental)) => metadata.experimental = experimental,
+            // keys from newer compilers are skipped
+            _ => continue,
+        }
+        known = true;
+    }
+    (known && cbor.pos == cbor.bytes.len()).then_some(metadata)
+}
+
+// The code without its metadata trailer, or all of it when there is none. Jump destinations and
+// code size then only count what can actually run
+pub fn strip_metadata(code: &[u8]) -> &[u8] {
+    match parse_metadata(code) {
+        Some(metadata) => &code[..code.len() - metadata.size],
+        None => code,
+    }
+}
+
+// The subset of CBOR solc writes: definite-length maps, byte and text strings, unsigned
+// integers and booleans
+enum Item<'a> {
+    Uint,
+    Bytes(&'a [u8]),
+    Text(&'a str),
+    Bool(bool),
+}
+
+struct Cbor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cbor<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        let taken = self.bytes.get(self.pos..self.pos.checked_add(count)?)?;
+        self.pos += count;
+        Some(taken)
+    }
+
+    // Major type and argument
+    fn header(&mut self) -> Option<(u8, u64)> {
+        let initial = self.take(1)?[0];
+        let argument = match initial & 0x1f {
+            info @ 0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().ok()?) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().ok()?) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().ok()?),
+            _ => return None,
+        };
+        Some((initial >> 5, argument))
+    }
+
+    fn item(&mut self) -> Option<Item<'a>> {
+        match self.header()? {
+            (0, _) => Some(Item::Uint),
+            (2, length) => Some(Item::Bytes(self.take(usize::try_from(length).ok()?)?)),
+            (3, length) => Some(Item::Text(std::str::from_utf8(self.take(usize::try_from(length).ok()?)?).ok()?)),
+            (7, 20) => Some(Item::Bool(false)),
+            (7, 21) => Some(Item::Bool(true)),
+            _ => None,
+        }
+    }
+}
+
+fn base58(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    // little-endian base-58 digits
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in bytes {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    std::iter::repeat_n('1', zeros).chain(digits.iter().rev().map(|&digit| ALPHABET[digit as usize] as char)).collect()
+}
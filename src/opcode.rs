@@ -0,0 +1,266 @@
+use crate::metadata::{parse_metadata, strip_metadata};
+use std::fmt;
+
+// Declares the opcode table once: byte, mnemonic, stack items popped and pushed, and the gas
+// charged up front. Everything else about an opcode is derived from these rows
+macro_rules! opcodes {
+    ($($name:ident = $byte:literal, $mnemonic:literal, $inputs:literal, $outputs:literal, $gas:expr;)*) => {
+        // Every opcode the machine implements, plus INVALID. Bytes outside the table are undefined
+        // and halt with InvalidOpcode, like INVALID itself
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[repr(u8)]
+        pub enum Opcode {
+            $($name = $byte,)*
+        }
+
+        impl Opcode {
+            pub const ALL: &[Opcode] = &[$(Opcode::$name,)*];
+
+            pub const fn mnemonic(self) -> &'static str {
+                match self {
+                    $(Opcode::$name => $mnemonic,)*
+                }
+            }
+
+            // Stack items taken and left behind
+            pub const fn stack_io(self) -> (usize, usize) {
+                match self {
+                    $(Opcode::$name => ($inputs, $outputs),)*
+                }
+            }
+
+            // Gas charged before the instruction runs. Memory expansion, copies, storage and call
+            // costs come on top, as do the access costs that replace this from Berlin on
+            pub const fn base_gas(self) -> u64 {
+                match self {
+                    $(Opcode::$name => $gas,)*
+                }
+            }
+        }
+
+        impl TryFrom<u8> for Opcode {
+            // the undefined byte
+            type Error = u8;
+
+            fn try_from(byte: u8) -> Result<Self, u8> {
+                match byte {
+                    $($byte => Ok(Opcode::$name),)*
+                    _ => Err(byte),
+                }
+            }
+        }
+    };
+}
+
+opcodes! {
+    Stop = 0x00, "STOP", 0, 0, 0;
+    Add = 0x01, "ADD", 2, 1, 3;
+    Mul = 0x02, "MUL", 2, 1, 5;
+    Sub = 0x03, "SUB", 2, 1, 3;
+    Div = 0x04, "DIV", 2, 1, 5;
+    Lt = 0x10, "LT", 2, 1, 3;
+    Gt = 0x11, "GT", 2, 1, 3;
+    Eq = 0x14, "EQ", 2, 1, 3;
+    IsZero = 0x15, "ISZERO", 1, 1, 3;
+    Sha3 = 0x20, "SHA3", 2, 1, 30;
+    Caller = 0x33, "CALLER", 0, 1, 2;
+    CallDataLoad = 0x35, "CALLDATALOAD", 1, 1, 0;
+    ReturnDataSize = 0x3d, "RETURNDATASIZE", 0, 1, 0;
+    ReturnDataCopy = 0x3e, "RETURNDATACOPY", 3, 0, 0;
+    BlockHash = 0x40, "BLOCKHASH", 1, 1, 20;
+    Coinbase = 0x41, "COINBASE", 0, 1, 2;
+    Timestamp = 0x42, "TIMESTAMP", 0, 1, 2;
+    Number = 0x43, "NUMBER", 0, 1, 2;
+    GasLimit = 0x45, "GASLIMIT", 0, 1, 2;
+    BaseFee = 0x48, "BASEFEE", 0, 1, 2;
+    BlobHash = 0x49, "BLOBHASH", 1, 1, 3;
+    Pop = 0x50, "POP", 1, 0, 3;
+    MLoad = 0x51, "MLOAD", 1, 1, 3;
+    MStore = 0x52, "MSTORE", 2, 0, 3;
+    SLoad = 0x54, "SLOAD", 1, 1, 800;
+    SStore = 0x55, "SSTORE", 2, 0, 20000;
+    Jump = 0x56, "JUMP", 1, 0, 8;
+    JumpI = 0x57, "JUMPI", 2, 0, 10;
+    JumpDest = 0x5b, "JUMPDEST", 0, 0, 0;
+    TLoad = 0x5c, "TLOAD", 1, 1, 100;
+    TStore = 0x5d, "TSTORE", 2, 0, 100;
+    Push1 = 0x60, "PUSH1", 0, 1, 3;
+    Push2 = 0x61, "PUSH2", 0, 1, 3;
+    Push3 = 0x62, "PUSH3", 0, 1, 3;
+    Push4 = 0x63, "PUSH4", 0, 1, 3;
+    Push5 = 0x64, "PUSH5", 0, 1, 3;
+    Push6 = 0x65, "PUSH6", 0, 1, 3;
+    Push7 = 0x66, "PUSH7", 0, 1, 3;
+    Push8 = 0x67, "PUSH8", 0, 1, 3;
+    Push9 = 0x68, "PUSH9", 0, 1, 3;
+    Push10 = 0x69, "PUSH10", 0, 1, 3;
+    Push11 = 0x6a, "PUSH11", 0, 1, 3;
+    Push12 = 0x6b, "PUSH12", 0, 1, 3;
+    Push13 = 0x6c, "PUSH13", 0, 1, 3;
+    Push14 = 0x6d, "PUSH14", 0, 1, 3;
+    Push15 = 0x6e, "PUSH15", 0, 1, 3;
+    Push16 = 0x6f, "PUSH16", 0, 1, 3;
+    Push17 = 0x70, "PUSH17", 0, 1, 3;
+    Push18 = 0x71, "PUSH18", 0, 1, 3;
+    Push19 = 0x72, "PUSH19", 0, 1, 3;
+    Push20 = 0x73, "PUSH20", 0, 1, 3;
+    Push21 = 0x74, "PUSH21", 0, 1, 3;
+    Push22 = 0x75, "PUSH22", 0, 1, 3;
+    Push23 = 0x76, "PUSH23", 0, 1, 3;
+    Push24 = 0x77, "PUSH24", 0, 1, 3;
+    Push25 = 0x78, "PUSH25", 0, 1, 3;
+    Push26 = 0x79, "PUSH26", 0, 1, 3;
+    Push27 = 0x7a, "PUSH27", 0, 1, 3;
+    Push28 = 0x7b, "PUSH28", 0, 1, 3;
+    Push29 = 0x7c, "PUSH29", 0, 1, 3;
+    Push30 = 0x7d, "PUSH30", 0, 1, 3;
+    Push31 = 0x7e, "PUSH31", 0, 1, 3;
+    Push32 = 0x7f, "PUSH32", 0, 1, 3;
+    Dup1 = 0x80, "DUP1", 1, 2, 3;
+    Dup2 = 0x81, "DUP2", 2, 3, 3;
+    Dup3 = 0x82, "DUP3", 3, 4, 3;
+    Dup4 = 0x83, "DUP4", 4, 5, 3;
+    Dup5 = 0x84, "DUP5", 5, 6, 3;
+    Dup6 = 0x85, "DUP6", 6, 7, 3;
+    Dup7 = 0x86, "DUP7", 7, 8, 3;
+    Dup8 = 0x87, "DUP8", 8, 9, 3;
+    Dup9 = 0x88, "DUP9", 9, 10, 3;
+    Dup10 = 0x89, "DUP10", 10, 11, 3;
+    Dup11 = 0x8a, "DUP11", 11, 12, 3;
+    Dup12 = 0x8b, "DUP12", 12, 13, 3;
+    Dup13 = 0x8c, "DUP13", 13, 14, 3;
+    Dup14 = 0x8d, "DUP14", 14, 15, 3;
+    Dup15 = 0x8e, "DUP15", 15, 16, 3;
+    Dup16 = 0x8f, "DUP16", 16, 17, 3;
+    Swap1 = 0x90, "SWAP1", 2, 2, 3;
+    Swap2 = 0x91, "SWAP2", 3, 3, 3;
+    Swap3 = 0x92, "SWAP3", 4, 4, 3;
+    Swap4 = 0x93, "SWAP4", 5, 5, 3;
+    Swap5 = 0x94, "SWAP5", 6, 6, 3;
+    Swap6 = 0x95, "SWAP6", 7, 7, 3;
+    Swap7 = 0x96, "SWAP7", 8, 8, 3;
+    Swap8 = 0x97, "SWAP8", 9, 9, 3;
+    Swap9 = 0x98, "SWAP9", 10, 10, 3;
+    Swap10 = 0x99, "SWAP10", 11, 11, 3;
+    Swap11 = 0x9a, "SWAP11", 12, 12, 3;
+    Swap12 = 0x9b, "SWAP12", 13, 13, 3;
+    Swap13 = 0x9c, "SWAP13", 14, 14, 3;
+    Swap14 = 0x9d, "SWAP14", 15, 15, 3;
+    Swap15 = 0x9e, "SWAP15", 16, 16, 3;
+    Swap16 = 0x9f, "SWAP16", 17, 17, 3;
+    Log0 = 0xa0, "LOG0", 2, 0, 375;
+    Log1 = 0xa1, "LOG1", 3, 0, 750;
+    Log2 = 0xa2, "LOG2", 4, 0, 1125;
+    Log3 = 0xa3, "LOG3", 5, 0, 1500;
+    Log4 = 0xa4, "LOG4", 6, 0, 1875;
+    Call = 0xf1, "CALL", 7, 1, 0;
+    Return = 0xf3, "RETURN", 2, 0, 0;
+    Revert = 0xfd, "REVERT", 2, 0, 0;
+    Invalid = 0xfe, "INVALID", 0, 0, 0;
+}
+
+impl Opcode {
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|op| op.mnemonic().eq_ignore_ascii_case(mnemonic))
+    }
+
+    // Bytes of immediate data following the opcode, only nonzero for PUSH1..PUSH32
+    pub fn immediate_size(self) -> usize {
+        if self.is_push() { (self as u8 - Opcode::Push1 as u8 + 1) as usize } else { 0 }
+    }
+
+    pub fn is_push(self) -> bool {
+        (Opcode::Push1..=Opcode::Push32).contains(&self)
+    }
+
+    pub fn is_dup(self) -> bool {
+        (Opcode::Dup1..=Opcode::Dup16).contains(&self)
+    }
+
+    pub fn is_swap(self) -> bool {
+        (Opcode::Swap1..=Opcode::Swap16).contains(&self)
+    }
+
+    pub fn is_log(self) -> bool {
+        (Opcode::Log0..=Opcode::Log4).contains(&self)
+    }
+
+    // Instructions after which execution never falls through to the next byte
+    pub fn is_terminator(self) -> bool {
+        matches!(self, Opcode::Stop | Opcode::Jump | Opcode::Return | Opcode::Revert | Opcode::Invalid)
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mnemonic())
+    }
+}
+
+// One decoded instruction. `immediate` is shorter than the opcode asks for when the code ends
+// inside a PUSH; the machine reads the missing bytes as zero
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instruction<'a> {
+    pub pc: usize,
+    pub byte: u8,
+    // None for undefined bytes
+    pub opcode: Option<Opcode>,
+    pub immediate: &'a [u8],
+}
+
+impl Instruction<'_> {
+    pub fn size(&self) -> usize {
+        1 + self.opcode.map_or(0, Opcode::immediate_size)
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.immediate.len() + 1 < self.size()
+    }
+}
+
+// `PUSH1 0x2a` style text, with undefined bytes spelled out
+impl fmt::Display for Instruction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.opcode {
+            Some(opcode) if opcode.is_push() => write!(f, "{} 0x{}", opcode, hex::encode(self.immediate)),
+            Some(opcode) => write!(f, "{}", opcode),
+            None => write!(f, "opcode {:#04x} not defined", self.byte),
+        }
+    }
+}
+
+// Walks code instruction by instruction, stepping over PUSH data
+#[derive(Debug, Clone)]
+pub struct Instructions<'a> {
+    code: &'a [u8],
+    pc: usize,
+}
+
+pub fn decode(code: &[u8]) -> Instructions<'_> {
+    Instructions { code, pc: 0 }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Instruction<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let &byte = self.code.get(self.pc)?;
+        let opcode = Opcode::try_from(byte).ok();
+        let start = self.pc + 1;
+        let end = (start + opcode.map_or(0, Opcode::immediate_size)).min(self.code.len());
+        let instruction = Instruction { pc: self.pc, byte, opcode, immediate: &self.code[start..end] };
+        self.pc = start + opcode.map_or(0, Opcode::immediate_size);
+        Some(instruction)
+    }
+}
+
+// Assembly listing, one `pc: instruction` line each. A solc metadata trailer gets one line of its
+// own instead of being decoded as instructions
+pub fn disassemble(code: &[u8]) -> String {
+    let instructions = strip_metadata(code);
+    let mut listing: String = decode(instructions).map(|instruction| format!("{}: {}\n", instruction.pc, instruction)).collect();
+    if let Some(metadata) = parse_metadata(code) {
+        listing.push_str(&format!("{}: metadata {}\n", instructions.len(), metadata));
+    }
+    listing
+}
@@ -0,0 +1,65 @@
+use crate::evm::{ExecutionResult, Machine, Transaction, TransactionError, TransactionOutcome};
+use alloy::primitives::Address;
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+// eth_call-style override of a single account. Fields left as None keep their current value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountOverride {
+    pub balance: Option<U256>,
+    pub nonce: Option<u64>,
+    pub code: Option<Vec<u8>>,
+    // replaces the whole storage, slots not listed read as zero
+    pub state: Option<HashMap<U256, U256>>,
+    // replaces only the listed slots
+    pub state_diff: HashMap<U256, U256>,
+}
+
+pub type StateOverride = HashMap<Address, AccountOverride>;
+
+impl Machine {
+    pub fn call_with_overrides(&mut self, caller: Address, to: Address, calldata: Vec<u8>, gas_limit: u64, overrides: &StateOverride) -> ExecutionResult {
+        let snapshot = self.accounts.clone();
+        let result = match self.apply_overrides(overrides) {
+            Ok(()) => self.call(caller, to, calldata, gas_limit),
+            Err(e) => ExecutionResult::HostError(e),
+        };
+        self.accounts = snapshot;
+        result
+    }
+
+    // `simulate` with the overrides in place for this one transaction
+    pub fn simulate_with_overrides(&mut self, tx: &Transaction, overrides: &StateOverride) -> Result<TransactionOutcome, TransactionError> {
+        let snapshot = self.accounts.clone();
+        let outcome = match self.apply_overrides(overrides) {
+            Ok(()) => self.transact(tx),
+            Err(e) => Err(TransactionError::HostError(e)),
+        };
+        self.accounts = snapshot;
+        outcome
+    }
+
+    fn apply_overrides(&mut self, overrides: &StateOverride) -> Result<(), String> {
+        for (address, account_override) in overrides {
+            // loaded first so `deploy` keeps the host's balance, nonce and storage
+            self.account(*address)?;
+            if let Some(code) = &account_override.code {
+                self.deploy(*address, code.clone());
+            }
+
+            let account = self.account(*address)?;
+            if let Some(balance) = account_override.balance {
+                account.balance = balance;
+            }
+            if let Some(nonce) = account_override.nonce {
+                account.nonce = nonce;
+            }
+            if let Some(state) = &account_override.state {
+                account.storage = state.clone();
+                account.storage_complete = true;
+            }
+            account.storage.extend(&account_override.state_diff);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use crate::evm::{Frame, Inspector, Machine};
+use crate::opcode::decode;
+use alloy::primitives::Address;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::time::Instant;
+
+// Attributes gas to call paths, `address:selector` frames joined by `;`, and writes them in the
+// folded-stack format inferno and flamegraph.pl take. Gas forwarded by a CALL is charged to the
+// callee, so every frame only shows what it burned itself
+#[derive(Debug, Default)]
+pub struct GasFlamegraph {
+    labels: Vec<String>,
+    path: String,
+    folded: BTreeMap<String, u64>,
+}
+
+impl GasFlamegraph {
+    pub fn folded(&self) -> &BTreeMap<String, u64> {
+        &self.folded
+    }
+
+    pub fn write_folded<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (path, gas) in self.folded.iter().filter(|(_, gas)| **gas > 0) {
+            writeln!(writer, "{} {}", path, gas)?;
+        }
+        writer.flush()
+    }
+}
+
+fn frame_label(frame: &Frame) -> String {
+    match frame.calldata.get(..4) {
+        Some(selector) => format!("{}:0x{}", frame.callee, hex::encode(selector)),
+        None => frame.callee.to_string(),
+    }
+}
+
+impl Inspector for GasFlamegraph {
+    fn step(&mut self, machine: &Machine) {
+        let depth = machine.call_stack.len();
+        if self.labels.len() != depth {
+            self.labels.truncate(depth);
+            let entered = machine.call_stack[self.labels.len()..].iter().map(frame_label).collect::<Vec<_>>();
+            self.labels.extend(entered);
+            self.path = self.labels.join(";");
+        }
+    }
+
+    fn step_end(&mut self, machine: &Machine, gas_cost: u64) {
+        let gas = own_gas(machine, self.labels.len(), gas_cost);
+        match self.folded.get_mut(&self.path) {
+            Some(total) => *total += gas,
+            None => {
+                self.folded.insert(self.path.clone(), gas);
+            }
+        }
+    }
+}
+
+// Gas a step burned itself: a CALL that entered a frame hands that frame's gas over
+fn own_gas(machine: &Machine, depth: usize, gas_cost: u64) -> u64 {
+    if machine.call_stack.len() > depth {
+        gas_cost.saturating_sub(machine.call_stack.last().unwrap().gas)
+    } else {
+        gas_cost
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PcCost {
+    pub hits: u64,
+    pub gas: u64,
+    pub nanos: u64,
+}
+
+// Cachegrind-style profile: hits, gas and wall time per pc of every contract that ran, printed
+// next to its disassembly. Times include the inspector's own overhead, so compare them relatively
+#[derive(Debug, Default)]
+pub struct PcProfiler {
+    contracts: BTreeMap<Address, ContractProfile>,
+    pending: Option<(Address, usize, usize, Instant)>,
+}
+
+#[derive(Debug)]
+struct ContractProfile {
+    code: Rc<Vec<u8>>,
+    costs: BTreeMap<usize, PcCost>,
+}
+
+impl PcProfiler {
+    pub fn costs(&self, address: Address) -> Option<&BTreeMap<usize, PcCost>> {
+        self.contracts.get(&address).map(|profile| &profile.costs)
+    }
+
+    // Every instruction of every profiled contract, executed or not
+    pub fn write_annotated<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (address, ContractProfile { code, costs }) in &self.contracts {
+            writeln!(writer, "{}", address)?;
+            writeln!(writer, "{:>6}  {:<24} {:>10} {:>12} {:>12}", "pc", "instruction", "hits", "gas", "ns")?;
+            for instruction in decode(code) {
+                let cost = costs.get(&instruction.pc).copied().unwrap_or_default();
+                writeln!(writer, "{:>6}  {:<24} {:>10} {:>12} {:>12}", instruction.pc, instruction.to_string(), cost.hits, cost.gas, cost.nanos)?;
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+}
+
+impl Inspector for PcProfiler {
+    fn step(&mut self, machine: &Machine) {
+        let frame = machine.call_stack.last().unwrap();
+        self.contracts.entry(frame.callee).or_insert_with(|| ContractProfile { code: frame.code.clone(), costs: BTreeMap::new() });
+        self.pending = Some((frame.callee, frame.pc, machine.call_stack.len(), Instant::now()));
+    }
+
+    fn step_end(&mut self, machine: &Machine, gas_cost: u64) {
+        let Some((address, pc, depth, started)) = self.pending.take() else {
+            return;
+        };
+        let nanos = started.elapsed().as_nanos() as u64;
+        let cost = self.contracts.get_mut(&address).unwrap().costs.entry(pc).or_default();
+        cost.hits += 1;
+        cost.gas += own_gas(machine, depth, gas_cost);
+        cost.nanos += nanos;
+    }
+}
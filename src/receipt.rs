@@ -0,0 +1,43 @@
+use crate::evm::{ExecutionResult, TransactionOutcome};
+use alloy::primitives::{keccak256, Bloom, Log};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Receipt {
+    pub status: bool,
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<Log>,
+    pub logs_bloom: Bloom,
+}
+
+impl TransactionOutcome {
+    // `gas_used_before` is the cumulative gas of the transactions preceding this one in its block
+    pub fn receipt(&self, gas_used_before: u64) -> Receipt {
+        Receipt {
+            status: matches!(self.result, ExecutionResult::Success(_)),
+            cumulative_gas_used: gas_used_before + self.gas_used,
+            logs: self.logs.clone(),
+            logs_bloom: logs_bloom(&self.logs),
+        }
+    }
+}
+
+// Yellow paper M3:2048: every log address and topic sets three bits, each taken from
+// the low 11 bits of a byte pair of its keccak hash
+pub fn logs_bloom(logs: &[Log]) -> Bloom {
+    let mut bloom = [0u8; 256];
+    for log in logs {
+        accrue(&mut bloom, log.address.as_slice());
+        for topic in log.topics() {
+            accrue(&mut bloom, topic.as_slice());
+        }
+    }
+    Bloom::from(bloom)
+}
+
+fn accrue(bloom: &mut [u8; 256], input: &[u8]) {
+    let hash = keccak256(input);
+    for i in [0, 2, 4] {
+        let bit = (u16::from_be_bytes([hash[i], hash[i + 1]]) & 2047) as usize;
+        bloom[255 - bit / 8] |= 1 << (bit % 8);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::evm::{ExecutionResult, Inspector, Machine};
+use crate::tracer::StructLog;
+use alloy::primitives::Address;
+
+// The first step where a run parted from its recording. `actual` is None when the run stopped
+// early, `expected` when it kept going past the end of the recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub step: usize,
+    pub expected: Option<StructLog>,
+    pub actual: Option<StructLog>,
+    // names of the StructLog fields that differ, empty when one side is missing
+    pub fields: Vec<&'static str>,
+}
+
+// Checks a run step by step against a recorded trace, from StructLogger or a binary trace read
+// back with TraceReader. Execution carries on after a divergence; only the first one is kept
+#[derive(Debug)]
+pub struct ReplayVerifier {
+    recorded: Vec<StructLog>,
+    position: usize,
+    pending: Option<StructLog>,
+    divergence: Option<Divergence>,
+}
+
+impl ReplayVerifier {
+    pub fn new(recorded: Vec<StructLog>) -> Self {
+        Self { recorded, position: 0, pending: None, divergence: None }
+    }
+
+    pub fn finish(self) -> Result<(), Box<Divergence>> {
+        if let Some(divergence) = self.divergence {
+            return Err(Box::new(divergence));
+        }
+        match self.recorded.get(self.position) {
+            Some(expected) => Err(Box::new(Divergence { step: self.position, expected: Some(expected.clone()), actual: None, fields: Vec::new() })),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Inspector for ReplayVerifier {
+    fn step(&mut self, machine: &Machine) {
+        if self.divergence.is_none() {
+            self.pending = Some(StructLog::capture(machine));
+        }
+    }
+
+    fn step_end(&mut self, _machine: &Machine, gas_cost: u64) {
+        let Some(mut actual) = self.pending.take() else {
+            return;
+        };
+        actual.gas_cost = gas_cost;
+        let step = self.position;
+        self.position += 1;
+        let Some(expected) = self.recorded.get(step) else {
+            self.divergence = Some(Divergence { step, expected: None, actual: Some(actual), fields: Vec::new() });
+            return;
+        };
+        let fields = differing_fields(expected, &actual);
+        if !fields.is_empty() {
+            self.divergence = Some(Divergence { step, expected: Some(expected.clone()), actual: Some(actual), fields });
+        }
+    }
+}
+
+fn differing_fields(expected: &StructLog, actual: &StructLog) -> Vec<&'static str> {
+    let checks = [
+        ("pc", expected.pc == actual.pc),
+        ("op", expected.op == actual.op),
+        ("gas", expected.gas == actual.gas),
+        ("gas_cost", expected.gas_cost == actual.gas_cost),
+        ("depth", expected.depth == actual.depth),
+        ("stack", expected.stack == actual.stack),
+    ];
+    checks.into_iter().filter(|(_, same)| !same).map(|(field, _)| field).collect()
+}
+
+impl Machine {
+    // Re-executes a call from the state the recording started in and verifies every step
+    pub fn replay(&mut self, caller: Address, to: Address, calldata: Vec<u8>, gas_limit: u64, recorded: Vec<StructLog>) -> Result<ExecutionResult, Box<Divergence>> {
+        let mut verifier = ReplayVerifier::new(recorded);
+        let result = self.call_with_inspector(caller, to, calldata, gas_limit, &mut verifier);
+        verifier.finish()?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,286 @@
+use crate::evm::{ExecutionResult, Machine, Transaction, TransactionError, TransactionOutcome};
+use crate::overrides::{AccountOverride, StateOverride};
+use crate::signed_tx::{SignedTransaction, SignedTransactionError};
+use crate::tracer::{opcode_name, StructLogger};
+use alloy::primitives::map::B256HashMap;
+use alloy::primitives::{Address, TxKind, B256};
+use alloy::rpc::types::TransactionRequest;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use ruint::aliases::U256;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::net::SocketAddr;
+use std::rc::Rc;
+use tokio::net::TcpListener;
+use tokio::task::LocalSet;
+
+const DEFAULT_CALL_GAS: u64 = 30_000_000;
+
+#[derive(Debug, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self { code: -32602, message: message.into(), data: None }
+    }
+
+    fn server(message: impl Into<String>) -> Self {
+        Self { code: -32000, message: message.into(), data: None }
+    }
+}
+
+// JSON-RPC front end over a single Machine. Every accepted transaction is mined
+// into its own block right away, so the "chain" is just a block counter
+#[derive(Debug)]
+pub struct RpcServer {
+    pub machine: Machine,
+    pub chain_id: u64,
+    pub block_number: u64,
+}
+
+impl RpcServer {
+    pub fn new(machine: Machine, chain_id: u64) -> Self {
+        Self { machine, chain_id, block_number: 0 }
+    }
+
+    // Handles a raw JSON-RPC request body (single call or batch) and returns the response body
+    pub fn handle_request(&mut self, body: &[u8]) -> Value {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Array(batch)) => Value::Array(batch.iter().map(|request| self.handle_single(request)).collect()),
+            Ok(request) => self.handle_single(&request),
+            Err(e) => error_response(Value::Null, RpcError { code: -32700, message: e.to_string(), data: None }),
+        }
+    }
+
+    fn handle_single(&mut self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return error_response(id, RpcError { code: -32600, message: "invalid request".into(), data: None });
+        };
+        let params = request.get("params").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+
+        match self.dispatch(method, params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e),
+        }
+    }
+
+    fn dispatch(&mut self, method: &str, params: &[Value]) -> Result<Value, RpcError> {
+        match method {
+            "eth_chainId" => Ok(quantity(U256::from(self.chain_id))),
+            "eth_blockNumber" => Ok(quantity(U256::from(self.block_number))),
+            "eth_getBalance" => {
+                let address: Address = param(params, 0)?;
+                let balance = self.machine.account(address).map_err(RpcError::server)?.balance;
+                Ok(quantity(balance))
+            }
+            "eth_getTransactionCount" => {
+                let address: Address = param(params, 0)?;
+                let nonce = self.machine.account(address).map_err(RpcError::server)?.nonce;
+                Ok(quantity(U256::from(nonce)))
+            }
+            "eth_getCode" => {
+                let address: Address = param(params, 0)?;
+                let code = self.machine.code(address).map_err(RpcError::server)?.to_vec();
+                Ok(json!(format!("0x{}", hex::encode(code))))
+            }
+            "eth_getStorageAt" => {
+                let address: Address = param(params, 0)?;
+                let slot: U256 = param(params, 1)?;
+                let value = self.machine.storage(address, slot).map_err(RpcError::server)?;
+                Ok(json!(format!("0x{}", hex::encode(value.to_be_bytes::<32>()))))
+            }
+            "eth_call" => {
+                let tx = call_transaction(param(params, 0)?)?;
+                // params[1] is the block tag, only the current state is served
+                let overrides = match params.get(2) {
+                    Some(_) => state_override(param(params, 2)?),
+                    None => StateOverride::new(),
+                };
+                let outcome = self.machine.simulate_with_overrides(&tx, &overrides).map_err(transaction_error)?;
+                match outcome.result {
+                    ExecutionResult::Success(data) => Ok(json!(format!("0x{}", hex::encode(data)))),
+                    result => Err(execution_error(result)),
+                }
+            }
+            "eth_sendRawTransaction" => {
+                let raw: alloy::primitives::Bytes = param(params, 0)?;
+                let signed = SignedTransaction::decode(&raw).map_err(signed_transaction_error)?;
+                signed.check_chain_id(self.chain_id).map_err(signed_transaction_error)?;
+
+                self.machine.transact(&signed.transaction).map_err(transaction_error)?;
+                self.block_number += 1;
+                Ok(json!(signed.hash))
+            }
+            "eth_createAccessList" => {
+                let tx = call_transaction(param(params, 0)?)?;
+                let created = self.machine.create_access_list(&tx).map_err(transaction_error)?;
+                let access_list: Vec<Value> = created.access_list.iter().map(|(address, keys)| json!({
+                    "address": address,
+                    "storageKeys": keys.iter().map(|key| B256::from(*key)).collect::<Vec<_>>(),
+                })).collect();
+
+                let mut result = json!({ "accessList": access_list, "gasUsed": quantity(U256::from(created.outcome.gas_used)) });
+                if !matches!(created.outcome.result, ExecutionResult::Success(_)) {
+                    result["error"] = json!(execution_error(created.outcome.result).message);
+                }
+                Ok(result)
+            }
+            "debug_traceCall" => {
+                let tx = call_transaction(param(params, 0)?)?;
+                let mut logger = StructLogger::default();
+                let outcome = self.machine.simulate_with_inspector(&tx, &mut logger).map_err(transaction_error)?;
+                Ok(trace_result(&outcome, &logger))
+            }
+            _ => Err(RpcError { code: -32601, message: format!("method {} not found", method), data: None }),
+        }
+    }
+
+    // Serves JSON-RPC over HTTP until the listener fails. Machine is not Send,
+    // so connections are driven on a LocalSet and share the server through a RefCell
+    pub async fn serve(self, addr: SocketAddr) -> std::io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        let server = Rc::new(RefCell::new(self));
+
+        LocalSet::new().run_until(async move {
+            loop {
+                let (stream, _) = listener.accept().await?;
+                let server = server.clone();
+                tokio::task::spawn_local(async move {
+                    let service = service_fn(move |request| handle_http(server.clone(), request));
+                    let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+                });
+            }
+        }).await
+    }
+}
+
+async fn handle_http(server: Rc<RefCell<RpcServer>>, request: Request<Incoming>) -> Result<Response<Full<Bytes>>, hyper::Error> {
+    let body = request.into_body().collect().await?.to_bytes();
+    let response = server.borrow_mut().handle_request(&body);
+
+    Ok(Response::builder()
+        .header("content-type", "application/json")
+        .body(Full::new(Bytes::from(response.to_string())))
+        .unwrap())
+}
+
+fn param<T: serde::de::DeserializeOwned>(params: &[Value], index: usize) -> Result<T, RpcError> {
+    let value = params.get(index).cloned().ok_or_else(|| RpcError::invalid_params(format!("missing param {}", index)))?;
+    serde_json::from_value(value).map_err(|e| RpcError::invalid_params(e.to_string()))
+}
+
+fn call_transaction(request: TransactionRequest) -> Result<Transaction, RpcError> {
+    let Some(TxKind::Call(to)) = request.to else {
+        return Err(RpcError::invalid_params("contract creation is not supported"));
+    };
+
+    Ok(Transaction {
+        caller: request.from.unwrap_or_default(),
+        to,
+        value: request.value.unwrap_or_default(),
+        data: request.input.input().map(|data| data.to_vec()).unwrap_or_default(),
+        gas_limit: request.gas.unwrap_or(DEFAULT_CALL_GAS),
+        gas_price: U256::from(request.gas_price.or(request.max_fee_per_gas).unwrap_or_default()),
+        gas_priority_fee: request.max_priority_fee_per_gas.map(U256::from),
+        nonce: None,
+        access_list: request.access_list.map(|list| {
+            list.iter().map(|item| (item.address, item.storage_keys.iter().map(|key| U256::from_be_bytes(key.0)).collect())).collect()
+        }).unwrap_or_default(),
+        blob_versioned_hashes: request.blob_versioned_hashes.unwrap_or_default(),
+        max_fee_per_blob_gas: U256::from(request.max_fee_per_blob_gas.unwrap_or_default()),
+    })
+}
+
+fn state_override(overrides: alloy::rpc::types::state::StateOverride) -> StateOverride {
+    let slots = |slots: B256HashMap<B256>| {
+        slots.into_iter().map(|(key, value)| (U256::from_be_bytes(key.0), U256::from_be_bytes(value.0))).collect()
+    };
+
+    overrides.into_iter().map(|(address, account)| (address, AccountOverride {
+        balance: account.balance,
+        nonce: account.nonce,
+        code: account.code.map(|code| code.to_vec()),
+        state: account.state.map(slots),
+        state_diff: account.state_diff.map(slots).unwrap_or_default(),
+    })).collect()
+}
+
+fn trace_result(outcome: &TransactionOutcome, logger: &StructLogger) -> Value {
+    let (failed, return_value) = match &outcome.result {
+        ExecutionResult::Success(data) => (false, hex::encode(data)),
+        ExecutionResult::Revert(data) => (true, hex::encode(data)),
+        _ => (true, String::new()),
+    };
+    let struct_logs: Vec<Value> = logger.logs.iter().map(|log| json!({
+        "pc": log.pc,
+        "op": opcode_name(log.op),
+        "gas": log.gas,
+        "gasCost": log.gas_cost,
+        "depth": log.depth,
+        "stack": log.stack.iter().map(|value| quantity(*value)).collect::<Vec<_>>(),
+    })).collect();
+
+    json!({
+        "gas": outcome.gas_used,
+        "failed": failed,
+        "returnValue": return_value,
+        "structLogs": struct_logs,
+    })
+}
+
+fn execution_error(result: ExecutionResult) -> RpcError {
+    match result {
+        ExecutionResult::Revert(data) => RpcError {
+            code: 3,
+            message: "execution reverted".into(),
+            data: Some(json!(format!("0x{}", hex::encode(data)))),
+        },
+        result => RpcError::server(format!("{:?}", result)),
+    }
+}
+
+fn transaction_error(error: TransactionError) -> RpcError {
+    RpcError::server(match error {
+        TransactionError::NonceMismatch { expected, got } => format!("nonce mismatch: expected {}, got {}", expected, got),
+        TransactionError::InsufficientFunds => "insufficient funds for gas * price + value".into(),
+        TransactionError::IntrinsicGasTooLow => "intrinsic gas too low".into(),
+        TransactionError::FeeCapTooLow => "max fee per gas less than block base fee".into(),
+        TransactionError::BlobsNotActive => "blob transactions are not active before cancun".into(),
+        TransactionError::TooManyBlobs => "too many blobs in transaction".into(),
+        TransactionError::InvalidBlobHash => "blob versioned hash with unsupported version".into(),
+        TransactionError::BlobFeeCapTooLow => "max fee per blob gas less than block blob base fee".into(),
+        TransactionError::HostError(e) => e,
+    })
+}
+
+fn signed_transaction_error(error: SignedTransactionError) -> RpcError {
+    RpcError::invalid_params(match error {
+        SignedTransactionError::Decode(e) => format!("invalid transaction: {}", e),
+        SignedTransactionError::UnsupportedType(ty) => format!("transaction type {} not supported", ty),
+        SignedTransactionError::InvalidSignature(e) => format!("invalid signature: {}", e),
+        SignedTransactionError::ChainIdMismatch { expected, got } => format!("invalid chain id {}, expected {}", got, expected),
+        SignedTransactionError::ContractCreation => "contract creation is not supported".into(),
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    let mut body = json!({ "code": error.code, "message": error.message });
+    if let Some(data) = error.data {
+        body["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": body })
+}
+
+fn quantity(value: U256) -> Value {
+    json!(format!("{:#x}", value))
+}
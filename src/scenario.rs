@@ -0,0 +1,153 @@
+use crate::evm::{Account, ExecutionResult, Machine};
+use crate::evm_asm;
+use alloy::primitives::{keccak256, Address};
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+// Where scenario code is installed; storage prestates and side effects belong to this account
+pub const SCENARIO_ADDRESS: Address = Address::new([0x10; 20]);
+
+#[derive(Debug, PartialEq)]
+pub enum Mismatch {
+    // the EVM side did not return successfully
+    EvmFailed(ExecutionResult),
+    // the EVM side returned something other than the native result as one word
+    Output { native: U256, evm: Vec<u8> },
+    // a slot the two sides left with different values, for scenarios that compare storage
+    Storage { slot: U256, native: U256, evm: U256 },
+}
+
+type NativeFn = Box<dyn Fn(&mut HashMap<U256, U256>) -> U256>;
+
+// One computation written twice, natively and as bytecode. The bytecode returns its result as a
+// single word; stateful scenarios also have their storage compared with the native side's map
+pub struct Scenario {
+    pub name: String,
+    pub description: String,
+    pub code: Vec<u8>,
+    pub calldata: Vec<u8>,
+    pub storage: HashMap<U256, U256>,
+    pub gas_limit: u64,
+    native: NativeFn,
+    compare_storage: bool,
+}
+
+impl Scenario {
+    pub fn new(name: impl Into<String>, code: Vec<u8>, native: impl Fn() -> U256 + 'static) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            code,
+            calldata: Vec::new(),
+            storage: HashMap::new(),
+            gas_limit: 1_000_000,
+            native: Box::new(move |_| native()),
+            compare_storage: false,
+        }
+    }
+
+    // The native side works on a copy of the prestate, which must end up like the contract's
+    pub fn stateful(name: impl Into<String>, code: Vec<u8>, native: impl Fn(&mut HashMap<U256, U256>) -> U256 + 'static) -> Self {
+        Self { native: Box::new(native), compare_storage: true, ..Self::new(name, code, || U256::ZERO) }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn with_calldata(mut self, calldata: Vec<u8>) -> Self {
+        self.calldata = calldata;
+        self
+    }
+
+    pub fn with_storage(mut self, storage: HashMap<U256, U256>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    pub fn with_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    pub fn run_native(&self, storage: &mut HashMap<U256, U256>) -> U256 {
+        (self.native)(storage)
+    }
+
+    // A fresh machine with the code and prestate installed, after the call
+    pub fn run_evm(&self) -> (ExecutionResult, Machine) {
+        let mut machine = Machine::default();
+        let mut account = Account::with_code(self.code.clone());
+        account.storage = self.storage.clone();
+        machine.accounts.insert(SCENARIO_ADDRESS, account);
+        let result = machine.call(Address::ZERO, SCENARIO_ADDRESS, self.calldata.clone(), self.gas_limit);
+        (result, machine)
+    }
+
+    // Runs both sides once. Benchmarks call this before timing anything, since numbers from two
+    // sides computing different things mean nothing
+    pub fn verify(&self) -> Result<(), Mismatch> {
+        let mut native_storage = self.storage.clone();
+        let native = self.run_native(&mut native_storage);
+        let (result, machine) = self.run_evm();
+        let output = match result {
+            ExecutionResult::Success(output) => output,
+            other => return Err(Mismatch::EvmFailed(other)),
+        };
+        if output != native.to_be_bytes::<32>() {
+            return Err(Mismatch::Output { native, evm: output });
+        }
+        if self.compare_storage {
+            let evm_storage = &machine.accounts[&SCENARIO_ADDRESS].storage;
+            let mut slots: Vec<U256> = native_storage.keys().chain(evm_storage.keys()).copied().collect();
+            slots.sort();
+            slots.dedup();
+            for slot in slots {
+                let native = native_storage.get(&slot).copied().unwrap_or_default();
+                let evm = evm_storage.get(&slot).copied().unwrap_or_default();
+                if native != evm {
+                    return Err(Mismatch::Storage { slot, native, evm });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// The scenarios the demo CLI and the benchmarks share
+pub fn builtin() -> Vec<Scenario> {
+    vec![
+        Scenario::new("add", evm_asm! { push 5; push 10; add; push 0; mstore; push 0x20; push 0; return }.to_vec(), || {
+            U256::from(5) + U256::from(10)
+        })
+        .with_description("5 + 10"),
+        // [2, 10, 5] -> ADD -> [2, 15] -> MUL -> [30]
+        Scenario::new("math", evm_asm! { push 2; push 10; push 5; add; mul; push 0; mstore; push 0x20; push 0; return }.to_vec(), || {
+            let a = U256::from(5);
+            let b = U256::from(10);
+            let multiplier = U256::from(2);
+            (a + b) * multiplier
+        })
+        .with_description("(5 + 10) * 2"),
+        Scenario::new("sum", evm_asm! {
+            push 0; push 100;
+            again:
+            dup1; swap2; add; swap1;
+            push 1; sub;
+            dup1; jumpi again;
+            pop; push 0; mstore; push 0x20; push 0; return;
+        }.to_vec(), || (1..=100u64).fold(U256::ZERO, |sum, i| sum + U256::from(i)))
+        .with_description("1 + 2 + ... + 100 in a loop"),
+        Scenario::new("keccak", evm_asm! { push 42; push 0; mstore; push 0x20; push 0; sha3; push 0; mstore; push 0x20; push 0; return }.to_vec(), || {
+            U256::from_be_bytes(keccak256(U256::from(42).to_be_bytes::<32>()).0)
+        })
+        .with_description("keccak256 of one word"),
+        Scenario::stateful("counter", evm_asm! { push 0; sload; push 1; add; dup1; push 0; sstore; push 0; mstore; push 0x20; push 0; return }.to_vec(), |storage| {
+            let counter = storage.entry(U256::ZERO).or_default();
+            *counter += U256::from(1);
+            *counter
+        })
+        .with_description("slot 0 += 1, storage compared too"),
+    ]
+}
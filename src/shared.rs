@@ -0,0 +1,86 @@
+use crate::evm::{Account, Host, Machine};
+use alloy::primitives::Address;
+use ruint::aliases::U256;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct BaseAccount {
+    balance: U256,
+    nonce: u64,
+    code: Vec<u8>,
+    storage: HashMap<U256, U256>,
+}
+
+// An immutable prestate that any number of Machines, on any number of threads, run against
+// through one Arc. Nothing is copied up front: a Machine pulls accounts and slots in as it
+// touches them and keeps its writes in its own `accounts`, which so acts as a copy-on-write
+// overlay. Clearing `accounts` puts a Machine back on the untouched base
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BaseState {
+    accounts: HashMap<Address, BaseAccount>,
+}
+
+impl BaseState {
+    // Takes the accounts a Machine was set up with, e.g. `std::mem::take(&mut machine.accounts)`
+    pub fn from_accounts(accounts: HashMap<Address, Account>) -> Self {
+        let mut base = Self::default();
+        for (address, account) in accounts {
+            base.insert(address, account);
+        }
+        base
+    }
+
+    pub fn insert(&mut self, address: Address, account: Account) {
+        self.accounts.insert(address, BaseAccount {
+            balance: account.balance,
+            nonce: account.nonce,
+            code: Rc::unwrap_or_clone(account.code),
+            storage: account.storage.into_iter().filter(|(_, value)| !value.is_zero()).collect(),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+}
+
+// Host reading from a shared BaseState; code is copied into a Machine only when it runs there
+#[derive(Debug, Clone)]
+pub struct SharedHost {
+    base: Arc<BaseState>,
+}
+
+impl SharedHost {
+    pub fn new(base: Arc<BaseState>) -> Self {
+        Self { base }
+    }
+}
+
+impl Host for SharedHost {
+    fn basic(&mut self, address: Address) -> Result<Account, String> {
+        Ok(match self.base.accounts.get(&address) {
+            Some(account) => Account { balance: account.balance, nonce: account.nonce, lazy_code: !account.code.is_empty(), ..Default::default() },
+            None => Account::default(),
+        })
+    }
+
+    fn storage(&mut self, address: Address, key: U256) -> Result<U256, String> {
+        Ok(self.base.accounts.get(&address).and_then(|account| account.storage.get(&key)).copied().unwrap_or_default())
+    }
+
+    fn code(&mut self, address: Address) -> Result<Vec<u8>, String> {
+        Ok(self.base.accounts.get(&address).map(|account| account.code.clone()).unwrap_or_default())
+    }
+}
+
+impl Machine {
+    pub fn with_base(base: Arc<BaseState>) -> Self {
+        Self::with_host(SharedHost::new(base))
+    }
+}
@@ -0,0 +1,79 @@
+use crate::evm::Transaction;
+use alloy::consensus::{Transaction as _, TxEnvelope};
+use alloy::eips::eip2718::{Decodable2718, Typed2718};
+use alloy::primitives::{Address, TxKind, B256};
+use ruint::aliases::U256;
+
+#[derive(Debug, PartialEq)]
+pub enum SignedTransactionError {
+    Decode(String),
+    UnsupportedType(u8),
+    InvalidSignature(String),
+    ChainIdMismatch { expected: u64, got: u64 },
+    ContractCreation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedTransaction {
+    pub hash: B256,
+    pub sender: Address,
+    // None for pre-EIP-155 legacy transactions, which are valid on every chain
+    pub chain_id: Option<u64>,
+    pub transaction: Transaction,
+}
+
+impl SignedTransaction {
+    // Decodes an EIP-2718 envelope (legacy, EIP-2930, EIP-1559 or EIP-4844 without sidecar)
+    // and recovers its sender
+    pub fn decode(mut raw: &[u8]) -> Result<Self, SignedTransactionError> {
+        let envelope = TxEnvelope::decode_2718(&mut raw).map_err(|e| SignedTransactionError::Decode(e.to_string()))?;
+        if !raw.is_empty() {
+            return Err(SignedTransactionError::Decode(format!("{} trailing bytes", raw.len())));
+        }
+        if !matches!(envelope, TxEnvelope::Legacy(_) | TxEnvelope::Eip2930(_) | TxEnvelope::Eip1559(_) | TxEnvelope::Eip4844(_)) {
+            return Err(SignedTransactionError::UnsupportedType(envelope.ty()));
+        }
+
+        // EIP-2: only the low-s form of a signature is valid
+        let signature = envelope.signature();
+        if signature.normalize_s().is_some() {
+            return Err(SignedTransactionError::InvalidSignature("s value is not in the lower half of the curve order".into()));
+        }
+        let sender = signature
+            .recover_address_from_prehash(&envelope.signature_hash())
+            .map_err(|e| SignedTransactionError::InvalidSignature(e.to_string()))?;
+
+        let TxKind::Call(to) = envelope.kind() else {
+            return Err(SignedTransactionError::ContractCreation);
+        };
+        let access_list = envelope.access_list().map(|list| {
+            list.iter().map(|item| (item.address, item.storage_keys.iter().map(|key| U256::from_be_bytes(key.0)).collect())).collect()
+        }).unwrap_or_default();
+
+        Ok(Self {
+            hash: *envelope.tx_hash(),
+            sender,
+            chain_id: envelope.chain_id(),
+            transaction: Transaction {
+                caller: sender,
+                to,
+                value: envelope.value(),
+                data: envelope.input().to_vec(),
+                gas_limit: envelope.gas_limit(),
+                gas_price: U256::from(envelope.max_fee_per_gas()),
+                gas_priority_fee: envelope.max_priority_fee_per_gas().map(U256::from),
+                nonce: Some(envelope.nonce()),
+                access_list,
+                blob_versioned_hashes: envelope.blob_versioned_hashes().map(<[B256]>::to_vec).unwrap_or_default(),
+                max_fee_per_blob_gas: U256::from(envelope.max_fee_per_blob_gas().unwrap_or_default()),
+            },
+        })
+    }
+
+    pub fn check_chain_id(&self, chain_id: u64) -> Result<(), SignedTransactionError> {
+        match self.chain_id {
+            Some(got) if got != chain_id => Err(SignedTransactionError::ChainIdMismatch { expected: chain_id, got }),
+            _ => Ok(()),
+        }
+    }
+}
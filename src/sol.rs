@@ -0,0 +1,42 @@
+use crate::evm::{ExecutionResult, Machine};
+use alloy::primitives::{Address, Log};
+use alloy::sol_types::{decode_revert_reason, SolCall, SolEvent};
+
+#[derive(Debug, PartialEq)]
+pub enum SolCallError {
+    Revert(Vec<u8>),
+    Halt(ExecutionResult),
+    Decode(String),
+}
+
+impl SolCallError {
+    // Error(string) and Panic(uint256) payloads, as solc emits them
+    pub fn revert_reason(&self) -> Option<String> {
+        match self {
+            SolCallError::Revert(data) => decode_revert_reason(data),
+            _ => None,
+        }
+    }
+}
+
+impl Machine {
+    pub fn call_sol<C: SolCall>(&mut self, caller: Address, to: Address, call: &C, gas_limit: u64) -> Result<C::Return, SolCallError> {
+        match self.call(caller, to, call.abi_encode(), gas_limit) {
+            ExecutionResult::Success(data) => C::abi_decode_returns(&data).map_err(|e| SolCallError::Decode(e.to_string())),
+            ExecutionResult::Revert(data) => Err(SolCallError::Revert(data)),
+            result => Err(SolCallError::Halt(result)),
+        }
+    }
+
+    // Logs of the last execution that decode as `E`, in emission order
+    pub fn events<E: SolEvent>(&self) -> Vec<Log<E>> {
+        decode_events(&self.logs)
+    }
+}
+
+pub fn decode_events<E: SolEvent>(logs: &[Log]) -> Vec<Log<E>> {
+    logs.iter()
+        .filter(|log| log.topics().first() == Some(&E::SIGNATURE_HASH))
+        .filter_map(|log| E::decode_log(log).ok())
+        .collect()
+}
@@ -0,0 +1,71 @@
+use crate::evm::{Account, Machine};
+use alloy::primitives::Address;
+use ruint::aliases::U256;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+// Accounts and storage live in HashMaps for speed, so anything printed or compared from them
+// comes out in a different order every run. A dump copies them into sorted maps instead
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountDump {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Vec<u8>,
+    // zero slots are left out, whether they were written or never set
+    pub storage: BTreeMap<U256, U256>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateDump {
+    pub accounts: BTreeMap<Address, AccountDump>,
+}
+
+impl Account {
+    // Nonzero slots by key
+    pub fn sorted_storage(&self) -> Vec<(U256, U256)> {
+        let mut slots: Vec<(U256, U256)> = self.storage.iter().filter(|(_, value)| !value.is_zero()).map(|(key, value)| (*key, *value)).collect();
+        slots.sort();
+        slots
+    }
+
+    pub fn dump(&self) -> AccountDump {
+        AccountDump { balance: self.balance, nonce: self.nonce, code: self.code.to_vec(), storage: self.sorted_storage().into_iter().collect() }
+    }
+}
+
+impl Machine {
+    // Loaded accounts by address; with a host, that is only what execution has touched so far
+    pub fn sorted_accounts(&self) -> Vec<(Address, &Account)> {
+        let mut accounts: Vec<(Address, &Account)> = self.accounts.iter().map(|(address, account)| (*address, account)).collect();
+        accounts.sort_by_key(|(address, _)| *address);
+        accounts
+    }
+
+    pub fn dump_state(&self) -> StateDump {
+        StateDump { accounts: self.accounts.iter().map(|(address, account)| (*address, account.dump())).collect() }
+    }
+}
+
+fn word(value: U256) -> String {
+    format!("0x{}", hex::encode(value.to_be_bytes::<32>()))
+}
+
+impl StateDump {
+    // The canonical form: pretty JSON with accounts and slots sorted, quantities as minimal hex and
+    // slots as full words, so equal states always give byte-identical text. Keys go in already
+    // sorted, which keeps the order the same whether or not serde_json preserves insertion order
+    pub fn to_json(&self) -> String {
+        let mut accounts = Map::new();
+        for (address, account) in &self.accounts {
+            let storage: Map<String, Value> = account.storage.iter().map(|(key, value)| (word(*key), json!(word(*value)))).collect();
+            accounts.insert(address.to_string().to_lowercase(), json!({
+                "balance": format!("{:#x}", account.balance),
+                "code": format!("0x{}", hex::encode(&account.code)),
+                "nonce": account.nonce,
+                "storage": storage,
+            }));
+        }
+        serde_json::to_string_pretty(&json!({ "accounts": accounts })).expect("JSON values always serialize")
+    }
+}
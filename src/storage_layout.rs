@@ -0,0 +1,277 @@
+use alloy::primitives::{keccak256, Address, B256};
+use ruint::aliases::U256;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutError {
+    Json(String),
+    UnknownVariable(String),
+    UnknownType(String),
+    // the variable's type cannot be read the way that was asked for
+    TypeMismatch { expected: &'static str, found: String },
+    // a type claiming to take no bytes, which nothing can be indexed by
+    ZeroSize(String),
+    // a length word too large to be real, as in corrupt or hostile storage
+    LengthOutOfRange(U256),
+}
+
+// Longest string or bytes value read back, far beyond anything a contract stores in practice
+const MAX_BYTES_LENGTH: usize = 1 << 24;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    // value types, static arrays and structs, laid out from the variable's own slot
+    Inplace,
+    Mapping,
+    DynamicArray,
+    // string and bytes
+    Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageType {
+    pub label: String,
+    pub encoding: Encoding,
+    pub number_of_bytes: usize,
+    // mapping key and value type ids
+    pub key: Option<String>,
+    pub value: Option<String>,
+    // array element type id
+    pub base: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageVariable {
+    pub label: String,
+    pub slot: U256,
+    // bytes from the low end of the slot, for packed variables
+    pub offset: usize,
+    pub type_id: String,
+}
+
+// The `storageLayout` solc emits with `--storage-layout` (or Foundry's `extra_output`), used to
+// read variables by name instead of by raw slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageLayout {
+    pub variables: Vec<StorageVariable>,
+    pub types: HashMap<String, StorageType>,
+}
+
+impl StorageLayout {
+    pub fn parse(json: &str) -> Result<Self, LayoutError> {
+        let value: Value = serde_json::from_str(json).map_err(|e| LayoutError::Json(e.to_string()))?;
+        Self::from_value(&value)
+    }
+
+    // Takes the layout object itself or a Foundry artifact holding it under "storageLayout"
+    pub fn from_value(json: &Value) -> Result<Self, LayoutError> {
+        let json = json.get("storageLayout").unwrap_or(json);
+        let storage = json.get("storage").and_then(Value::as_array).ok_or_else(|| LayoutError::Json("missing \"storage\"".into()))?;
+        let variables = storage.iter().map(|entry| {
+            Ok(StorageVariable {
+                label: string_field(entry, "label")?,
+                slot: string_field(entry, "slot")?.parse().map_err(|_| LayoutError::Json(format!("bad slot in {}", entry)))?,
+                offset: entry.get("offset").and_then(Value::as_u64).unwrap_or(0) as usize,
+                type_id: string_field(entry, "type")?,
+            })
+        }).collect::<Result<_, LayoutError>>()?;
+
+        let mut types = HashMap::new();
+        for (id, entry) in json.get("types").and_then(Value::as_object).into_iter().flatten() {
+            let encoding = match string_field(entry, "encoding")?.as_str() {
+                "inplace" => Encoding::Inplace,
+                "mapping" => Encoding::Mapping,
+                "dynamic_array" => Encoding::DynamicArray,
+                "bytes" => Encoding::Bytes,
+                other => return Err(LayoutError::Json(format!("unknown encoding {}", other))),
+            };
+            let optional = |field| entry.get(field).and_then(Value::as_str).map(str::to_string);
+            types.insert(id.clone(), StorageType {
+                label: string_field(entry, "label")?,
+                encoding,
+                number_of_bytes: string_field(entry, "numberOfBytes")?.parse().map_err(|_| LayoutError::Json(format!("bad numberOfBytes in {}", id)))?,
+                key: optional("key"),
+                value: optional("value"),
+                base: optional("base"),
+            });
+        }
+        Ok(Self { variables, types })
+    }
+
+    pub fn variable(&self, label: &str) -> Result<StorageRef<'_>, LayoutError> {
+        let variable = self.variables.iter().find(|v| v.label == label).ok_or_else(|| LayoutError::UnknownVariable(label.to_string()))?;
+        Ok(StorageRef { layout: self, slot: variable.slot, offset: variable.offset, type_id: &variable.type_id })
+    }
+
+    fn storage_type(&self, id: &str) -> Result<&StorageType, LayoutError> {
+        self.types.get(id).ok_or_else(|| LayoutError::UnknownType(id.to_string()))
+    }
+}
+
+fn string_field(entry: &Value, field: &str) -> Result<String, LayoutError> {
+    entry.get(field).and_then(Value::as_str).map(str::to_string).ok_or_else(|| LayoutError::Json(format!("missing \"{}\" in {}", field, entry)))
+}
+
+// Mapping keys as solc hashes them: value types padded to a word, strings and bytes as they are
+pub trait MappingKey {
+    fn key_bytes(&self) -> Vec<u8>;
+}
+
+impl MappingKey for U256 {
+    fn key_bytes(&self) -> Vec<u8> {
+        self.to_be_bytes::<32>().to_vec()
+    }
+}
+
+impl MappingKey for u64 {
+    fn key_bytes(&self) -> Vec<u8> {
+        U256::from(*self).key_bytes()
+    }
+}
+
+impl MappingKey for bool {
+    fn key_bytes(&self) -> Vec<u8> {
+        U256::from(*self as u8).key_bytes()
+    }
+}
+
+impl MappingKey for Address {
+    fn key_bytes(&self) -> Vec<u8> {
+        self.into_word().to_vec()
+    }
+}
+
+impl MappingKey for B256 {
+    fn key_bytes(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl MappingKey for &str {
+    fn key_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+// A variable, mapping entry or array element, located but not yet read
+#[derive(Debug, Clone, Copy)]
+pub struct StorageRef<'a> {
+    layout: &'a StorageLayout,
+    pub slot: U256,
+    pub offset: usize,
+    type_id: &'a str,
+}
+
+impl<'a> StorageRef<'a> {
+    pub fn storage_type(&self) -> Result<&'a StorageType, LayoutError> {
+        self.layout.storage_type(self.type_id)
+    }
+
+    // The entry of a mapping at keccak256(key . slot)
+    pub fn key(&self, key: impl MappingKey) -> Result<StorageRef<'a>, LayoutError> {
+        let mapping = self.expect(Encoding::Mapping, "mapping")?;
+        let mut preimage = key.key_bytes();
+        preimage.extend_from_slice(&self.slot.to_be_bytes::<32>());
+        let value = mapping.value.as_deref().ok_or_else(|| LayoutError::UnknownType(format!("{} value", self.type_id)))?;
+        Ok(self.at(U256::from_be_bytes(keccak256(preimage).0), 0, value))
+    }
+
+    // An element of a static or dynamic array; dynamic ones start at keccak256(slot). Elements
+    // of 16 bytes or less share slots
+    pub fn index(&self, index: usize) -> Result<StorageRef<'a>, LayoutError> {
+        let array = self.storage_type()?;
+        let start = match array.encoding {
+            Encoding::DynamicArray => U256::from_be_bytes(keccak256(self.slot.to_be_bytes::<32>()).0),
+            Encoding::Inplace if array.base.is_some() => self.slot,
+            _ => return Err(self.mismatch("array")),
+        };
+        let base = array.base.as_deref().unwrap_or_default();
+        let size = self.layout.storage_type(base)?.number_of_bytes;
+        if size == 0 {
+            return Err(LayoutError::ZeroSize(base.to_string()));
+        }
+        if size > 16 {
+            let slots = size.div_ceil(32);
+            return Ok(self.at(start + U256::from(index) * U256::from(slots), 0, base));
+        }
+        let per_slot = 32 / size;
+        Ok(self.at(start + U256::from(index) / U256::from(per_slot), index % per_slot * size, base))
+    }
+
+    // The length word of a dynamic array
+    pub fn len(&self, storage: &HashMap<U256, U256>) -> Result<usize, LayoutError> {
+        self.expect(Encoding::DynamicArray, "dynamic array")?;
+        let length = word(storage, self.slot);
+        usize::try_from(length).map_err(|_| LayoutError::LengthOutOfRange(length))
+    }
+
+    // A value type, masked out of its slot
+    pub fn uint(&self, storage: &HashMap<U256, U256>) -> Result<U256, LayoutError> {
+        let value_type = self.expect(Encoding::Inplace, "value type")?;
+        if value_type.base.is_some() || value_type.number_of_bytes > 32 {
+            return Err(self.mismatch("value type"));
+        }
+        let value = word(storage, self.slot) >> (self.offset * 8);
+        if value_type.number_of_bytes == 32 {
+            return Ok(value);
+        }
+        Ok(value & ((U256::from(1) << (value_type.number_of_bytes * 8)) - U256::from(1)))
+    }
+
+    pub fn address(&self, storage: &HashMap<U256, U256>) -> Result<Address, LayoutError> {
+        Ok(Address::from_word(self.uint(storage)?.to_be_bytes::<32>().into()))
+    }
+
+    pub fn boolean(&self, storage: &HashMap<U256, U256>) -> Result<bool, LayoutError> {
+        Ok(!self.uint(storage)?.is_zero())
+    }
+
+    // Up to 31 bytes live in the slot itself with 2 * length in the low byte; longer values keep
+    // 2 * length + 1 there and their data from keccak256(slot) on
+    pub fn bytes(&self, storage: &HashMap<U256, U256>) -> Result<Vec<u8>, LayoutError> {
+        self.expect(Encoding::Bytes, "string or bytes")?;
+        let header = word(storage, self.slot);
+        if !header.bit(0) {
+            let length = (header.as_limbs()[0] & 0xff) as usize / 2;
+            return Ok(header.to_be_bytes::<32>()[..length.min(31)].to_vec());
+        }
+        let length = match usize::try_from(header >> 1) {
+            Ok(length) if length <= MAX_BYTES_LENGTH => length,
+            _ => return Err(LayoutError::LengthOutOfRange(header >> 1)),
+        };
+        let start = U256::from_be_bytes(keccak256(self.slot.to_be_bytes::<32>()).0);
+        let mut data = Vec::with_capacity(length.div_ceil(32) * 32);
+        for chunk in 0..length.div_ceil(32) {
+            data.extend_from_slice(&word(storage, start + U256::from(chunk)).to_be_bytes::<32>());
+        }
+        data.truncate(length);
+        Ok(data)
+    }
+
+    pub fn string(&self, storage: &HashMap<U256, U256>) -> Result<String, LayoutError> {
+        let bytes = self.bytes(storage)?;
+        String::from_utf8(bytes).map_err(|_| self.mismatch("utf-8 string"))
+    }
+
+    fn at(&self, slot: U256, offset: usize, type_id: &'a str) -> StorageRef<'a> {
+        StorageRef { layout: self.layout, slot, offset, type_id }
+    }
+
+    fn expect(&self, encoding: Encoding, expected: &'static str) -> Result<&'a StorageType, LayoutError> {
+        let storage_type = self.storage_type()?;
+        if storage_type.encoding != encoding {
+            return Err(self.mismatch(expected));
+        }
+        Ok(storage_type)
+    }
+
+    fn mismatch(&self, expected: &'static str) -> LayoutError {
+        let found = self.layout.types.get(self.type_id).map_or(self.type_id, |t| t.label.as_str());
+        LayoutError::TypeMismatch { expected, found: found.to_string() }
+    }
+}
+
+fn word(storage: &HashMap<U256, U256>, slot: U256) -> U256 {
+    storage.get(&slot).copied().unwrap_or_default()
+}
@@ -0,0 +1,511 @@
+use crate::evm::{ExecutionResult, Hardfork, Machine};
+use crate::opcode::Opcode;
+use alloy::primitives::{keccak256, Address};
+use ruint::aliases::U256;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+// Memory offsets and sizes, and calldata offsets, the explorer follows. Anything past them is
+// valid bytecode but would mean hashing, allocating or walking gigabytes, so the path ends there
+const MAX_OFFSET: usize = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    // the calldata word starting at this byte
+    Calldata(usize),
+    // a storage slot as it was before execution, with `symbolic_storage`
+    Storage(U256),
+    // a value nothing can steer, such as a block hash; tagged with the pc that produced it
+    Opaque(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Const(U256),
+    Var(Symbol),
+    // an opcode over its operands, in the interpreter's order: SUB [a, b] is a - b where a was
+    // the deeper stack item. CALLDATALOAD and SLOAD stand for reads at a symbolic location
+    Op(Opcode, Vec<Rc<Expr>>),
+}
+
+impl Expr {
+    // Folds to a constant whenever every operand is one
+    fn op(op: Opcode, args: Vec<Rc<Expr>>) -> Rc<Expr> {
+        let values: Option<Vec<U256>> = args.iter().map(|arg| arg.as_const()).collect();
+        match values {
+            Some(values) if op != Opcode::CallDataLoad && op != Opcode::SLoad => Rc::new(Expr::Const(fold(op, &values))),
+            _ => Rc::new(Expr::Op(op, args)),
+        }
+    }
+
+    pub fn as_const(&self) -> Option<U256> {
+        match self {
+            Expr::Const(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+fn constant(value: U256) -> Rc<Expr> {
+    Rc::new(Expr::Const(value))
+}
+
+// Same arithmetic as Machine::step
+fn fold(op: Opcode, values: &[U256]) -> U256 {
+    let flag = |condition: bool| if condition { U256::from(1) } else { U256::ZERO };
+    match (op, values) {
+        (Opcode::Add, [a, b]) => a.wrapping_add(*b),
+        (Opcode::Mul, [a, b]) => a.wrapping_mul(*b),
+        (Opcode::Sub, [a, b]) => a.wrapping_sub(*b),
+        (Opcode::Div, [a, b]) => a.checked_div(*b).unwrap_or_default(),
+        (Opcode::Lt, [a, b]) => flag(a < b),
+        (Opcode::Gt, [a, b]) => flag(a > b),
+        (Opcode::Eq, [a, b]) => flag(a == b),
+        (Opcode::IsZero, [a]) => flag(a.is_zero()),
+        (Opcode::Sha3, words) => U256::from_be_bytes(keccak256(words.iter().flat_map(|word| word.to_be_bytes::<32>()).collect::<Vec<_>>()).0),
+        _ => U256::ZERO,
+    }
+}
+
+// Concrete inputs for a path: calldata and, with `symbolic_storage`, the storage it assumes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Witness {
+    pub calldata: Vec<u8>,
+    pub storage: HashMap<U256, U256>,
+}
+
+impl Witness {
+    fn word(&self, offset: usize) -> U256 {
+        let mut word = [0u8; 32];
+        for (i, byte) in word.iter_mut().enumerate() {
+            *byte = offset.checked_add(i).and_then(|index| self.calldata.get(index)).copied().unwrap_or(0);
+        }
+        U256::from_be_bytes(word)
+    }
+
+    // False for offsets no witness can reasonably supply calldata up to
+    fn set_word(&mut self, offset: usize, value: U256) -> bool {
+        if offset > MAX_OFFSET {
+            return false;
+        }
+        if self.calldata.len() < offset + 32 {
+            self.calldata.resize(offset + 32, 0);
+        }
+        self.calldata[offset..offset + 32].copy_from_slice(&value.to_be_bytes::<32>());
+        true
+    }
+
+    pub fn eval(&self, expr: &Expr) -> U256 {
+        match expr {
+            Expr::Const(value) => *value,
+            Expr::Var(Symbol::Calldata(offset)) => self.word(*offset),
+            Expr::Var(Symbol::Storage(key)) => self.storage.get(key).copied().unwrap_or_default(),
+            Expr::Var(Symbol::Opaque(_)) => U256::ZERO,
+            Expr::Op(op, args) => {
+                let values: Vec<U256> = args.iter().map(|arg| self.eval(arg)).collect();
+                match (*op, values.as_slice()) {
+                    (Opcode::CallDataLoad, [offset]) => usize::try_from(*offset).map_or(U256::ZERO, |offset| self.word(offset)),
+                    (Opcode::SLoad, [key]) => self.storage.get(key).copied().unwrap_or_default(),
+                    (op, values) => fold(op, values),
+                }
+            }
+        }
+    }
+
+    // Best-effort inversion of the shapes compiled code branches on; a false result, or a
+    // constraint that still fails afterwards, just means no witness
+    fn solve(&mut self, expr: &Expr, target: U256) -> bool {
+        let one = U256::from(1);
+        match expr {
+            Expr::Const(value) => *value == target,
+            Expr::Var(Symbol::Calldata(offset)) => self.set_word(*offset, target),
+            Expr::Var(Symbol::Storage(key)) => {
+                self.storage.insert(*key, target);
+                true
+            }
+            Expr::Var(Symbol::Opaque(_)) => false,
+            Expr::Op(op, args) => {
+                let values: Vec<U256> = args.iter().map(|arg| self.eval(arg)).collect();
+                match (*op, args.as_slice()) {
+                    (Opcode::IsZero, [a]) if target.is_zero() => !values[0].is_zero() || self.solve(a, one),
+                    (Opcode::IsZero, [a]) => self.solve(a, U256::ZERO),
+                    (Opcode::Eq, [a, b]) if target.is_zero() => {
+                        values[0] != values[1] || self.solve(a, values[1].wrapping_add(one)) || self.solve(b, values[0].wrapping_add(one))
+                    }
+                    (Opcode::Eq, [a, b]) => self.solve(a, values[1]) || self.solve(b, values[0]),
+                    (Opcode::Lt, [a, b]) if target.is_zero() => values[0] >= values[1] || self.solve(a, values[1]) || self.solve(b, values[0]),
+                    (Opcode::Lt, [a, b]) => {
+                        values[0] < values[1]
+                            || (!values[1].is_zero() && self.solve(a, values[1] - one))
+                            || (values[0] < U256::MAX && self.solve(b, values[0] + one))
+                    }
+                    (Opcode::Gt, [a, b]) if target.is_zero() => values[0] <= values[1] || self.solve(a, values[1]) || self.solve(b, values[0]),
+                    (Opcode::Gt, [a, b]) => {
+                        values[0] > values[1]
+                            || (values[1] < U256::MAX && self.solve(a, values[1] + one))
+                            || (!values[0].is_zero() && self.solve(b, values[0] - one))
+                    }
+                    (Opcode::Add, [a, b]) => self.solve(a, target.wrapping_sub(values[1])) || self.solve(b, target.wrapping_sub(values[0])),
+                    (Opcode::Sub, [a, b]) => self.solve(a, target.wrapping_add(values[1])) || self.solve(b, values[0].wrapping_sub(target)),
+                    (Opcode::Mul, [a, b]) => {
+                        (!values[1].is_zero() && (target % values[1]).is_zero() && self.solve(a, target / values[1]))
+                            || (!values[0].is_zero() && (target % values[0]).is_zero() && self.solve(b, target / values[0]))
+                    }
+                    (Opcode::Div, [a, _]) => target.checked_mul(values[1]).is_some_and(|dividend| self.solve(a, dividend)),
+                    (Opcode::CallDataLoad, [_]) => usize::try_from(values[0]).is_ok_and(|offset| self.set_word(offset, target)),
+                    (Opcode::SLoad, [_]) => {
+                        self.storage.insert(values[0], target);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+// A JUMPI decision on the path: the jump was taken exactly when `taken` is set
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub condition: Rc<Expr>,
+    pub taken: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PathEnd {
+    Success,
+    Revert,
+    Halt(ExecutionResult),
+    StepLimit,
+    Unsupported(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExploredPath {
+    pub end: PathEnd,
+    pub constraints: Vec<Constraint>,
+    // inputs the concrete interpreter confirmed to end the same way
+    pub witness: Option<Witness>,
+}
+
+#[derive(Debug, Default)]
+pub struct SymbolicReport {
+    pub paths: Vec<ExploredPath>,
+    // more branches existed than `max_paths` allowed
+    pub truncated: bool,
+}
+
+impl SymbolicReport {
+    // REVERTs and INVALID (the pre-0.8 Solidity assert)
+    pub fn failures(&self) -> impl Iterator<Item = &ExploredPath> {
+        self.paths.iter().filter(|path| matches!(path.end, PathEnd::Revert | PathEnd::Halt(ExecutionResult::InvalidOpcode)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymbolicConfig {
+    pub max_paths: usize,
+    pub max_steps: usize,
+    // start from unknown storage instead of the machine's
+    pub symbolic_storage: bool,
+    // for the concrete runs that confirm witnesses
+    pub gas_limit: u64,
+}
+
+impl Default for SymbolicConfig {
+    fn default() -> Self {
+        Self { max_paths: 64, max_steps: 10_000, symbolic_storage: false, gas_limit: 10_000_000 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct PathState {
+    pc: usize,
+    stack: Vec<Rc<Expr>>,
+    // 32-byte words by offset
+    memory: HashMap<usize, Rc<Expr>>,
+    storage: Vec<(Rc<Expr>, Rc<Expr>)>,
+    transient: Vec<(Rc<Expr>, Rc<Expr>)>,
+    constraints: Vec<Constraint>,
+    steps: usize,
+}
+
+impl PathState {
+    fn pop(&mut self) -> Result<Rc<Expr>, PathEnd> {
+        self.stack.pop().ok_or(PathEnd::Halt(ExecutionResult::StackUnderflow))
+    }
+
+    // A constant memory offset or size, up to `MAX_OFFSET`
+    fn pop_concrete(&mut self, what: &str) -> Result<usize, PathEnd> {
+        let value = self.pop()?;
+        let value = value.as_const().ok_or_else(|| PathEnd::Unsupported(format!("symbolic {}", what)))?;
+        match usize::try_from(value) {
+            Ok(value) if value <= MAX_OFFSET => Ok(value),
+            _ => Err(PathEnd::Unsupported(format!("{} out of range", what))),
+        }
+    }
+
+    fn mload(&self, offset: usize) -> Result<Rc<Expr>, PathEnd> {
+        if let Some(word) = self.memory.get(&offset) {
+            return Ok(word.clone());
+        }
+        if self.memory.keys().any(|other| other.abs_diff(offset) < 32) {
+            return Err(PathEnd::Unsupported("unaligned memory access".into()));
+        }
+        Ok(constant(U256::ZERO))
+    }
+
+    fn mstore(&mut self, offset: usize, value: Rc<Expr>) -> Result<(), PathEnd> {
+        if self.memory.keys().any(|other| *other != offset && other.abs_diff(offset) < 32) {
+            return Err(PathEnd::Unsupported("unaligned memory access".into()));
+        }
+        self.memory.insert(offset, value);
+        Ok(())
+    }
+
+    fn contradicts(&self, condition: &Rc<Expr>, taken: bool) -> bool {
+        self.constraints.iter().any(|constraint| constraint.condition == *condition && constraint.taken != taken)
+    }
+}
+
+// Last write to a structurally equal key. Keys that only coincide at runtime are not matched
+fn lookup(writes: &[(Rc<Expr>, Rc<Expr>)], key: &Rc<Expr>) -> Option<Rc<Expr>> {
+    writes.iter().rev().find(|(written, _)| written == key).map(|(_, value)| value.clone())
+}
+
+impl Machine {
+    // Explores `to`'s code with symbolic calldata, forking at every JUMPI whose condition depends
+    // on it, and tries to confirm each path with a concrete run. Gas is ignored while exploring
+    // and external calls end a path as unsupported. State is left as it was
+    pub fn explore(&mut self, to: Address, config: SymbolicConfig) -> Result<SymbolicReport, String> {
+        let code = self.code(to)?;
+        let jumpdests = self.account(to)?.jumpdests.clone();
+        let mut report = SymbolicReport::default();
+        let mut pending = vec![PathState::default()];
+
+        while let Some(mut state) = pending.pop() {
+            let end = match self.explore_path(to, &code, &jumpdests, &mut state, config, &mut pending, &mut report) {
+                Ok(end) | Err(end) => end,
+            };
+            let witness = match end {
+                PathEnd::Success | PathEnd::Revert | PathEnd::Halt(_) => self.confirm(to, &state.constraints, &end, config)?,
+                PathEnd::StepLimit | PathEnd::Unsupported(_) => None,
+            };
+            report.paths.push(ExploredPath { end, constraints: state.constraints, witness });
+        }
+        Ok(report)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn explore_path(
+        &mut self,
+        to: Address,
+        code: &[u8],
+        jumpdests: &HashSet<usize>,
+        state: &mut PathState,
+        config: SymbolicConfig,
+        pending: &mut Vec<PathState>,
+        report: &mut SymbolicReport,
+    ) -> Result<PathEnd, PathEnd> {
+        loop {
+            if state.steps >= config.max_steps {
+                return Ok(PathEnd::StepLimit);
+            }
+            state.steps += 1;
+            let Some(&byte) = code.get(state.pc) else {
+                return Ok(PathEnd::Success);
+            };
+            state.pc += 1;
+            let Ok(op) = Opcode::try_from(byte) else {
+                return Ok(PathEnd::Halt(ExecutionResult::InvalidOpcode));
+            };
+
+            match op {
+                Opcode::Stop => return Ok(PathEnd::Success),
+                Opcode::Return | Opcode::Revert => {
+                    state.pop()?;
+                    state.pop()?;
+                    return Ok(if op == Opcode::Return { PathEnd::Success } else { PathEnd::Revert });
+                }
+                Opcode::Add | Opcode::Mul => {
+                    let a = state.pop()?;
+                    let b = state.pop()?;
+                    state.stack.push(Expr::op(op, vec![a, b]));
+                }
+                Opcode::Sub | Opcode::Div | Opcode::Lt | Opcode::Gt | Opcode::Eq => {
+                    let b = state.pop()?;
+                    let a = state.pop()?;
+                    state.stack.push(Expr::op(op, vec![a, b]));
+                }
+                Opcode::IsZero => {
+                    let a = state.pop()?;
+                    state.stack.push(Expr::op(Opcode::IsZero, vec![a]));
+                }
+                Opcode::Sha3 => {
+                    let offset = state.pop_concrete("SHA3 offset")?;
+                    let size = state.pop_concrete("SHA3 size")?;
+                    if size % 32 != 0 {
+                        return Err(PathEnd::Unsupported("SHA3 over a partial word".into()));
+                    }
+                    if offset + size > MAX_OFFSET {
+                        return Err(PathEnd::Unsupported("SHA3 size out of range".into()));
+                    }
+                    let words = (0..size / 32).map(|i| state.mload(offset + 32 * i)).collect::<Result<Vec<_>, _>>()?;
+                    state.stack.push(Expr::op(Opcode::Sha3, words));
+                }
+                Opcode::CallDataLoad => {
+                    let offset = state.pop()?;
+                    state.stack.push(match offset.as_const().map(usize::try_from) {
+                        Some(Ok(offset)) if offset <= MAX_OFFSET => Rc::new(Expr::Var(Symbol::Calldata(offset))),
+                        Some(_) => return Err(PathEnd::Unsupported("calldata offset out of range".into())),
+                        None => Expr::op(Opcode::CallDataLoad, vec![offset]),
+                    });
+                }
+                Opcode::BlockHash | Opcode::BlobHash => {
+                    state.pop()?;
+                    state.stack.push(Rc::new(Expr::Var(Symbol::Opaque(state.pc - 1))));
+                }
+                // paths are confirmed with calls from the zero address
+                Opcode::Caller => state.stack.push(constant(U256::ZERO)),
+                Opcode::Coinbase => state.stack.push(constant(U256::from_be_bytes(self.block.coinbase.into_word().0))),
+                Opcode::Timestamp => state.stack.push(constant(U256::from(self.block.timestamp))),
+                Opcode::Number => state.stack.push(constant(U256::from(self.block.number))),
+                Opcode::GasLimit => state.stack.push(constant(U256::from(self.block.gas_limit))),
+                Opcode::BaseFee => state.stack.push(constant(self.block.base_fee)),
+                Opcode::Pop => {
+                    state.pop()?;
+                }
+                Opcode::MLoad => {
+                    let offset = state.pop_concrete("memory offset")?;
+                    let word = state.mload(offset)?;
+                    state.stack.push(word);
+                }
+                Opcode::MStore => {
+                    let offset = state.pop_concrete("memory offset")?;
+                    let value = state.pop()?;
+                    state.mstore(offset, value)?;
+                }
+                Opcode::SLoad => {
+                    let key = state.pop()?;
+                    let value = match (lookup(&state.storage, &key), key.as_const()) {
+                        (Some(value), _) => value,
+                        (None, Some(key)) if config.symbolic_storage => Rc::new(Expr::Var(Symbol::Storage(key))),
+                        (None, Some(key)) => constant(self.storage(to, key).map_err(|e| PathEnd::Halt(ExecutionResult::HostError(e)))?),
+                        (None, None) => Expr::op(Opcode::SLoad, vec![key]),
+                    };
+                    state.stack.push(value);
+                }
+                Opcode::SStore => {
+                    let key = state.pop()?;
+                    let value = state.pop()?;
+                    state.storage.push((key, value));
+                }
+                Opcode::TLoad | Opcode::TStore if self.hardfork < Hardfork::Cancun => return Ok(PathEnd::Halt(ExecutionResult::InvalidOpcode)),
+                Opcode::TLoad => {
+                    let key = state.pop()?;
+                    let value = lookup(&state.transient, &key).unwrap_or_else(|| constant(U256::ZERO));
+                    state.stack.push(value);
+                }
+                Opcode::TStore => {
+                    let key = state.pop()?;
+                    let value = state.pop()?;
+                    state.transient.push((key, value));
+                }
+                Opcode::Jump => {
+                    let dest = state.pop_concrete("jump destination")?;
+                    if !jumpdests.contains(&dest) {
+                        return Ok(PathEnd::Halt(ExecutionResult::InvalidJump));
+                    }
+                    state.pc = dest;
+                }
+                Opcode::JumpI => {
+                    let dest = state.pop_concrete("jump destination")?;
+                    let condition = state.pop()?;
+                    if !jumpdests.contains(&dest) {
+                        return Ok(PathEnd::Halt(ExecutionResult::InvalidJump));
+                    }
+                    match condition.as_const() {
+                        Some(value) if !value.is_zero() => state.pc = dest,
+                        Some(_) => {}
+                        None => {
+                            // the fall-through side is explored later, this path takes the jump
+                            if !state.contradicts(&condition, false) {
+                                if report.paths.len() + pending.len() + 1 < config.max_paths {
+                                    let mut fallthrough = state.clone();
+                                    fallthrough.constraints.push(Constraint { condition: condition.clone(), taken: false });
+                                    pending.push(fallthrough);
+                                } else {
+                                    report.truncated = true;
+                                }
+                            }
+                            if state.contradicts(&condition, true) {
+                                return Err(PathEnd::Unsupported("infeasible branch".into()));
+                            }
+                            state.constraints.push(Constraint { condition, taken: true });
+                            state.pc = dest;
+                        }
+                    }
+                }
+                Opcode::JumpDest => {}
+                op if op.is_push() => {
+                    let size = op.immediate_size();
+                    let mut bytes = vec![0u8; size];
+                    let available = &code[state.pc.min(code.len())..(state.pc + size).min(code.len())];
+                    bytes[..available.len()].copy_from_slice(available);
+                    state.stack.push(constant(U256::from_be_slice(&bytes)));
+                    state.pc += size;
+                }
+                op if op.is_dup() => {
+                    let index = (op as u8 - Opcode::Dup1 as u8) as usize;
+                    if state.stack.len() <= index {
+                        return Ok(PathEnd::Halt(ExecutionResult::StackUnderflow));
+                    }
+                    state.stack.push(state.stack[state.stack.len() - 1 - index].clone());
+                }
+                op if op.is_swap() => {
+                    let index = (op as u8 - Opcode::Swap1 as u8 + 1) as usize;
+                    if state.stack.len() <= index {
+                        return Ok(PathEnd::Halt(ExecutionResult::StackUnderflow));
+                    }
+                    let top = state.stack.len() - 1;
+                    state.stack.swap(top, top - index);
+                }
+                op if op.is_log() => {
+                    for _ in 0..2 + (op as u8 - Opcode::Log0 as u8) {
+                        state.pop()?;
+                    }
+                }
+                Opcode::Call | Opcode::ReturnDataSize | Opcode::ReturnDataCopy => return Ok(PathEnd::Unsupported("external call".into())),
+                _ => return Ok(PathEnd::Halt(ExecutionResult::InvalidOpcode)),
+            }
+        }
+    }
+
+    // Searches inputs satisfying every constraint, then replays them on a copy of the state
+    fn confirm(&mut self, to: Address, constraints: &[Constraint], end: &PathEnd, config: SymbolicConfig) -> Result<Option<Witness>, String> {
+        let mut witness = Witness::default();
+        let holds = |witness: &Witness, constraint: &Constraint| witness.eval(&constraint.condition).is_zero() != constraint.taken;
+        for _ in 0..2 {
+            for constraint in constraints {
+                if !holds(&witness, constraint) {
+                    witness.solve(&constraint.condition, if constraint.taken { U256::from(1) } else { U256::ZERO });
+                }
+            }
+        }
+        if !constraints.iter().all(|constraint| holds(&witness, constraint)) {
+            return Ok(None);
+        }
+
+        let snapshot = self.accounts.clone();
+        self.account(to)?.storage.extend(witness.storage.iter().map(|(key, value)| (*key, *value)));
+        let result = self.call(Address::ZERO, to, witness.calldata.clone(), config.gas_limit);
+        self.accounts = snapshot;
+
+        let confirmed = match (end, &result) {
+            (PathEnd::Success, ExecutionResult::Success(_)) | (PathEnd::Revert, ExecutionResult::Revert(_)) => true,
+            (PathEnd::Halt(expected), result) => expected == result,
+            _ => false,
+        };
+        Ok(confirmed.then_some(witness))
+    }
+}
@@ -0,0 +1,153 @@
+use crate::evm::Machine;
+use crate::sol::SolCallError;
+use alloy::primitives::{hex, Address, U256};
+use alloy::sol;
+
+// Hand-assembled tokens for tests and benchmarks, runnable on the opcodes the machine implements.
+// Storage follows what solc would lay out for the same declarations, so `storage_layout` and raw
+// slot math both work on them. Events match the standard signatures; `mint` is open to anyone
+
+// uint256 totalSupply (slot 0); mapping(address => uint256) balances (slot 1);
+// mapping(address => mapping(address => uint256)) allowances (slot 2)
+pub const ERC20_CODE: &[u8] = &hex!(
+    "6000357c010000000000000000000000000000000000000000000000000000000004806318160ddd1461007457806370"
+    "a0823114610081578063a9059cbb14610122578063dd62ed3e1461009c578063095ea7b3146100c557806323b872dd14"
+    "61012f57806340c10f19146101cd575b600080fd5b5060005460005260206000f35b5060043560005260016020526040"
+    "6000205460005260206000f35b5060043560005260026020526040600020602052602435600052604060002054600052"
+    "60206000f35b503360005260026020526040600020602052600435600052604060002060243590556024356000526004"
+    "35337f8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b92560206000a36001600052602060"
+    "00f35b5033600435602435610169565b5060043560005260026020526040600020602052336000526040600020805460"
+    "443580821161006f57039055600435602435604435610169565b8260005260016020526040600020805482811161006f"
+    "57820390558160005260016020526040600020805482019055600052907fddf252ad1be2c89b69c2b068fc378daa952b"
+    "a7f163c4a11628f55a4df523b3ef60206000a3600160005260206000f35b5060243560005401600054811161006f5760"
+    "005560043560005260016020526040600020805460243501905560243560005260043560007fddf252ad1be2c89b69c2"
+    "b068fc378daa952ba7f163c4a11628f55a4df523b3ef60206000a300"
+);
+
+// mapping(uint256 => address) owners (slot 0); mapping(address => uint256) balances (slot 1);
+// mapping(uint256 => address) tokenApprovals (slot 2). No operators and no safeTransferFrom
+pub const ERC721_CODE: &[u8] = &hex!(
+    "6000357c010000000000000000000000000000000000000000000000000000000004806370a082311461006957806363"
+    "52211e14610084578063081812fc146100a5578063095ea7b3146100c057806323b872dd1461011d57806340c10f1914"
+    "6101c9575b600080fd5b50600435600052600160205260406000205460005260206000f35b5060043560005260006020"
+    "5260406000205480156100645760005260206000f35b50600435600052600260205260406000205460005260206000f3"
+    "5b5060243560005260006020526040600020548033141561006457600435602435600052600260205260406000205560"
+    "243590600435907f8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925600080a4005b5060"
+    "443560005260006020526040600020805460043514156100645760243515610064576004353314604435600052600260"
+    "205260406000208054331482011561006457600090555060243590556004356000526001602052604060002080546001"
+    "03905560243560005260016020526040600020805460010190556044356024356004357fddf252ad1be2c89b69c2b068"
+    "fc378daa952ba7f163c4a11628f55a4df523b3ef600080a4005b50602435600052600060205260406000208054151561"
+    "0064576004358015610064579055600435600052600160205260406000208054600101905560243560043560007fddf2"
+    "52ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef600080a400"
+);
+
+// Gas every harness call runs with
+pub const TOKEN_GAS_LIMIT: u64 = 1_000_000;
+
+sol! {
+    interface IERC20 {
+        event Transfer(address indexed from, address indexed to, uint256 value);
+        event Approval(address indexed owner, address indexed spender, uint256 value);
+
+        function totalSupply() external view returns (uint256);
+        function balanceOf(address owner) external view returns (uint256);
+        function allowance(address owner, address spender) external view returns (uint256);
+        function transfer(address to, uint256 amount) external returns (bool);
+        function approve(address spender, uint256 amount) external returns (bool);
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
+        function mint(address to, uint256 amount) external;
+    }
+
+    interface IERC721 {
+        event Transfer(address indexed from, address indexed to, uint256 indexed tokenId);
+        event Approval(address indexed owner, address indexed approved, uint256 indexed tokenId);
+
+        function balanceOf(address owner) external view returns (uint256);
+        function ownerOf(uint256 tokenId) external view returns (address);
+        function getApproved(uint256 tokenId) external view returns (address);
+        function approve(address to, uint256 tokenId) external;
+        function transferFrom(address from, address to, uint256 tokenId) external;
+        function mint(address to, uint256 tokenId) external;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Erc20 {
+    pub address: Address,
+}
+
+impl Erc20 {
+    pub fn deploy(machine: &mut Machine, address: Address) -> Self {
+        machine.deploy(address, ERC20_CODE.to_vec());
+        Self { address }
+    }
+
+    pub fn total_supply(&self, machine: &mut Machine) -> Result<U256, SolCallError> {
+        machine.call_sol(Address::ZERO, self.address, &IERC20::totalSupplyCall {}, TOKEN_GAS_LIMIT)
+    }
+
+    pub fn balance_of(&self, machine: &mut Machine, owner: Address) -> Result<U256, SolCallError> {
+        machine.call_sol(Address::ZERO, self.address, &IERC20::balanceOfCall { owner }, TOKEN_GAS_LIMIT)
+    }
+
+    pub fn allowance(&self, machine: &mut Machine, owner: Address, spender: Address) -> Result<U256, SolCallError> {
+        machine.call_sol(Address::ZERO, self.address, &IERC20::allowanceCall { owner, spender }, TOKEN_GAS_LIMIT)
+    }
+
+    pub fn transfer(&self, machine: &mut Machine, from: Address, to: Address, amount: U256) -> Result<bool, SolCallError> {
+        machine.call_sol(from, self.address, &IERC20::transferCall { to, amount }, TOKEN_GAS_LIMIT)
+    }
+
+    pub fn approve(&self, machine: &mut Machine, owner: Address, spender: Address, amount: U256) -> Result<bool, SolCallError> {
+        machine.call_sol(owner, self.address, &IERC20::approveCall { spender, amount }, TOKEN_GAS_LIMIT)
+    }
+
+    pub fn transfer_from(&self, machine: &mut Machine, spender: Address, from: Address, to: Address, amount: U256) -> Result<bool, SolCallError> {
+        machine.call_sol(spender, self.address, &IERC20::transferFromCall { from, to, amount }, TOKEN_GAS_LIMIT)
+    }
+
+    pub fn mint(&self, machine: &mut Machine, to: Address, amount: U256) -> Result<(), SolCallError> {
+        machine.call_sol(Address::ZERO, self.address, &IERC20::mintCall { to, amount }, TOKEN_GAS_LIMIT)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Erc721 {
+    pub address: Address,
+}
+
+impl Erc721 {
+    pub fn deploy(machine: &mut Machine, address: Address) -> Self {
+        machine.deploy(address, ERC721_CODE.to_vec());
+        Self { address }
+    }
+
+    pub fn balance_of(&self, machine: &mut Machine, owner: Address) -> Result<U256, SolCallError> {
+        machine.call_sol(Address::ZERO, self.address, &IERC721::balanceOfCall { owner }, TOKEN_GAS_LIMIT)
+    }
+
+    // Reverts for tokens that were never minted
+    pub fn owner_of(&self, machine: &mut Machine, token_id: U256) -> Result<Address, SolCallError> {
+        machine.call_sol(Address::ZERO, self.address, &IERC721::ownerOfCall { tokenId: token_id }, TOKEN_GAS_LIMIT)
+    }
+
+    pub fn get_approved(&self, machine: &mut Machine, token_id: U256) -> Result<Address, SolCallError> {
+        machine.call_sol(Address::ZERO, self.address, &IERC721::getApprovedCall { tokenId: token_id }, TOKEN_GAS_LIMIT)
+    }
+
+    pub fn approve(&self, machine: &mut Machine, owner: Address, to: Address, token_id: U256) -> Result<(), SolCallError> {
+        machine.call_sol(owner, self.address, &IERC721::approveCall { to, tokenId: token_id }, TOKEN_GAS_LIMIT)?;
+        Ok(())
+    }
+
+    pub fn transfer_from(&self, machine: &mut Machine, spender: Address, from: Address, to: Address, token_id: U256) -> Result<(), SolCallError> {
+        machine.call_sol(spender, self.address, &IERC721::transferFromCall { from, to, tokenId: token_id }, TOKEN_GAS_LIMIT)?;
+        Ok(())
+    }
+
+    pub fn mint(&self, machine: &mut Machine, to: Address, token_id: U256) -> Result<(), SolCallError> {
+        machine.call_sol(Address::ZERO, self.address, &IERC721::mintCall { to, tokenId: token_id }, TOKEN_GAS_LIMIT)?;
+        Ok(())
+    }
+}
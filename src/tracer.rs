@@ -0,0 +1,126 @@
+use crate::evm::{Inspector, Machine};
+use crate::opcode::Opcode;
+use ruint::aliases::U256;
+use serde_json::json;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructLog {
+    pub pc: usize,
+    pub op: u8,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: usize,
+    pub stack: Vec<U256>,
+}
+
+// Geth-style struct logger, the format debug_traceCall returns by default
+#[derive(Debug, Default)]
+pub struct StructLogger {
+    pub logs: Vec<StructLog>,
+}
+
+impl StructLog {
+    // The instruction about to run; gas_cost is only known once it has
+    pub(crate) fn capture(machine: &Machine) -> Self {
+        let frame = machine.call_stack.last().unwrap();
+        Self {
+            pc: frame.pc,
+            op: frame.code.get(frame.pc).copied().unwrap_or(0x00),
+            gas: frame.gas,
+            gas_cost: 0,
+            depth: machine.call_stack.len(),
+            stack: frame.stack.clone(),
+        }
+    }
+}
+
+impl Inspector for StructLogger {
+    fn step(&mut self, machine: &Machine) {
+        self.logs.push(StructLog::capture(machine));
+    }
+
+    fn step_end(&mut self, _machine: &Machine, gas_cost: u64) {
+        if let Some(log) = self.logs.last_mut() {
+            log.gas_cost = gas_cost;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    // one JSON object per line, with the fields of debug_traceCall's structLogs
+    JsonLines,
+    // `pc op gas gasCost depth stack...`, space separated with hex stack items
+    Compact,
+}
+
+// Writes each step to `writer` as soon as it completes instead of keeping the trace in memory.
+// Wrap files in a BufWriter. The first write error stops the trace and `finish` returns it
+#[derive(Debug)]
+pub struct StreamingTracer<W: Write> {
+    writer: W,
+    format: TraceFormat,
+    pending: Option<StructLog>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> StreamingTracer<W> {
+    pub fn new(writer: W, format: TraceFormat) -> Self {
+        Self { writer, format, pending: None, error: None }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write(&mut self, log: &StructLog) -> io::Result<()> {
+        match self.format {
+            TraceFormat::JsonLines => {
+                let line = json!({
+                    "pc": log.pc,
+                    "op": opcode_name(log.op),
+                    "gas": log.gas,
+                    "gasCost": log.gas_cost,
+                    "depth": log.depth,
+                    "stack": log.stack.iter().map(|value| format!("{:#x}", value)).collect::<Vec<_>>(),
+                });
+                serde_json::to_writer(&mut self.writer, &line)?;
+            }
+            TraceFormat::Compact => {
+                write!(self.writer, "{} {} {} {} {}", log.pc, opcode_name(log.op), log.gas, log.gas_cost, log.depth)?;
+                for value in &log.stack {
+                    write!(self.writer, " {:#x}", value)?;
+                }
+            }
+        }
+        writeln!(self.writer)
+    }
+}
+
+impl<W: Write> Inspector for StreamingTracer<W> {
+    fn step(&mut self, machine: &Machine) {
+        if self.error.is_none() {
+            self.pending = Some(StructLog::capture(machine));
+        }
+    }
+
+    fn step_end(&mut self, _machine: &Machine, gas_cost: u64) {
+        if let Some(mut log) = self.pending.take() {
+            log.gas_cost = gas_cost;
+            self.error = self.write(&log).err();
+        }
+    }
+}
+
+// Mnemonic of a raw opcode byte, as the tracers print it
+pub fn opcode_name(op: u8) -> String {
+    match Opcode::try_from(op) {
+        Ok(opcode) => opcode.mnemonic().to_string(),
+        Err(_) => format!("opcode {:#04x} not defined", op),
+    }
+}
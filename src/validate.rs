@@ -0,0 +1,84 @@
+use crate::cfg::Cfg;
+use crate::evm::Machine;
+use crate::opcode::{decode, Opcode};
+use alloy::primitives::Address;
+use std::collections::{BTreeSet, HashMap};
+
+// The EVM's own stack limit, independent of `Limits::max_stack_height`
+const MAX_STACK_HEIGHT: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Diagnostic {
+    // an instruction needs more items than some path reaching it leaves on the stack
+    StackUnderflow { pc: usize, required: usize, available: usize },
+    StackOverflow { pc: usize, height: usize },
+    // a PUSH whose immediate runs past the end of code, `missing` bytes short
+    TruncatedPush { pc: usize, missing: usize },
+}
+
+// Checks code without running it. Stack heights are followed from pc 0 across the static edges of
+// the control-flow graph, once per distinct entry height, so a block only reachable through a
+// computed jump is not checked. Diagnostics come back sorted by kind, then pc
+pub fn validate_bytecode(code: &[u8]) -> Vec<Diagnostic> {
+    let cfg = Cfg::build(code);
+    let mut diagnostics = BTreeSet::new();
+
+    // only the last instruction can run past the end of code
+    if let Some(last) = decode(code).last()
+        && last.is_truncated()
+    {
+        diagnostics.insert(Diagnostic::TruncatedPush { pc: last.pc, missing: last.size() - 1 - last.immediate.len() });
+    }
+
+    let mut successors: HashMap<usize, Vec<usize>> = HashMap::new();
+    for edge in &cfg.edges {
+        successors.entry(edge.from).or_default().push(edge.to);
+    }
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![(0, 0)];
+    while let Some((start, entry)) = pending.pop() {
+        let Some(block) = cfg.blocks.get(&start) else {
+            continue;
+        };
+        if !visited.insert((start, entry)) {
+            continue;
+        }
+        let mut height = entry;
+        let mut halted = false;
+        for &(pc, op, _) in &block.instructions {
+            let Ok((pops, pushes)) = Opcode::try_from(op).map(Opcode::stack_io) else {
+                halted = true;
+                break;
+            };
+            if height < pops {
+                diagnostics.insert(Diagnostic::StackUnderflow { pc, required: pops, available: height });
+                halted = true;
+                break;
+            }
+            height = height - pops + pushes;
+            if height > MAX_STACK_HEIGHT {
+                diagnostics.insert(Diagnostic::StackOverflow { pc, height });
+                halted = true;
+                break;
+            }
+        }
+        if !halted {
+            for &next in successors.get(&start).into_iter().flatten() {
+                pending.push((next, height));
+            }
+        }
+    }
+    diagnostics.into_iter().collect()
+}
+
+impl Machine {
+    // `deploy`, unless validation finds anything wrong with the code
+    pub fn deploy_validated(&mut self, address: Address, code: Vec<u8>) -> Result<(), Vec<Diagnostic>> {
+        let diagnostics = validate_bytecode(&code);
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+        self.deploy(address, code);
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use alloy::dyn_abi::DynSolValue;
+use alloy::primitives::{Address, FixedBytes};
+use native_vs_evm::abi::{self, AbiError, AbiType, AbiValue};
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+use ruint::aliases::U256;
+use std::rc::Rc;
+
+mod common;
+use common::assemble;
+
+#[test]
+fn test_selector() {
+    assert_eq!(abi::selector("transfer(address,uint256)"), [0xa9, 0x05, 0x9c, 0xbb]);
+    assert_eq!(abi::selector("balanceOf(address)"), [0x70, 0xa0, 0x82, 0x31]);
+}
+
+#[test]
+fn test_parse_types() {
+    assert_eq!(AbiType::parse("uint256"), Ok(AbiType::Uint(256)));
+    assert_eq!(AbiType::parse("bytes32[]"), Ok(AbiType::Array(Box::new(AbiType::FixedBytes(32)))));
+    assert_eq!(
+        AbiType::parse("(address,(bool,string))[2]"),
+        Ok(AbiType::FixedArray(Box::new(AbiType::Tuple(vec![
+            AbiType::Address,
+            AbiType::Tuple(vec![AbiType::Bool, AbiType::String]),
+        ])), 2))
+    );
+    assert_eq!(AbiType::parse("uint7"), Err(AbiError::InvalidType("uint7".to_string())));
+}
+
+// the example from the Solidity ABI spec: f(uint256,uint32[],bytes10,bytes)
+#[test]
+fn test_encode_matches_spec_example() {
+    let calldata = abi::encode_call("f(uint256,uint32[],bytes10,bytes)", &[
+        AbiValue::uint(0x123),
+        AbiValue::Array(vec![AbiValue::uint(0x456), AbiValue::uint(0x789)]),
+        AbiValue::FixedBytes(b"1234567890".to_vec()),
+        AbiValue::Bytes(b"Hello, world!".to_vec()),
+    ]);
+
+    let expected = concat!(
+        "8be65246",
+        "0000000000000000000000000000000000000000000000000000000000000123",
+        "0000000000000000000000000000000000000000000000000000000000000080",
+        "3132333435363738393000000000000000000000000000000000000000000000",
+        "00000000000000000000000000000000000000000000000000000000000000e0",
+        "0000000000000000000000000000000000000000000000000000000000000002",
+        "0000000000000000000000000000000000000000000000000000000000000456",
+        "0000000000000000000000000000000000000000000000000000000000000789",
+        "000000000000000000000000000000000000000000000000000000000000000d",
+        "48656c6c6f2c20776f726c642100000000000000000000000000000000000000",
+    );
+    assert_eq!(hex::encode(calldata), expected);
+}
+
+#[test]
+fn test_nested_roundtrip_matches_alloy() {
+    let address: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+    let values = vec![
+        AbiValue::Tuple(vec![AbiValue::uint(1), AbiValue::Tuple(vec![AbiValue::Bool(true), AbiValue::uint(2)])]),
+        AbiValue::Array(vec![
+            AbiValue::Tuple(vec![AbiValue::Address(address), AbiValue::String("first".to_string())]),
+            AbiValue::Tuple(vec![AbiValue::Address(Address::ZERO), AbiValue::String(String::new())]),
+        ]),
+        AbiValue::FixedArray(vec![AbiValue::Bytes(vec![1, 2, 3]), AbiValue::Bytes(vec![0xff; 40])]),
+    ];
+    let expected = DynSolValue::Tuple(vec![
+        DynSolValue::Tuple(vec![
+            DynSolValue::Uint(U256::from(1), 256),
+            DynSolValue::Tuple(vec![DynSolValue::Bool(true), DynSolValue::Uint(U256::from(2), 256)]),
+        ]),
+        DynSolValue::Array(vec![
+            DynSolValue::Tuple(vec![DynSolValue::Address(address), DynSolValue::String("first".to_string())]),
+            DynSolValue::Tuple(vec![DynSolValue::Address(Address::ZERO), DynSolValue::String(String::new())]),
+        ]),
+        DynSolValue::FixedArray(vec![DynSolValue::Bytes(vec![1, 2, 3]), DynSolValue::Bytes(vec![0xff; 40])]),
+    ]);
+
+    let encoded = abi::encode(&values);
+    assert_eq!(encoded, expected.abi_encode_params());
+
+    let types = ["(uint256,(bool,uint256))", "(address,string)[]", "bytes[2]"].map(|ty| AbiType::parse(ty).unwrap());
+    assert_eq!(abi::decode(&types, &encoded), Ok(values));
+}
+
+#[test]
+fn test_decode_rejects_truncated_data() {
+    let encoded = abi::encode(&[AbiValue::Bytes(vec![7; 64])]);
+    assert!(matches!(abi::decode(&[AbiType::Bytes], &encoded[..80]), Err(AbiError::OutOfBounds { .. })));
+    assert!(matches!(abi::decode(&[AbiType::FixedBytes(4), AbiType::Bool], &[0u8; 32]), Err(AbiError::OutOfBounds { .. })));
+    assert_eq!(abi::decode(&[AbiType::FixedBytes(4)], &[0xab; 32]), Ok(vec![AbiValue::FixedBytes(FixedBytes::<4>::repeat_byte(0xab).to_vec())]));
+}
+
+#[test]
+fn test_decode_return_data_from_machine() {
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+    let mut machine = Machine::default();
+    // returns calldata[4..36] * 2
+    machine.accounts.insert(contract, Account {
+        code: Rc::new(assemble("PUSH1 0x04 CALLDATALOAD PUSH1 0x02 MUL PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")),
+        ..Default::default()
+    });
+
+    let calldata = abi::encode_call("double(uint256)", &[AbiValue::uint(21)]);
+    let ExecutionResult::Success(data) = machine.call(Address::ZERO, contract, calldata, 100_000) else {
+        panic!("call failed");
+    };
+    let decoded = abi::decode(&[AbiType::Uint(256)], &data).unwrap();
+    assert_eq!(decoded[0].as_uint(), Some(U256::from(42)));
+}
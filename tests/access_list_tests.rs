@@ -0,0 +1,85 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, ExecutionResult, Machine, Transaction};
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+mod common;
+use common::assemble;
+
+fn sender() -> Address {
+    "0x3000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn outer() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn inner() -> Address {
+    "0x2100000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn machine() -> Machine {
+    let mut machine = Machine::default();
+    machine.block.coinbase = "0x4000000000000000000000000000000000000000".parse().unwrap();
+    machine.accounts.insert(sender(), Account { balance: U256::from(1_000_000), ..Default::default() });
+
+    // reads slot 1, writes slot 2, then calls `inner`
+    let mut outer_account = Account::with_code(assemble(
+        "PUSH1 0x01 SLOAD POP PUSH1 0x07 PUSH1 0x02 SSTORE \
+         PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 0x2100000000000000000000000000000000000000 PUSH2 0xffff CALL STOP",
+    ));
+    outer_account.storage = HashMap::from([(U256::from(1), U256::from(11)), (U256::from(2), U256::from(22))]);
+    machine.accounts.insert(outer(), outer_account);
+
+    let mut inner_account = Account::with_code(assemble("PUSH1 0x05 SLOAD STOP"));
+    inner_account.storage = HashMap::from([(U256::from(5), U256::from(55)), (U256::from(6), U256::from(66))]);
+    machine.accounts.insert(inner(), inner_account);
+    machine
+}
+
+fn tx() -> Transaction {
+    Transaction { caller: sender(), to: outer(), gas_limit: 100_000, gas_price: U256::from(1), ..Default::default() }
+}
+
+#[test]
+fn test_access_list_covers_nested_reads_and_writes() {
+    let mut machine = machine();
+    let created = machine.create_access_list(&tx()).unwrap();
+
+    assert_eq!(created.outcome.result, ExecutionResult::Success(vec![]));
+    assert_eq!(created.access_list, vec![
+        (outer(), vec![U256::from(1), U256::from(2)]),
+        (inner(), vec![U256::from(5)]),
+    ]);
+}
+
+#[test]
+fn test_witness_holds_pre_state_of_touched_slots() {
+    let mut machine = machine();
+    let witness = machine.create_access_list(&tx()).unwrap().witness;
+
+    let mut addresses: Vec<_> = witness.accounts.keys().copied().collect();
+    addresses.sort();
+    assert_eq!(addresses, vec![outer(), inner(), sender(), machine.block.coinbase]);
+
+    let outer_account = &witness.accounts[&outer()];
+    assert_eq!(outer_account.storage[&U256::from(2)], U256::from(22));
+    assert_eq!(outer_account.code, machine.code(outer()).unwrap());
+    assert_eq!(witness.accounts[&inner()].storage, HashMap::from([(U256::from(5), U256::from(55))]));
+    assert_eq!(witness.accounts[&sender()].balance, U256::from(1_000_000));
+
+    assert_eq!(machine.storage(outer(), U256::from(2)), Ok(U256::from(22)));
+}
+
+#[test]
+fn test_witness_is_enough_to_re_execute() {
+    let mut machine = machine();
+    let created = machine.create_access_list(&tx()).unwrap();
+
+    let mut stateless = Machine::default();
+    stateless.block = machine.block.clone();
+    stateless.accounts = created.witness.accounts;
+    let outcome = stateless.transact(&tx()).unwrap();
+    assert_eq!(outcome.gas_used, created.outcome.gas_used);
+    assert_eq!(stateless.storage(outer(), U256::from(2)), Ok(U256::from(7)));
+}
@@ -0,0 +1,82 @@
+use alloy::primitives::{keccak256, Address};
+use native_vs_evm::artifacts::{load_combined_json, load_foundry_out, ArtifactError};
+use native_vs_evm::evm::{ExecutionResult, Machine};
+use ruint::aliases::U256;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+mod common;
+use common::assemble;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("native-vs-evm-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// PUSH20 <library> PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN, with the address left as a placeholder
+fn linked_code_hex(placeholder: &str) -> String {
+    format!("73{}{}", placeholder, hex::encode(assemble("PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")))
+}
+
+#[test]
+fn test_load_combined_json_and_link() {
+    let dir = temp_dir("combined");
+    let hash = hex::encode(keccak256("src/Math.sol:Math"));
+    let json = format!(
+        r#"{{"contracts": {{
+            "src/Counter.sol:Counter": {{"bin-runtime": "{}"}},
+            "src/Uses.sol:Uses": {{"bin-runtime": "{}"}}
+        }}, "version": "0.8.26"}}"#,
+        hex::encode(assemble("PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")),
+        linked_code_hex(&format!("__${}$__", &hash[..34])),
+    );
+    fs::write(dir.join("combined.json"), json).unwrap();
+
+    let artifacts = load_combined_json(dir.join("combined.json")).unwrap();
+    let counter = artifacts.iter().find(|a| a.name == "Counter").unwrap();
+    let uses = artifacts.iter().find(|a| a.name == "Uses").unwrap();
+    assert_eq!(uses.fully_qualified_name(), "src/Uses.sol:Uses");
+    assert!(matches!(uses.bytecode(), Err(ArtifactError::UnlinkedLibrary(_))));
+
+    let library: Address = "0x00000000000000000000000000000000000000aa".parse().unwrap();
+    let libraries = HashMap::from([("src/Math.sol:Math".to_string(), library)]);
+    let counter_address: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+    let uses_address: Address = "0x3000000000000000000000000000000000000000".parse().unwrap();
+
+    let mut machine = Machine::default();
+    machine.deploy_artifact(counter_address, counter, &HashMap::new()).unwrap();
+    machine.deploy_artifact(uses_address, uses, &libraries).unwrap();
+
+    let result = machine.call(Address::ZERO, counter_address, vec![], 100_000);
+    assert_eq!(result, ExecutionResult::Success(U256::from(42).to_be_bytes::<32>().to_vec()));
+    let result = machine.call(Address::ZERO, uses_address, vec![], 100_000);
+    assert_eq!(result, ExecutionResult::Success(library.into_word().to_vec()));
+}
+
+#[test]
+fn test_load_foundry_out() {
+    let dir = temp_dir("foundry");
+    fs::create_dir_all(dir.join("Counter.sol")).unwrap();
+    fs::create_dir_all(dir.join("IERC20.sol")).unwrap();
+    fs::create_dir_all(dir.join("build-info")).unwrap();
+
+    let legacy_placeholder = format!("__{:_<38}", "src/Math.sol:Math");
+    fs::write(dir.join("Counter.sol/Counter.json"), format!(
+        r#"{{"abi": [], "deployedBytecode": {{"object": "0x{}", "linkReferences": {{}}}},
+            "metadata": {{"settings": {{"compilationTarget": {{"src/Counter.sol": "Counter"}}}}}}}}"#,
+        linked_code_hex(&legacy_placeholder),
+    )).unwrap();
+    fs::write(dir.join("IERC20.sol/IERC20.json"), r#"{"abi": [], "deployedBytecode": {"object": "0x"}}"#).unwrap();
+    fs::write(dir.join("build-info/abc.json"), r#"{"id": "abc"}"#).unwrap();
+
+    let artifacts = load_foundry_out(&dir).unwrap();
+    assert_eq!(artifacts.len(), 1);
+    assert_eq!(artifacts[0].fully_qualified_name(), "src/Counter.sol:Counter");
+
+    let library: Address = "0x00000000000000000000000000000000000000bb".parse().unwrap();
+    let account = artifacts[0].to_account(&HashMap::from([("src/Math.sol:Math".to_string(), library)])).unwrap();
+    assert_eq!(&account.code[1..21], library.as_slice());
+}
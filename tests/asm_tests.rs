@@ -0,0 +1,172 @@
+use alloy::primitives::{Address, U256};
+use native_vs_evm::evm::{ExecutionResult, Machine};
+use native_vs_evm::evm_asm;
+
+mod common;
+use common::{assemble, contract};
+
+#[test]
+fn test_matches_the_string_assembler() {
+    const CODE: [u8; 11] = evm_asm! { push 0x2a; push 0; SSTORE; push 0; sload; dup1; swap1; return };
+
+    assert_eq!(CODE.to_vec(), assemble("PUSH1 0x2a PUSH1 0x00 SSTORE PUSH1 0x00 SLOAD DUP1 SWAP1 RETURN"));
+}
+
+#[test]
+fn test_push_literals() {
+    let code = evm_asm! {
+        push 256;
+        push 0x0000ff;
+        push 1_000_000;
+        push 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff;
+        push 115792089237316195423570985008687907853269984665640564039457584007913129639935;
+    };
+    let max = [vec![0x7f], vec![0xff; 32]].concat();
+
+    assert_eq!(code.to_vec(), [assemble("PUSH2 0x0100 PUSH1 0xff PUSH3 0x0f4240"), max.clone(), max].concat());
+}
+
+#[test]
+fn test_labels_run_a_loop() {
+    // sums 5 + 4 + 3 + 2 + 1 into slot 0
+    let code = evm_asm! {
+        push 0; push 5;
+        loop:
+        dup1; iszero; jumpi done;
+        dup1; swap2; add; swap1;
+        push 1; sub;
+        jump loop;
+        done:
+        pop; push 0; sstore;
+        stop;
+    };
+    assert_eq!(&code[4..10], &assemble("JUMPDEST DUP1 ISZERO PUSH2 0x0016")[..]);
+
+    let mut machine = Machine::default();
+    machine.deploy(contract(), code.to_vec());
+    assert_eq!(machine.call(Address::ZERO, contract(), vec![], 1_000_000), ExecutionResult::Success(vec![]));
+    assert_eq!(machine.storage(contract(), U256::ZERO).unwrap(), U256::from(15));
+}
+
+#[test]
+fn test_push_label_for_computed_jumps() {
+    let code = evm_asm! { push target; jump; invalid; target: stop };
+
+    assert_eq!(code.to_vec(), assemble("PUSH2 0x0005 JUMP INVALID JUMPDEST STOP"));
+}
+
+#[test]
+fn test_long_bodies_do_not_recurse() {
+    // 200 statements of straight-line code, well past what a recursive macro gets through
+    let code = evm_asm! {
+        push 0;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 1; add;
+        push 0; sstore;
+        stop;
+    };
+    assert_eq!(code.len(), 2 + 100 * 3 + 4);
+
+    let mut machine = Machine::default();
+    machine.deploy(contract(), code.to_vec());
+    assert_eq!(machine.call(Address::ZERO, contract(), vec![], 1_000_000), ExecutionResult::Success(vec![]));
+    assert_eq!(machine.storage(contract(), U256::ZERO).unwrap(), U256::from(100));
+}
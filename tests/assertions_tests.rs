@@ -0,0 +1,105 @@
+use alloy::primitives::{keccak256, Address, U256};
+use alloy::sol_types::{SolCall, SolError, SolEvent};
+use native_vs_evm::evm::{Account, ExecutionResult, Host, Machine, Transaction};
+use native_vs_evm::tokens::{Erc20, IERC20};
+
+mod common;
+use common::{alice, assemble, contract};
+
+fn transfer(to: Address, amount: u64) -> Vec<u8> {
+    IERC20::transferCall { to, amount: U256::from(amount) }.abi_encode()
+}
+
+fn erc20_balance_slot(owner: Address) -> U256 {
+    let preimage = [owner.into_word().as_slice(), &U256::from(1).to_be_bytes::<32>()].concat();
+    U256::from_be_bytes(keccak256(preimage).0)
+}
+
+#[test]
+fn test_chained_expectations_on_a_token_transfer() {
+    let mut machine = Machine::default();
+    let erc20 = Erc20::deploy(&mut machine, contract());
+    erc20.mint(&mut machine, alice(), U256::from(100)).unwrap();
+
+    let balance_slot = erc20_balance_slot(Address::ZERO);
+    machine
+        .expect_call(alice(), contract(), transfer(Address::ZERO, 40), 100_000)
+        .expect_success()
+        .expect_return(&U256::from(1).to_be_bytes::<32>())
+        .expect_log(contract(), IERC20::Transfer::SIGNATURE_HASH)
+        .expect_storage(contract(), balance_slot, U256::from(40))
+        // two flat-priced SSTOREs plus the surrounding code
+        .expect_gas_between(40_000, 50_000);
+
+    machine.expect_call(alice(), contract(), transfer(Address::ZERO, 61), 100_000).expect_revert().expect_no_logs();
+}
+
+#[test]
+fn test_revert_reasons_and_halts() {
+    // Error("not allowed") laid out from byte 28 on, so the 4-byte selector ends a word
+    let reason = alloy::sol_types::Revert::from("not allowed").abi_encode();
+    let shifted = [&[0u8; 28][..], &reason].concat();
+    let mut code = String::new();
+    for (i, word) in shifted.chunks(32).enumerate() {
+        code += &format!("PUSH32 0x{:0<64} PUSH1 {} MSTORE ", hex::encode(word), 0x20 * i);
+    }
+    code += &format!("PUSH1 {} PUSH1 0x1c REVERT", reason.len());
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract(), Account::with_code(assemble(&code)));
+
+    machine.expect_call(Address::ZERO, contract(), vec![], 100_000).expect_revert().expect_revert_with("not allowed");
+    machine.accounts.insert(contract(), Account::with_code(assemble("PUSH1 0x00 JUMP")));
+    machine.expect_call(Address::ZERO, contract(), vec![], 100_000).expect_halt(ExecutionResult::InvalidJump).expect_gas_between(100_000, 100_000);
+}
+
+#[test]
+#[should_panic(expected = "expected a revert with \"other\"")]
+fn test_wrong_revert_reason_panics() {
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract(), Account::with_code(assemble("PUSH1 0x00 PUSH1 0x00 REVERT")));
+    machine.expect_call(Address::ZERO, contract(), vec![], 100_000).expect_revert_with("other");
+}
+
+#[test]
+#[should_panic(expected = "storage of")]
+fn test_storage_mismatch_panics() {
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract(), Account::with_code(assemble("PUSH1 0x2a PUSH1 0x00 SSTORE STOP")));
+    machine.expect_call(Address::ZERO, contract(), vec![], 100_000).expect_success().expect_storage(contract(), U256::ZERO, U256::from(41));
+}
+
+#[test]
+fn test_expect_transact() {
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract(), Account::with_code(assemble("PUSH1 0x2a PUSH1 0x00 SSTORE STOP")));
+    machine.accounts.insert(alice(), Account::default());
+    let tx = Transaction { caller: alice(), to: contract(), gas_limit: 100_000, ..Default::default() };
+    machine.expect_transact(&tx).expect_success().expect_storage(contract(), U256::ZERO, U256::from(42)).expect_gas_between(21_000 + 20_000, 21_000 + 20_006);
+}
+
+// every slot the contract never wrote holds 7
+#[derive(Debug)]
+struct SevensHost;
+
+impl Host for SevensHost {
+    fn basic(&mut self, address: Address) -> Result<Account, String> {
+        match address {
+            a if a == contract() => Ok(Account::with_code(assemble("PUSH1 0x2a PUSH1 0x00 SSTORE STOP"))),
+            _ => Ok(Account::default()),
+        }
+    }
+
+    fn storage(&mut self, _address: Address, _key: U256) -> Result<U256, String> {
+        Ok(U256::from(7))
+    }
+}
+
+#[test]
+fn test_expect_storage_reads_through_the_host() {
+    let mut machine = Machine::with_host(SevensHost);
+    machine
+        .expect_call(Address::ZERO, contract(), vec![], 100_000)
+        .expect_success()
+        .expect_storage(contract(), U256::ZERO, U256::from(42))
+        .expect_storage(contract(), U256::from(1), U256::from(7));
+}
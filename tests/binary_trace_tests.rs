@@ -0,0 +1,60 @@
+use alloy::primitives::Address;
+use native_vs_evm::binary_trace::{BinaryTracer, TraceReader};
+use native_vs_evm::evm::{Account, Machine};
+use native_vs_evm::tracer::{StreamingTracer, StructLogger, TraceFormat};
+
+mod common;
+use common::{assemble, contract};
+
+// loops a few times around a CALL into a contract that returns a word
+fn machine() -> Machine {
+    let sub: Address = "0x2100000000000000000000000000000000000000".parse().unwrap();
+    let mut machine = Machine::default();
+    machine.accounts.insert(sub, Account::with_code(assemble("PUSH32 0xff00000000000000000000000000000000000000000000000000000000000001 PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")));
+    machine.accounts.insert(contract(), Account::with_code(assemble(&format!(
+        "PUSH1 0x03 JUMPDEST PUSH1 0x20 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 {} PUSH2 0xffff CALL POP \
+         PUSH1 0x01 SUB DUP1 PUSH1 0x02 JUMPI STOP",
+        sub
+    ))));
+    machine
+}
+
+#[test]
+fn test_replay_matches_the_struct_logger() {
+    let mut logger = StructLogger::default();
+    machine().call_with_inspector(Address::ZERO, contract(), vec![], 1_000_000, &mut logger);
+
+    let mut tracer = BinaryTracer::new(Vec::new()).unwrap();
+    machine().call_with_inspector(Address::ZERO, contract(), vec![], 1_000_000, &mut tracer);
+    let trace = tracer.finish().unwrap();
+
+    let replayed: Vec<_> = TraceReader::new(trace.as_slice()).unwrap().collect::<Result<_, _>>().unwrap();
+    assert!(logger.logs.iter().any(|log| log.depth == 2));
+    assert_eq!(replayed, logger.logs);
+}
+
+#[test]
+fn test_binary_trace_is_much_smaller_than_json() {
+    let mut tracer = BinaryTracer::new(Vec::new()).unwrap();
+    machine().call_with_inspector(Address::ZERO, contract(), vec![], 1_000_000, &mut tracer);
+    let binary = tracer.finish().unwrap();
+
+    let mut tracer = StreamingTracer::new(Vec::new(), TraceFormat::JsonLines);
+    machine().call_with_inspector(Address::ZERO, contract(), vec![], 1_000_000, &mut tracer);
+    let json = tracer.finish().unwrap();
+
+    assert!(binary.len() * 5 < json.len(), "{} vs {}", binary.len(), json.len());
+}
+
+#[test]
+fn test_truncated_and_foreign_input_is_rejected() {
+    assert!(TraceReader::new(&b"{\"pc\":0}"[..]).is_err());
+
+    let mut tracer = BinaryTracer::new(Vec::new()).unwrap();
+    machine().call_with_inspector(Address::ZERO, contract(), vec![], 1_000_000, &mut tracer);
+    let trace = tracer.finish().unwrap();
+
+    let mut steps = TraceReader::new(&trace[..trace.len() - 1]).unwrap();
+    assert!(steps.by_ref().take_while(Result::is_ok).count() > 0);
+    assert!(steps.next().is_none());
+}
@@ -0,0 +1,131 @@
+use alloy::primitives::{Address, B256};
+use native_vs_evm::block::{Block, BlockError, Header, BLOB_GAS_PER_BLOB, TARGET_BLOB_GAS_PER_BLOCK};
+use native_vs_evm::chain::SimpleChain;
+use native_vs_evm::evm::{Account, ExecutionResult, Hardfork, Machine, Transaction, TransactionError};
+use ruint::aliases::U256;
+
+mod common;
+use common::{assemble, contract};
+
+const FUNDS: u64 = 1_000_000_000_000;
+
+fn sender() -> Address {
+    "0x3000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn blob_hash(n: u8) -> B256 {
+    let mut hash = B256::repeat_byte(n);
+    hash[0] = 0x01;
+    hash
+}
+
+fn machine() -> Machine {
+    let mut machine = Machine::default();
+    machine.hardfork = Hardfork::Cancun;
+    machine.accounts.insert(sender(), Account { balance: U256::from(FUNDS), ..Default::default() });
+    // storage[0] = BLOBHASH(0), storage[1] = BLOBHASH(5)
+    machine.accounts.insert(contract(), Account::with_code(assemble("PUSH1 0x00 BLOBHASH PUSH1 0x00 SSTORE PUSH1 0x05 BLOBHASH PUSH1 0x01 SSTORE STOP")));
+    machine
+}
+
+fn blob_tx(blobs: usize) -> Transaction {
+    Transaction {
+        caller: sender(),
+        to: contract(),
+        gas_limit: 100_000,
+        blob_versioned_hashes: (0..blobs as u8).map(blob_hash).collect(),
+        max_fee_per_blob_gas: U256::from(10),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_blob_base_fee_follows_excess_blob_gas() {
+    let header = |excess_blob_gas| Header { excess_blob_gas, ..Default::default() };
+    assert_eq!(header(0).blob_base_fee(), U256::from(1));
+    // e^1, rounded down
+    assert_eq!(header(3_338_477).blob_base_fee(), U256::from(2));
+    assert_eq!(header(10 * 3_338_477).blob_base_fee(), U256::from(22026));
+
+    assert_eq!(header(0).next_excess_blob_gas(2 * BLOB_GAS_PER_BLOB), 0);
+    assert_eq!(header(BLOB_GAS_PER_BLOB).next_excess_blob_gas(TARGET_BLOB_GAS_PER_BLOCK + BLOB_GAS_PER_BLOB), 2 * BLOB_GAS_PER_BLOB);
+}
+
+#[test]
+fn test_blob_transaction_pays_blob_gas_and_exposes_hashes() {
+    let mut machine = machine();
+    machine.block.excess_blob_gas = 3_338_477;
+
+    let outcome = machine.transact(&blob_tx(2)).unwrap();
+    assert_eq!(outcome.result, ExecutionResult::Success(vec![]));
+    assert_eq!(machine.storage(contract(), U256::ZERO), Ok(U256::from_be_bytes(blob_hash(0).0)));
+    assert_eq!(machine.storage(contract(), U256::from(1)), Ok(U256::ZERO));
+
+    // gas price is zero, so only the blob fee at a blob base fee of 2 is paid
+    let blob_fee = 2 * BLOB_GAS_PER_BLOB * 2;
+    assert_eq!(machine.accounts[&sender()].balance, U256::from(FUNDS - blob_fee));
+}
+
+#[test]
+fn test_blob_fee_is_burned_on_revert() {
+    let mut machine = machine();
+    machine.deploy(contract(), assemble("PUSH1 0x00 PUSH1 0x00 REVERT"));
+
+    let outcome = machine.transact(&blob_tx(1)).unwrap();
+    assert_eq!(outcome.result, ExecutionResult::Revert(vec![]));
+    assert_eq!(machine.accounts[&sender()].balance, U256::from(FUNDS - BLOB_GAS_PER_BLOB));
+}
+
+#[test]
+fn test_invalid_blob_transactions() {
+    let mut machine = machine();
+    assert_eq!(machine.transact(&blob_tx(7)).unwrap_err(), TransactionError::TooManyBlobs);
+
+    let mut bad_version = blob_tx(1);
+    bad_version.blob_versioned_hashes[0][0] = 0x02;
+    assert_eq!(machine.transact(&bad_version).unwrap_err(), TransactionError::InvalidBlobHash);
+
+    machine.block.excess_blob_gas = 10 * 3_338_477;
+    assert_eq!(machine.transact(&blob_tx(1)).unwrap_err(), TransactionError::BlobFeeCapTooLow);
+
+    machine.hardfork = Hardfork::Shanghai;
+    assert_eq!(machine.transact(&blob_tx(1)).unwrap_err(), TransactionError::BlobsNotActive);
+
+    // max_fee_per_blob_gas * blob gas counts towards the balance check
+    let mut machine = self::machine();
+    machine.accounts.get_mut(&sender()).unwrap().balance = U256::from(10 * BLOB_GAS_PER_BLOB - 1);
+    assert_eq!(machine.transact(&blob_tx(1)).unwrap_err(), TransactionError::InsufficientFunds);
+}
+
+#[test]
+fn test_blobhash_needs_cancun() {
+    let mut machine = machine();
+    machine.hardfork = Hardfork::Shanghai;
+    assert_eq!(machine.transact(&blob_tx(0)).unwrap().result, ExecutionResult::InvalidOpcode);
+}
+
+#[test]
+fn test_block_blob_gas_limit() {
+    let mut machine = machine();
+    let first = Transaction { nonce: Some(0), ..blob_tx(4) };
+    let second = Transaction { nonce: Some(1), ..blob_tx(3) };
+
+    let block = Block { header: Header { gas_limit: 1_000_000, ..Default::default() }, transactions: vec![first, second] };
+    assert_eq!(machine.execute_block(&block), Err(BlockError::BlobGasLimitExceeded {
+        index: 1,
+        blob_gas: 3 * BLOB_GAS_PER_BLOB,
+        blob_gas_available: 2 * BLOB_GAS_PER_BLOB,
+    }));
+}
+
+#[test]
+fn test_chain_tracks_excess_blob_gas() {
+    let mut chain = SimpleChain::new(machine());
+    let mined = chain.mine(vec![Transaction { nonce: Some(0), ..blob_tx(6) }]).unwrap();
+    assert_eq!(mined.blob_gas_used, 6 * BLOB_GAS_PER_BLOB);
+
+    chain.mine_empty(1);
+    assert_eq!(chain.latest().header.excess_blob_gas, 3 * BLOB_GAS_PER_BLOB);
+    chain.mine_empty(1);
+    assert_eq!(chain.latest().header.excess_blob_gas, 0);
+}
@@ -0,0 +1,104 @@
+use alloy::primitives::Address;
+use native_vs_evm::block::{Block, BlockError, Header};
+use native_vs_evm::evm::{Account, Machine, Transaction, TransactionError};
+use ruint::aliases::U256;
+
+mod common;
+use common::assemble;
+
+fn sender() -> Address {
+    "0x3000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn coinbase() -> Address {
+    "0x4000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn recorder() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn reverter() -> Address {
+    "0x2100000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn machine() -> Machine {
+    let mut machine = Machine::default();
+    machine.accounts.insert(sender(), Account { balance: U256::from(10u64.pow(12)), ..Default::default() });
+    // storage[NUMBER] = TIMESTAMP
+    machine.accounts.insert(recorder(), Account::with_code(assemble("TIMESTAMP NUMBER SSTORE STOP")));
+    machine.accounts.insert(reverter(), Account::with_code(assemble("PUSH1 0x00 PUSH1 0x00 REVERT")));
+    machine
+}
+
+fn header() -> Header {
+    Header { number: 7, timestamp: 1_700_000_000, coinbase: coinbase(), gas_limit: 200_000, base_fee: U256::from(10), ..Default::default() }
+}
+
+fn tx(to: Address, nonce: u64) -> Transaction {
+    Transaction {
+        caller: sender(),
+        to,
+        gas_limit: 50_000,
+        gas_price: U256::from(100),
+        gas_priority_fee: Some(U256::from(2)),
+        nonce: Some(nonce),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_execute_block_returns_receipts_and_pays_coinbase() {
+    let mut machine = machine();
+    let block = Block { header: header(), transactions: vec![tx(recorder(), 0), tx(reverter(), 1), tx(recorder(), 2)] };
+
+    let outcome = machine.execute_block(&block).unwrap();
+    let statuses: Vec<bool> = outcome.receipts.iter().map(|receipt| receipt.status).collect();
+    assert_eq!(statuses, vec![true, false, true]);
+
+    let recorder_gas = 21000 + 2 + 2 + 20000;
+    let reverter_gas = 21000 + 3 + 3;
+    let cumulative: Vec<u64> = outcome.receipts.iter().map(|receipt| receipt.cumulative_gas_used).collect();
+    assert_eq!(cumulative, vec![recorder_gas, recorder_gas + reverter_gas, 2 * recorder_gas + reverter_gas]);
+    assert_eq!(outcome.gas_used, 2 * recorder_gas + reverter_gas);
+
+    assert_eq!(machine.accounts[&recorder()].storage[&U256::from(7)], U256::from(1_700_000_000u64));
+    assert_eq!(machine.accounts[&coinbase()].balance, U256::from(2 * outcome.gas_used));
+    assert_eq!(machine.accounts[&sender()].balance, U256::from(10u64.pow(12) - 12 * outcome.gas_used));
+    assert_eq!(machine.block, header());
+}
+
+#[test]
+fn test_block_gas_limit() {
+    let mut machine = machine();
+    let block = Block {
+        header: Header { gas_limit: 2 * 41_004 + 50_000, ..header() },
+        transactions: vec![tx(recorder(), 0), tx(recorder(), 1), tx(recorder(), 2)],
+    };
+
+    // the limit is checked against gas actually used, not the gas limits of earlier transactions
+    assert!(machine.execute_block(&block).is_ok());
+
+    let mut machine = self::machine();
+    let block = Block {
+        header: Header { gas_limit: 90_000, ..header() },
+        transactions: vec![tx(recorder(), 0), tx(recorder(), 1), tx(recorder(), 2)],
+    };
+    assert_eq!(
+        machine.execute_block(&block),
+        Err(BlockError::GasLimitExceeded { index: 1, gas_limit: 50_000, gas_available: 90_000 - 41_004 })
+    );
+    assert_eq!(machine.accounts[&sender()].nonce, 0);
+    assert!(machine.accounts[&recorder()].storage.is_empty());
+}
+
+#[test]
+fn test_invalid_transaction_rejects_block() {
+    let mut machine = machine();
+    let underpriced = Transaction { gas_price: U256::from(5), gas_priority_fee: None, ..tx(recorder(), 1) };
+    let block = Block { header: header(), transactions: vec![tx(recorder(), 0), underpriced] };
+
+    assert_eq!(machine.execute_block(&block), Err(BlockError::InvalidTransaction { index: 1, error: TransactionError::FeeCapTooLow }));
+    assert_eq!(machine.accounts[&sender()].nonce, 0);
+    assert_eq!(machine.block, Header::default());
+}
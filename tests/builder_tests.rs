@@ -0,0 +1,63 @@
+use alloy::primitives::{Address, U256};
+use native_vs_evm::builder::{BuildError, BytecodeBuilder, CallParams};
+use native_vs_evm::evm::{ExecutionResult, Machine};
+
+mod common;
+use common::{assemble, contract, sub};
+
+#[test]
+fn test_push_picks_the_shortest_encoding() {
+    let mut builder = BytecodeBuilder::new();
+    builder.push(U256::ZERO).push(U256::from(0x1234)).push(U256::MAX).dup(2).swap(16).log(4);
+
+    let expected = [assemble("PUSH1 0x00 PUSH2 0x1234"), vec![0x7f], vec![0xff; 32], assemble("DUP2 SWAP16 LOG4")].concat();
+    assert_eq!(builder.build().unwrap(), expected);
+}
+
+#[test]
+fn test_labels_resolve_forward_and_backward() {
+    // sums 5 + 4 + 3 + 2 + 1 into slot 0
+    let mut builder = BytecodeBuilder::new();
+    builder.push(U256::ZERO).push(U256::from(5))
+        .jumpdest("loop")
+        .dup(1).iszero().jumpi("done")
+        .dup(1).swap(2).add().swap(1)
+        .push(U256::from(1)).sub()
+        .jump("loop")
+        .jumpdest("done")
+        .pop().push(U256::ZERO).sstore().stop();
+    let code = builder.build().unwrap();
+    assert_eq!(&code[7..10], &assemble("PUSH2 0x0016")[..]);
+    assert_eq!(&code[18..21], &assemble("PUSH2 0x0004")[..]);
+
+    let mut machine = Machine::default();
+    machine.deploy(contract(), code);
+    assert_eq!(machine.call(Address::ZERO, contract(), vec![], 1_000_000), ExecutionResult::Success(vec![]));
+    assert_eq!(machine.storage(contract(), U256::ZERO).unwrap(), U256::from(15));
+}
+
+#[test]
+fn test_call_pushes_operands_in_order() {
+    let mut callee = BytecodeBuilder::new();
+    callee.caller().push(U256::ZERO).sstore().stop();
+    let mut caller = BytecodeBuilder::new();
+    caller.call(CallParams { gas: 50_000, to: sub(), ..Default::default() }).push(U256::from(1)).sstore().stop();
+
+    let mut machine = Machine::default();
+    machine.deploy(sub(), callee.build().unwrap());
+    machine.deploy(contract(), caller.build().unwrap());
+    assert_eq!(machine.call(Address::ZERO, contract(), vec![], 1_000_000), ExecutionResult::Success(vec![]));
+    assert_eq!(machine.storage(contract(), U256::from(1)).unwrap(), U256::from(1));
+    assert_eq!(machine.storage(sub(), U256::ZERO).unwrap(), U256::from_be_slice(contract().as_slice()));
+}
+
+#[test]
+fn test_label_errors() {
+    let mut builder = BytecodeBuilder::new();
+    builder.jump("missing");
+    assert_eq!(builder.build(), Err(BuildError::UndefinedLabel("missing".into())));
+
+    let mut builder = BytecodeBuilder::new();
+    builder.jumpdest("twice").jumpdest("twice");
+    assert_eq!(builder.build(), Err(BuildError::DuplicateLabel("twice".into())));
+}
@@ -0,0 +1,97 @@
+use alloy::primitives::Address;
+use native_vs_evm::bundle::Change;
+use native_vs_evm::evm::{Account, ExecutionResult, Host, Machine, Transaction, TransactionError};
+use ruint::aliases::U256;
+
+mod common;
+use common::assemble;
+
+const FUNDS: u64 = 1_000_000_000_000;
+
+fn sender() -> Address {
+    "0x3000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn claim() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+// first writer wins: storage[0] = calldata[0..32] if it is still zero, otherwise revert
+fn claim_code() -> Vec<u8> {
+    assemble("PUSH1 0x00 SLOAD PUSH1 0x0d JUMPI PUSH1 0x00 CALLDATALOAD PUSH1 0x00 SSTORE STOP JUMPDEST PUSH1 0x00 PUSH1 0x00 REVERT")
+}
+
+// serves the funded sender and the claim contract, so nothing is cached up front
+#[derive(Debug)]
+struct PrestateHost;
+
+impl Host for PrestateHost {
+    fn basic(&mut self, address: Address) -> Result<Account, String> {
+        Ok(match address {
+            a if a == sender() => Account { balance: U256::from(FUNDS), ..Default::default() },
+            a if a == claim() => Account::with_code(claim_code()),
+            _ => Account::default(),
+        })
+    }
+
+    fn storage(&mut self, _address: Address, _key: U256) -> Result<U256, String> {
+        Ok(U256::ZERO)
+    }
+}
+
+fn tx(nonce: u64, value: u64) -> Transaction {
+    Transaction {
+        caller: sender(),
+        to: claim(),
+        data: U256::from(value).to_be_bytes::<32>().to_vec(),
+        gas_limit: 100_000,
+        gas_price: U256::from(1),
+        nonce: Some(nonce),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_bundle_runs_in_order_and_reports_the_diff() {
+    let mut machine = Machine::with_host(PrestateHost);
+    let outcome = machine.simulate_bundle(&[tx(0, 1), tx(1, 2)]).unwrap();
+
+    let results: Vec<_> = outcome.results.iter().map(|result| &result.as_ref().unwrap().result).collect();
+    assert_eq!(results, vec![&ExecutionResult::Success(vec![]), &ExecutionResult::Revert(vec![])]);
+
+    let gas_used: u64 = outcome.results.iter().map(|result| result.as_ref().unwrap().gas_used).sum();
+    let sender_diff = &outcome.state_diff[&sender()];
+    assert_eq!(sender_diff.nonce, Some(Change { before: 0, after: 2 }));
+    assert_eq!(sender_diff.balance, Some(Change { before: U256::from(FUNDS), after: U256::from(FUNDS - gas_used) }));
+
+    let claim_diff = &outcome.state_diff[&claim()];
+    assert_eq!(claim_diff.balance, None);
+    assert_eq!(claim_diff.storage[&U256::ZERO], Change { before: U256::ZERO, after: U256::from(1) });
+}
+
+#[test]
+fn test_bundle_order_changes_the_outcome() {
+    let mut machine = Machine::with_host(PrestateHost);
+    let outcome = machine.simulate_bundle(&[tx(0, 2), tx(1, 1)]).unwrap();
+    assert_eq!(outcome.state_diff[&claim()].storage[&U256::ZERO].after, U256::from(2));
+}
+
+#[test]
+fn test_bundle_commits_nothing() {
+    let mut machine = Machine::with_host(PrestateHost);
+    machine.simulate_bundle(&[tx(0, 1)]).unwrap();
+
+    assert_eq!(machine.storage(claim(), U256::ZERO), Ok(U256::ZERO));
+    let sender = machine.account(sender()).unwrap();
+    assert_eq!((sender.nonce, sender.balance), (0, U256::from(FUNDS)));
+}
+
+#[test]
+fn test_invalid_transactions_are_reported_and_skipped() {
+    let mut machine = Machine::with_host(PrestateHost);
+    let outcome = machine.simulate_bundle(&[tx(1, 1), tx(0, 2)]).unwrap();
+
+    assert_eq!(outcome.results[0].as_ref().unwrap_err(), &TransactionError::NonceMismatch { expected: 0, got: 1 });
+    assert!(outcome.results[1].is_ok());
+    assert_eq!(outcome.state_diff[&claim()].storage[&U256::ZERO].after, U256::from(2));
+}
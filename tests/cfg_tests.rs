@@ -0,0 +1,43 @@
+use native_vs_evm::cfg::{Cfg, Edge, EdgeKind};
+
+mod common;
+use common::assemble;
+
+#[test]
+fn test_blocks_and_static_edges() {
+    // 0: PUSH1 0x03 | 2: JUMPDEST PUSH1 0x01 SUB DUP1 PUSH1 0x02 JUMPI | 10: STOP
+    let cfg = Cfg::build(&assemble("PUSH1 0x03 JUMPDEST PUSH1 0x01 SUB DUP1 PUSH1 0x02 JUMPI STOP"));
+
+    assert_eq!(cfg.blocks.keys().copied().collect::<Vec<_>>(), vec![0, 2, 10]);
+    assert_eq!(cfg.blocks[&2].end, 10);
+    assert_eq!(cfg.blocks[&2].instructions.len(), 6);
+    assert_eq!(cfg.edges, vec![
+        Edge { from: 0, to: 2, kind: EdgeKind::Fallthrough },
+        Edge { from: 2, to: 2, kind: EdgeKind::Taken },
+        Edge { from: 2, to: 10, kind: EdgeKind::Fallthrough },
+    ]);
+    assert!(cfg.dynamic_jumps.is_empty());
+}
+
+#[test]
+fn test_dynamic_and_invalid_jumps_are_flagged() {
+    // 0: CALLDATALOAD-computed jump | 4: static jump to a non-JUMPDEST | 7: unreachable JUMPDEST
+    let cfg = Cfg::build(&assemble("PUSH1 0x00 CALLDATALOAD JUMP PUSH1 0x00 JUMP JUMPDEST STOP"));
+
+    assert_eq!(cfg.dynamic_jumps, vec![3]);
+    assert_eq!(cfg.invalid_jumps, vec![6]);
+    assert_eq!(cfg.blocks.keys().copied().collect::<Vec<_>>(), vec![0, 4, 7]);
+    assert!(cfg.edges.is_empty());
+}
+
+#[test]
+fn test_dot_export() {
+    let cfg = Cfg::build(&assemble("PUSH1 0x04 JUMP STOP JUMPDEST PUSH1 0x00 CALLDATALOAD JUMP"));
+    let dot = cfg.to_dot();
+
+    assert!(dot.starts_with("digraph cfg {"));
+    assert!(dot.contains("b0 [label=\"0: PUSH1 0x04\\l2: JUMP\\l\"];"));
+    assert!(dot.contains("b0 -> b4 [label=\"jump\"];"));
+    assert!(dot.contains("b4 -> dynamic8 [style=dashed];"));
+    assert!(!dot.contains("b3 ->"));
+}
@@ -0,0 +1,91 @@
+use alloy::primitives::{Address, B256};
+use native_vs_evm::block::BlockError;
+use native_vs_evm::chain::SimpleChain;
+use native_vs_evm::evm::{Account, Machine, Transaction};
+use ruint::aliases::U256;
+
+mod common;
+use common::{assemble, contract};
+
+fn sender() -> Address {
+    "0x3000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn chain(code: &str) -> SimpleChain {
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract(), Account::with_code(assemble(code)));
+    SimpleChain::new(machine)
+}
+
+fn tx(nonce: u64) -> Transaction {
+    Transaction { caller: sender(), to: contract(), gas_limit: 100_000, nonce: Some(nonce), ..Default::default() }
+}
+
+#[test]
+fn test_blocks_link_parent_hashes_and_advance() {
+    let mut chain = chain("STOP");
+    let genesis_hash = chain.latest().hash;
+
+    chain.mine_empty(2);
+    let first = chain.block(1).unwrap();
+    let second = chain.block(2).unwrap();
+
+    assert_eq!(first.header.parent_hash, genesis_hash);
+    assert_eq!(second.header.parent_hash, first.hash);
+    assert_eq!(second.hash, second.header.hash());
+    assert_eq!((first.header.number, first.header.timestamp), (1, 12));
+    assert_eq!((second.header.number, second.header.timestamp), (2, 24));
+    assert_eq!(chain.machine.block, second.header);
+}
+
+#[test]
+fn test_time_locked_contract_unlocks_after_advance() {
+    // reverts while TIMESTAMP < 100, otherwise storage[0] = 1
+    let mut chain = chain("TIMESTAMP PUSH1 100 LT PUSH1 0x0d JUMPI PUSH1 1 PUSH1 0 SSTORE STOP JUMPDEST PUSH1 0 PUSH1 0 REVERT");
+
+    let locked = chain.mine(vec![tx(0)]).unwrap();
+    assert!(!locked.receipts[0].status);
+
+    chain.advance_time(100);
+    let unlocked = chain.mine(vec![tx(1)]).unwrap();
+    assert_eq!(unlocked.header.timestamp, 124);
+    assert!(unlocked.receipts[0].status);
+    assert_eq!(chain.machine.storage(contract(), U256::ZERO).unwrap(), U256::from(1));
+
+    // the advance only applies once
+    assert_eq!(chain.mine(Vec::new()).unwrap().header.timestamp, 136);
+}
+
+#[test]
+fn test_blockhash_returns_parent_hash() {
+    // storage[0] = BLOCKHASH(NUMBER - 1), storage[1] = BLOCKHASH(NUMBER)
+    let mut chain = chain("NUMBER PUSH1 1 SUB BLOCKHASH PUSH1 0 SSTORE NUMBER BLOCKHASH PUSH1 1 SSTORE STOP");
+    chain.mine_empty(3);
+    let parent_hash = chain.latest().hash;
+
+    chain.mine(vec![tx(0)]).unwrap();
+    assert_eq!(B256::from(chain.machine.storage(contract(), U256::ZERO).unwrap()), parent_hash);
+    assert_eq!(chain.machine.storage(contract(), U256::from(1)).unwrap(), U256::ZERO);
+}
+
+#[test]
+fn test_block_hashes_keep_last_256() {
+    let mut chain = chain("STOP");
+    chain.mine_empty(300);
+
+    assert_eq!(chain.machine.block_hashes.len(), 256);
+    assert!(!chain.machine.block_hashes.contains_key(&44));
+    assert_eq!(chain.machine.block_hashes.get(&45), Some(&chain.block(45).unwrap().hash));
+}
+
+#[test]
+fn test_rejected_block_leaves_chain_unchanged() {
+    let mut chain = chain("STOP");
+
+    let error = chain.mine(vec![tx(1)]).unwrap_err();
+    assert!(matches!(error, BlockError::InvalidTransaction { index: 0, .. }));
+    assert_eq!(chain.blocks.len(), 1);
+    assert_eq!(chain.machine.block.number, 0);
+
+    assert_eq!(chain.mine(vec![tx(0)]).unwrap().header.number, 1);
+}
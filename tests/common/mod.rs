@@ -0,0 +1,45 @@
+// Each test crate compiles this module on its own and uses only part of it
+#![allow(dead_code)]
+
+use alloy::primitives::Address;
+use native_vs_evm::opcode::Opcode;
+use ruint::aliases::U256;
+
+pub fn assemble(code: &str) -> Vec<u8> {
+    let mut bytecode = Vec::new();
+    let mut parts = code.split_whitespace();
+    while let Some(part) = parts.next() {
+        let opcode = Opcode::from_mnemonic(part).unwrap_or_else(|| panic!("Unknown assembly instruction: {}", part));
+        bytecode.push(opcode as u8);
+
+        if opcode.is_push() {
+            let num_bytes = opcode.immediate_size();
+            if let Some(data_part) = parts.next() {
+                let bytes = if let Some(hex_val) = data_part.strip_prefix("0x") {
+                    let padded_hex = format!("{:0>width$}", hex_val, width = num_bytes * 2);
+                    hex::decode(padded_hex).unwrap()
+                } else {
+                    let num = U256::from_str_radix(data_part, 10).expect("Invalid decimal number");
+                    let arr = num.to_be_bytes::<32>();
+                    arr[32 - num_bytes..].to_vec()
+                };
+                bytecode.extend(bytes);
+            } else {
+                panic!("PUSH instruction is missing data");
+            }
+        }
+    }
+    bytecode
+}
+
+pub fn contract() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+pub fn sub() -> Address {
+    "0x2100000000000000000000000000000000000000".parse().unwrap()
+}
+
+pub fn alice() -> Address {
+    Address::with_last_byte(0xa1)
+}
@@ -0,0 +1,68 @@
+use native_vs_evm::compiler::{BinOp, Expr, Program, Stmt};
+use native_vs_evm::evm::{ExecutionResult, Machine};
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+fn run_evm(program: &Program, inputs: &[U256]) -> U256 {
+    let mut machine = Machine::new(program.to_bytecode(), Program::calldata(inputs), HashMap::new(), 10_000_000);
+    match machine.run() {
+        ExecutionResult::Success(output) => U256::from_be_slice(&output),
+        other => panic!("{:?} for {:?}", other, program),
+    }
+}
+
+// x ** n by repeated multiplication
+fn power() -> Program {
+    let (x, n, result) = (0, 1, 2);
+    Program {
+        inputs: 2,
+        body: vec![
+            Stmt::Assign(result, Expr::constant(1)),
+            Stmt::While(Expr::binary(BinOp::Gt, Expr::Var(n), Expr::constant(0)), vec![
+                Stmt::Assign(result, Expr::binary(BinOp::Mul, Expr::Var(result), Expr::Var(x))),
+                Stmt::Assign(n, Expr::binary(BinOp::Sub, Expr::Var(n), Expr::constant(1))),
+            ]),
+        ],
+        result: Expr::Var(result),
+    }
+}
+
+#[test]
+fn test_loop_compiles_to_both_targets() {
+    let program = power();
+    let native = program.to_native();
+    let inputs = [U256::from(3), U256::from(5)];
+
+    assert_eq!(program.variables(), 3);
+    assert_eq!(native(&inputs), U256::from(243));
+    assert_eq!(run_evm(&program, &inputs), U256::from(243));
+}
+
+#[test]
+fn test_evm_semantics_on_the_native_side() {
+    let program = Program {
+        inputs: 1,
+        body: vec![Stmt::If(Expr::Not(Box::new(Expr::Var(0))), vec![Stmt::Assign(1, Expr::binary(BinOp::Sub, Expr::constant(0), Expr::constant(1)))])],
+        // (0 - 1) / x, with x = 0 dividing by zero
+        result: Expr::binary(BinOp::Add, Expr::binary(BinOp::Div, Expr::Var(1), Expr::Var(0)), Expr::binary(BinOp::Eq, Expr::Var(1), Expr::Const(U256::MAX))),
+    };
+    let native = program.to_native();
+
+    assert_eq!(native(&[U256::ZERO]), U256::from(1));
+    assert_eq!(run_evm(&program, &[U256::ZERO]), U256::from(1));
+    assert_eq!(native(&[U256::from(7)]), U256::ZERO);
+    assert_eq!(run_evm(&program, &[U256::from(7)]), U256::ZERO);
+}
+
+#[test]
+fn test_random_programs_agree() {
+    for seed in 0..64 {
+        let program = Program::random(seed);
+        assert_eq!(program, Program::random(seed));
+        let native = program.to_native();
+        for inputs in [[0u64, 0, 0], [1, 2, 3], [999, 17, 4], [u64::MAX, 5, 0]] {
+            let inputs: Vec<U256> = inputs[..program.inputs].iter().map(|&value| U256::from(value)).collect();
+            assert_eq!(native(&inputs), run_evm(&program, &inputs), "seed {} inputs {:?}", seed, inputs);
+        }
+    }
+}
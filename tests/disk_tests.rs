@@ -0,0 +1,68 @@
+#![cfg(feature = "disk")]
+
+use alloy::primitives::Address;
+use native_vs_evm::disk::DiskHost;
+use native_vs_evm::evm::{Account, ExecutionResult, Host, Machine};
+use ruint::aliases::U256;
+
+mod common;
+use common::assemble;
+
+fn contract_address() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn contract() -> Account {
+    // storage[0] = storage[0] + storage[1]
+    let mut account = Account::with_code(assemble("PUSH1 0x00 SLOAD PUSH1 0x01 SLOAD ADD PUSH1 0x00 SSTORE STOP"));
+    account.balance = U256::from(5);
+    account.nonce = 3;
+    account.storage.insert(U256::from(0), U256::from(40));
+    account.storage.insert(U256::from(1), U256::from(2));
+    account
+}
+
+#[test]
+fn test_disk_host_round_trips_accounts() {
+    let mut host = DiskHost::temporary().unwrap();
+    host.commit([(&contract_address(), &contract())]).unwrap();
+
+    let account = host.basic(contract_address()).unwrap();
+    assert_eq!(account.balance, U256::from(5));
+    assert_eq!(account.nonce, 3);
+    assert!(account.lazy_code);
+    assert_eq!(host.code(contract_address()).unwrap(), *contract().code);
+    assert_eq!(host.storage(contract_address(), U256::from(1)).unwrap(), U256::from(2));
+
+    assert_eq!(host.basic(Address::ZERO).unwrap().balance, U256::ZERO);
+    assert_eq!(host.storage(contract_address(), U256::from(9)).unwrap(), U256::ZERO);
+}
+
+#[test]
+fn test_machine_executes_against_disk_and_commits_back() {
+    let host = DiskHost::temporary().unwrap();
+    host.commit([(&contract_address(), &contract())]).unwrap();
+
+    let mut machine = Machine::with_host(host.clone());
+    let result = machine.call(Address::ZERO, contract_address(), vec![], 100_000);
+    assert_eq!(result, ExecutionResult::Success(vec![]));
+    host.commit(&machine.accounts).unwrap();
+
+    let mut fresh = Machine::with_host(host.clone());
+    assert_eq!(fresh.storage(contract_address(), U256::from(0)).unwrap(), U256::from(42));
+    assert_eq!(fresh.account(contract_address()).unwrap().nonce, 3);
+}
+
+#[test]
+fn test_commit_deletes_zeroed_slots() {
+    let mut host = DiskHost::temporary().unwrap();
+    let mut account = contract();
+    host.commit([(&contract_address(), &account)]).unwrap();
+
+    account.storage.insert(U256::from(1), U256::ZERO);
+    account.storage.remove(&U256::from(0));
+    host.commit([(&contract_address(), &account)]).unwrap();
+
+    assert_eq!(host.storage(contract_address(), U256::from(1)).unwrap(), U256::ZERO);
+    assert_eq!(host.storage(contract_address(), U256::from(0)).unwrap(), U256::from(40));
+}
@@ -0,0 +1,97 @@
+use alloy::primitives::Address;
+use native_vs_evm::events::{EventStream, ExecutionEvent};
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+use ruint::aliases::U256;
+use std::sync::mpsc;
+use std::thread;
+
+mod common;
+use common::assemble;
+
+fn outer() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn inner() -> Address {
+    "0x2100000000000000000000000000000000000000".parse().unwrap()
+}
+
+// outer calls inner, which sets storage[1] = 7 and logs, then outer stops
+fn machine() -> Machine {
+    let mut machine = Machine::default();
+    let call = format!(
+        "PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 0x{} PUSH2 0xffff CALL STOP",
+        inner().to_string().strip_prefix("0x").unwrap()
+    );
+    machine.accounts.insert(outer(), Account::with_code(assemble(&call)));
+    machine.accounts.insert(inner(), Account::with_code(assemble("PUSH1 0x07 PUSH1 0x01 SSTORE PUSH1 0x00 PUSH1 0x00 LOG0 STOP")));
+    machine
+}
+
+fn collect(machine: &mut Machine) -> (ExecutionResult, Vec<ExecutionEvent>) {
+    let mut events = Vec::new();
+    let result = machine.call_with_events(Address::ZERO, outer(), vec![], 1_000_000, |event| events.push(event));
+    (result, events)
+}
+
+#[test]
+fn test_events_follow_frames_storage_and_logs() {
+    let (result, events) = collect(&mut machine());
+    assert_eq!(result, ExecutionResult::Success(vec![]));
+
+    let log = match &events[3] {
+        ExecutionEvent::LogEmitted(log) => log.clone(),
+        other => panic!("expected a log, got {:?}", other),
+    };
+    assert_eq!(log.address, inner());
+    assert_eq!(events, vec![
+        ExecutionEvent::FrameEntered { depth: 1, caller: Address::ZERO, address: outer() },
+        ExecutionEvent::FrameEntered { depth: 2, caller: outer(), address: inner() },
+        ExecutionEvent::StorageWritten { address: inner(), key: U256::from(1), value: U256::from(7) },
+        ExecutionEvent::LogEmitted(log),
+        ExecutionEvent::FrameExited { depth: 2, address: inner(), success: true },
+        ExecutionEvent::FrameExited { depth: 1, address: outer(), success: true },
+        ExecutionEvent::Finished(ExecutionResult::Success(vec![])),
+    ]);
+}
+
+#[test]
+fn test_exceptional_halt_closes_open_frames() {
+    let mut machine = Machine::default();
+    machine.accounts.insert(outer(), Account::with_code(assemble("PUSH1 0x00 JUMP")));
+    let mut events = Vec::new();
+    machine.call_with_events(Address::ZERO, outer(), vec![], 1_000_000, |event| events.push(event));
+
+    assert_eq!(events[1..], [
+        ExecutionEvent::FrameExited { depth: 1, address: outer(), success: false },
+        ExecutionEvent::Finished(ExecutionResult::InvalidJump),
+    ]);
+}
+
+#[test]
+fn test_step_counts_every_interval() {
+    let mut machine = machine();
+    let mut events = Vec::new();
+    let mut stream = EventStream::new(|event| events.push(event)).with_step_interval(5);
+    machine.call_with_inspector(Address::ZERO, outer(), vec![], 1_000_000, &mut stream);
+    drop(stream);
+
+    // 9 instructions in outer and 7 in inner
+    let counts: Vec<_> = events.iter().filter_map(|event| match event {
+        ExecutionEvent::StepsExecuted(steps) => Some(*steps),
+        _ => None,
+    }).collect();
+    assert_eq!(counts, [5, 10, 15]);
+}
+
+#[test]
+fn test_events_reach_another_thread() {
+    let (sender, receiver) = mpsc::sync_channel(1);
+    // the machine lives on the worker; only events cross over
+    let worker = thread::spawn(move || machine().call_with_events(Address::ZERO, outer(), vec![], 1_000_000, sender));
+
+    let events: Vec<ExecutionEvent> = receiver.iter().collect();
+    assert_eq!(worker.join().unwrap(), ExecutionResult::Success(vec![]));
+    assert_eq!(events.len(), 7);
+    assert_eq!(events.last(), Some(&ExecutionEvent::Finished(ExecutionResult::Success(vec![]))));
+}
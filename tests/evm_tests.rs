@@ -3,75 +3,10 @@ use ruint::aliases::U256;
 use std::collections::HashMap;
 use ruint::uint;
 use std::rc::Rc;
-use alloy::primitives::{Address};
-
-fn assemble(code: &str) -> Vec<u8> {
-    let mut bytecode = Vec::new();
-    let mut parts = code.split_whitespace().peekable();
-    while let Some(part) = parts.next() {
-        let uppercase_part = part.to_uppercase();
-        match uppercase_part.as_str() {
-            "STOP" => bytecode.push(0x00),
-            "ADD" => bytecode.push(0x01),
-            "MUL" => bytecode.push(0x02),
-            "SUB" => bytecode.push(0x03),
-            "DIV" => bytecode.push(0x04),
-            "LT" => bytecode.push(0x10),
-            "GT" => bytecode.push(0x11),
-            "EQ" => bytecode.push(0x14),
-            "ISZERO" => bytecode.push(0x15),
-            "SHA3" => bytecode.push(0x20),
-            "CALLDATALOAD" => bytecode.push(0x35),
-            "RETURNDATASIZE" => bytecode.push(0x3d),
-            "RETURNDATACOPY" => bytecode.push(0x3e),
-            "POP" => bytecode.push(0x50),
-            "MLOAD" => bytecode.push(0x51),
-            "MSTORE" => bytecode.push(0x52),
-            "SLOAD" => bytecode.push(0x54),
-            "SSTORE" => bytecode.push(0x55),
-            "JUMP" => bytecode.push(0x56),
-            "JUMPI" => bytecode.push(0x57),
-            "JUMPDEST" => bytecode.push(0x5b),
-            "CALL" => bytecode.push(0xf1),
-            "RETURN" => bytecode.push(0xf3),
-            "REVERT" => bytecode.push(0xfd),
-            _ if uppercase_part.starts_with("DUP") => {
-                let num_str = &uppercase_part[3..];
-                let num = num_str.parse::<u8>().unwrap();
-                bytecode.push(0x80 + num - 1);
-            }
-            _ if uppercase_part.starts_with("SWAP") => {
-                let num_str = &uppercase_part[4..];
-                let num = num_str.parse::<u8>().unwrap();
-                bytecode.push(0x90 + num - 1);
-            }
-            _ if uppercase_part.starts_with("PUSH") => {
-                let num_bytes_str = &uppercase_part[4..];
-                let num_bytes = num_bytes_str.parse::<u8>().unwrap();
-                bytecode.push(0x60 + num_bytes - 1);
-
-                if let Some(data_part) = parts.next() {
-                    let bytes = if data_part.starts_with("0x") {
-                        let hex_val = &data_part[2..];
-                        let padded_hex = format!("{:0>width$}", hex_val, width = (num_bytes as usize) * 2);
-                        hex::decode(padded_hex).unwrap()
-                    } else {
-                        let num = U256::from_str_radix(data_part, 10).expect("Invalid decimal number");
-                        let arr = num.to_be_bytes::<32>();
-                        arr[32 - num_bytes as usize..].to_vec()
-                    };
-                    bytecode.extend(bytes);
-                } else {
-                    panic!("PUSH instruction is missing data");
-                }
-            }
-            _ => {
-                panic!("Unknown assembly instruction: {}", part);
-            }
-        }
-    }
-    bytecode
-}
+use alloy::primitives::{Address, B256};
+
+mod common;
+use common::assemble;
 
 #[test]
 fn test_add_and_stop() {
@@ -199,4 +134,171 @@ fn test_simple_call_and_return_data() {
     let result = machine.run();
     let expected_return = U256::from(1).to_be_bytes::<32>().to_vec();
     assert_eq!(result, ExecutionResult::Success(expected_return));
-}
\ No newline at end of file
+}
+#[test]
+fn test_transact_transfers_value_and_charges_gas() {
+    let caller: Address = "0x3000000000000000000000000000000000000000".parse().unwrap();
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+
+    let mut machine = Machine::default();
+    machine.accounts.insert(caller, Account { balance: U256::from(1_000_000), ..Default::default() });
+    machine.accounts.insert(contract, Account {
+        code: Rc::new(assemble("PUSH1 0x42 PUSH1 0x01 SSTORE STOP")),
+        ..Default::default()
+    });
+
+    let tx = Transaction {
+        caller,
+        to: contract,
+        value: U256::from(100),
+        gas_limit: 50_000,
+        gas_price: U256::from(2),
+        nonce: Some(0),
+        ..Default::default()
+    };
+    let outcome = machine.transact(&tx).unwrap();
+
+    assert_eq!(outcome.result, ExecutionResult::Success(vec![]));
+    assert_eq!(outcome.gas_used, 21000 + 3 + 3 + 20000);
+    assert_eq!(machine.accounts[&caller].balance, U256::from(1_000_000 - 100 - 2 * outcome.gas_used));
+    assert_eq!(machine.accounts[&caller].nonce, 1);
+    assert_eq!(machine.accounts[&contract].balance, U256::from(100));
+    assert_eq!(machine.accounts[&contract].storage[&U256::from(1)], U256::from(0x42));
+
+    assert_eq!(machine.transact(&tx), Err(TransactionError::NonceMismatch { expected: 1, got: 0 }));
+}
+
+#[test]
+fn test_transact_revert_rolls_back_state() {
+    let caller: Address = "0x3000000000000000000000000000000000000000".parse().unwrap();
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+
+    let mut machine = Machine::default();
+    machine.accounts.insert(caller, Account { balance: U256::from(1_000), ..Default::default() });
+    machine.accounts.insert(contract, Account {
+        code: Rc::new(assemble("PUSH1 0x42 PUSH1 0x01 SSTORE PUSH1 0x00 PUSH1 0x00 REVERT")),
+        ..Default::default()
+    });
+
+    let tx = Transaction { caller, to: contract, value: U256::from(10), gas_limit: 100_000, ..Default::default() };
+    let outcome = machine.transact(&tx).unwrap();
+
+    assert_eq!(outcome.result, ExecutionResult::Revert(vec![]));
+    assert!(machine.accounts[&contract].storage.is_empty());
+    assert_eq!(machine.accounts[&contract].balance, U256::ZERO);
+    assert_eq!(machine.accounts[&caller].balance, U256::from(1_000));
+    assert_eq!(machine.accounts[&caller].nonce, 1);
+}
+
+#[test]
+fn test_simulate_discards_state() {
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract, Account {
+        code: Rc::new(assemble("PUSH1 0x42 PUSH1 0x01 SSTORE PUSH1 0x01 SLOAD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")),
+        ..Default::default()
+    });
+
+    let tx = Transaction { to: contract, gas_limit: 100_000, ..Default::default() };
+    let outcome = machine.simulate(&tx).unwrap();
+
+    assert_eq!(outcome.result, ExecutionResult::Success(U256::from(0x42).to_be_bytes::<32>().to_vec()));
+    assert!(machine.accounts[&contract].storage.is_empty());
+    assert!(!machine.accounts.contains_key(&Address::ZERO));
+}
+
+#[test]
+fn test_log() {
+    let bytecode = assemble("PUSH1 0xaa PUSH1 0x00 MSTORE PUSH1 0x07 PUSH1 0x01 PUSH1 0x1f LOG1 STOP");
+    let mut machine = Machine::new(bytecode, vec![], HashMap::new(), 1_000_000);
+    let result = machine.run();
+    assert_eq!(result, ExecutionResult::Success(vec![]));
+
+    let callee: Address = "0x1000000000000000000000000000000000000000".parse().unwrap();
+    assert_eq!(machine.logs.len(), 1);
+    assert_eq!(machine.logs[0].address, callee);
+    assert_eq!(machine.logs[0].topics(), &[B256::from(U256::from(7))]);
+    assert_eq!(machine.logs[0].data.data.to_vec(), vec![0xaa]);
+}
+
+#[test]
+fn test_capture_final_frame_on_stop() {
+    let bytecode = assemble("PUSH1 0x05 PUSH1 0x0a ADD PUSH1 0xff PUSH1 0x00 MSTORE PUSH1 0x07");
+    let mut machine = Machine::new(bytecode, vec![], HashMap::new(), 1_000_000);
+    machine.capture_final_frame = true;
+    assert_eq!(machine.run(), ExecutionResult::Success(vec![]));
+
+    let frame = machine.final_frame.unwrap();
+    assert_eq!(frame.stack, vec![U256::from(15), U256::from(7)]);
+    assert_eq!(frame.memory, U256::from(0xff).to_be_bytes::<32>().to_vec());
+}
+
+#[test]
+fn test_capture_final_frame_on_halt_and_in_outcome() {
+    let caller: Address = "0x3000000000000000000000000000000000000000".parse().unwrap();
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+
+    let mut machine = Machine::default();
+    machine.accounts.insert(caller, Account { balance: U256::from(1_000_000), ..Default::default() });
+    machine.accounts.insert(contract, Account::with_code(assemble("PUSH1 0x2a PUSH1 0x01 JUMP")));
+    let tx = Transaction { caller, to: contract, gas_limit: 50_000, ..Default::default() };
+
+    let outcome = machine.transact(&tx).unwrap();
+    assert_eq!(outcome.result, ExecutionResult::InvalidJump);
+    assert_eq!(outcome.final_frame, None);
+
+    machine.capture_final_frame = true;
+    let outcome = machine.transact(&tx).unwrap();
+    assert_eq!(outcome.final_frame.unwrap().stack, vec![U256::from(0x2a)]);
+}
+
+// 2^64 + 32, which used to be read as 32
+const PAST_U64: &str = "PUSH9 0x010000000000000020";
+
+#[test]
+fn test_mload_past_u64_runs_out_of_gas() {
+    let bytecode = assemble(&format!("{} MLOAD STOP", PAST_U64));
+    let mut machine = Machine::new(bytecode, vec![], HashMap::new(), 1_000_000);
+    assert_eq!(machine.run(), ExecutionResult::OutOfGas);
+}
+
+#[test]
+fn test_sha3_of_huge_size_runs_out_of_gas() {
+    let bytecode = assemble(&format!("{} PUSH1 0x00 SHA3 STOP", PAST_U64));
+    let mut machine = Machine::new(bytecode, vec![], HashMap::new(), 1_000_000);
+    assert_eq!(machine.run(), ExecutionResult::OutOfGas);
+}
+
+#[test]
+fn test_empty_return_ignores_its_offset() {
+    let bytecode = assemble(&format!("PUSH1 0x00 {} RETURN", PAST_U64));
+    let mut machine = Machine::new(bytecode, vec![], HashMap::new(), 1_000_000);
+    assert_eq!(machine.run(), ExecutionResult::Success(vec![]));
+}
+
+#[test]
+fn test_return_of_untouched_memory_is_zeros() {
+    let bytecode = assemble("PUSH1 0x20 PUSH1 0x00 RETURN");
+    let mut machine = Machine::new(bytecode, vec![], HashMap::new(), 1_000_000);
+    assert_eq!(machine.run(), ExecutionResult::Success(vec![0; 32]));
+}
+
+#[test]
+fn test_jump_past_u64_is_invalid() {
+    // pc 32 would be a JUMPDEST if the target were truncated
+    let mut code = format!("{} JUMP", PAST_U64);
+    code.push_str(&" STOP".repeat(21));
+    code.push_str(" JUMPDEST STOP");
+    let bytecode = assemble(&code);
+    assert_eq!(bytecode[32], 0x5b);
+    let mut machine = Machine::new(bytecode, vec![], HashMap::new(), 1_000_000);
+    assert_eq!(machine.run(), ExecutionResult::InvalidJump);
+}
+
+#[test]
+fn test_calldataload_past_u64_reads_zero() {
+    let bytecode = assemble(&format!("{} CALLDATALOAD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN", PAST_U64));
+    let mut machine = Machine::new(bytecode, vec![0xff; 64], HashMap::new(), 1_000_000);
+    assert_eq!(machine.run(), ExecutionResult::Success(vec![0; 32]));
+}
@@ -0,0 +1,268 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, AsyncHost, ExecutionResult, Hardfork, Host, Machine};
+use ruint::aliases::U256;
+use std::cell::Cell;
+use std::rc::Rc;
+
+mod common;
+use common::assemble;
+
+fn contract_address() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+// returns storage[1] + storage[1]
+fn contract_code() -> Vec<u8> {
+    assemble("PUSH1 0x01 SLOAD PUSH1 0x01 SLOAD ADD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")
+}
+
+#[derive(Debug, Default)]
+struct CountingHost {
+    basic_calls: Rc<Cell<usize>>,
+    storage_calls: Rc<Cell<usize>>,
+}
+
+impl Host for CountingHost {
+    fn basic(&mut self, address: Address) -> Result<Account, String> {
+        self.basic_calls.set(self.basic_calls.get() + 1);
+        if address == contract_address() {
+            Ok(Account { code: Rc::new(contract_code()), ..Default::default() })
+        } else {
+            Ok(Account::default())
+        }
+    }
+
+    fn storage(&mut self, _address: Address, key: U256) -> Result<U256, String> {
+        self.storage_calls.set(self.storage_calls.get() + 1);
+        Ok(key + U256::from(20))
+    }
+}
+
+#[derive(Debug)]
+struct FailingHost;
+
+impl Host for FailingHost {
+    fn basic(&mut self, _address: Address) -> Result<Account, String> {
+        Ok(Account { code: Rc::new(contract_code()), ..Default::default() })
+    }
+
+    fn storage(&mut self, _address: Address, _key: U256) -> Result<U256, String> {
+        Err("connection refused".to_string())
+    }
+}
+
+#[test]
+fn test_host_state_is_loaded_lazily_and_cached() {
+    let host = CountingHost::default();
+    let (basic_calls, storage_calls) = (host.basic_calls.clone(), host.storage_calls.clone());
+    let mut machine = Machine::with_host(host);
+
+    let result = machine.call(Address::ZERO, contract_address(), vec![], 100_000);
+    assert_eq!(result, ExecutionResult::Success(U256::from(42).to_be_bytes::<32>().to_vec()));
+    assert_eq!(basic_calls.get(), 1);
+    assert_eq!(storage_calls.get(), 1);
+
+    machine.call(Address::ZERO, contract_address(), vec![], 100_000);
+    assert_eq!(basic_calls.get(), 1);
+    assert_eq!(storage_calls.get(), 1);
+}
+
+#[test]
+fn test_local_writes_shadow_host_state() {
+    let mut machine = Machine::with_host(CountingHost::default());
+    machine.account(contract_address()).unwrap().storage.insert(U256::from(1), U256::from(5));
+
+    let result = machine.call(Address::ZERO, contract_address(), vec![], 100_000);
+    assert_eq!(result, ExecutionResult::Success(U256::from(10).to_be_bytes::<32>().to_vec()));
+    assert_eq!(machine.storage(contract_address(), U256::from(2)), Ok(U256::from(22)));
+}
+
+#[test]
+fn test_host_errors_abort_execution() {
+    let mut machine = Machine::with_host(FailingHost);
+    let result = machine.call(Address::ZERO, contract_address(), vec![], 100_000);
+    assert_eq!(result, ExecutionResult::HostError("connection refused".to_string()));
+}
+
+// Leaves code out of `basic` and counts how often it is asked for it
+#[derive(Debug, Default)]
+struct LazyCodeHost {
+    code_calls: Rc<Cell<usize>>,
+}
+
+impl Host for LazyCodeHost {
+    fn basic(&mut self, _address: Address) -> Result<Account, String> {
+        Ok(Account { balance: U256::from(9), lazy_code: true, ..Default::default() })
+    }
+
+    fn storage(&mut self, _address: Address, key: U256) -> Result<U256, String> {
+        Ok(key + U256::from(20))
+    }
+
+    fn code(&mut self, _address: Address) -> Result<Vec<u8>, String> {
+        self.code_calls.set(self.code_calls.get() + 1);
+        Ok(contract_code())
+    }
+}
+
+#[test]
+fn test_code_is_loaded_on_first_call() {
+    let host = LazyCodeHost::default();
+    let code_calls = host.code_calls.clone();
+    let mut machine = Machine::with_host(host);
+
+    assert_eq!(machine.account(contract_address()).unwrap().balance, U256::from(9));
+    assert_eq!(machine.storage(contract_address(), U256::from(1)), Ok(U256::from(21)));
+    assert_eq!(code_calls.get(), 0);
+
+    let result = machine.call(Address::ZERO, contract_address(), vec![], 100_000);
+    assert_eq!(result, ExecutionResult::Success(U256::from(42).to_be_bytes::<32>().to_vec()));
+    machine.call(Address::ZERO, contract_address(), vec![], 100_000);
+    assert_eq!(machine.code(contract_address()).unwrap(), Rc::new(contract_code()));
+    assert_eq!(code_calls.get(), 1);
+}
+
+#[test]
+fn test_deploy_replaces_lazy_code() {
+    let host = LazyCodeHost::default();
+    let code_calls = host.code_calls.clone();
+    let mut machine = Machine::with_host(host);
+
+    machine.account(contract_address()).unwrap();
+    machine.deploy(contract_address(), assemble("PUSH1 0x07 PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN"));
+    let result = machine.call(Address::ZERO, contract_address(), vec![], 100_000);
+    assert_eq!(result, ExecutionResult::Success(U256::from(7).to_be_bytes::<32>().to_vec()));
+    assert_eq!(code_calls.get(), 0);
+}
+
+impl AsyncHost for CountingHost {
+    async fn basic(&mut self, address: Address) -> Result<Account, String> {
+        tokio::task::yield_now().await;
+        Host::basic(self, address)
+    }
+
+    async fn storage(&mut self, address: Address, key: U256) -> Result<U256, String> {
+        tokio::task::yield_now().await;
+        Host::storage(self, address, key)
+    }
+}
+
+#[tokio::test]
+async fn test_call_async_awaits_state_and_caches_it() {
+    let mut host = CountingHost::default();
+    let mut machine = Machine::default();
+
+    let result = machine.call_async(&mut host, Address::ZERO, contract_address(), vec![], 100_000).await;
+    assert_eq!(result, ExecutionResult::Success(U256::from(42).to_be_bytes::<32>().to_vec()));
+    assert_eq!(host.basic_calls.get(), 1);
+    assert_eq!(host.storage_calls.get(), 1);
+
+    machine.call_async(&mut host, Address::ZERO, contract_address(), vec![], 100_000).await;
+    assert_eq!(host.storage_calls.get(), 1);
+}
+
+#[tokio::test]
+async fn test_call_async_fetches_call_targets() {
+    let caller_address: Address = "0x1000000000000000000000000000000000000000".parse().unwrap();
+    let mut machine = Machine::default();
+    // CALL(gas, contract, 0, 0, 0, 0, 0x20), then return the callee's word
+    machine.accounts.insert(caller_address, Account::with_code(assemble(
+        "PUSH1 0x20 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 0x2000000000000000000000000000000000000000 PUSH2 0xffff CALL \
+         PUSH1 0x20 PUSH1 0x00 RETURN",
+    )));
+
+    let mut host = CountingHost::default();
+    let result = machine.call_async(&mut host, Address::ZERO, caller_address, vec![], 100_000).await;
+    assert_eq!(result, ExecutionResult::Success(U256::from(42).to_be_bytes::<32>().to_vec()));
+    assert_eq!(host.basic_calls.get(), 1);
+}
+
+// rewrites storage[1] with the 21 the host already holds there
+#[derive(Debug)]
+struct RewritingHost;
+
+impl AsyncHost for RewritingHost {
+    async fn basic(&mut self, _address: Address) -> Result<Account, String> {
+        tokio::task::yield_now().await;
+        Ok(Account::with_code(assemble("PUSH1 0x15 PUSH1 0x01 SSTORE STOP")))
+    }
+
+    async fn storage(&mut self, _address: Address, key: U256) -> Result<U256, String> {
+        tokio::task::yield_now().await;
+        Ok(key + U256::from(20))
+    }
+}
+
+#[tokio::test]
+async fn test_call_async_meters_sstore_against_host_storage() {
+    let mut machine = Machine::default();
+    machine.hardfork = Hardfork::Berlin;
+
+    let result = machine.call_async(&mut RewritingHost, Address::ZERO, contract_address(), vec![], 100_000).await;
+    assert_eq!(result, ExecutionResult::Success(vec![]));
+    // two pushes, a cold slot and an unchanged write
+    assert_eq!(100_000 - machine.gas_left(), 3 + 3 + 2100 + 100);
+}
+
+#[cfg(feature = "rpc")]
+#[test]
+fn test_fork_host_fetches_remote_state() {
+    use native_vs_evm::fork::ForkHost;
+    use native_vs_evm::rpc::RpcServer;
+    use std::time::Duration;
+
+    let addr = "127.0.0.1:18546".parse().unwrap();
+    std::thread::spawn(move || {
+        let mut remote = Machine::default();
+        remote.accounts.insert(contract_address(), Account { code: Rc::new(contract_code()), ..Default::default() });
+        remote.accounts.get_mut(&contract_address()).unwrap().storage.insert(U256::from(1), U256::from(50));
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(RpcServer::new(remote, 1).serve(addr)).unwrap();
+    });
+
+    let url = url::Url::parse("http://127.0.0.1:18546").unwrap();
+    let host = (0..50)
+        .find_map(|_| ForkHost::new(url.clone(), None).ok().or_else(|| { std::thread::sleep(Duration::from_millis(20)); None }))
+        .expect("fork node did not start");
+    assert_eq!(host.block_number(), 0);
+
+    let mut machine = Machine::with_host(host);
+    let result = machine.call(Address::ZERO, contract_address(), vec![], 100_000);
+    assert_eq!(result, ExecutionResult::Success(U256::from(100).to_be_bytes::<32>().to_vec()));
+}
+
+#[cfg(feature = "rpc")]
+#[tokio::test]
+async fn test_fork_host_runs_inside_tokio() {
+    use native_vs_evm::fork::ForkHost;
+    use native_vs_evm::rpc::RpcServer;
+    use std::time::Duration;
+
+    let addr = "127.0.0.1:18547".parse().unwrap();
+    std::thread::spawn(move || {
+        let mut remote = Machine::default();
+        remote.accounts.insert(contract_address(), Account { code: Rc::new(contract_code()), ..Default::default() });
+        remote.accounts.get_mut(&contract_address()).unwrap().storage.insert(U256::from(1), U256::from(7));
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(RpcServer::new(remote, 1).serve(addr)).unwrap();
+    });
+
+    let url = url::Url::parse("http://127.0.0.1:18547").unwrap();
+    let mut host = None;
+    for _ in 0..50 {
+        match ForkHost::connect(url.clone(), None).await {
+            Ok(connected) => {
+                host = Some(connected);
+                break;
+            }
+            Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+        }
+    }
+    let mut host = host.expect("fork node did not start");
+
+    let mut machine = Machine::default();
+    let result = machine.call_async(&mut host, Address::ZERO, contract_address(), vec![], 100_000).await;
+    assert_eq!(result, ExecutionResult::Success(U256::from(14).to_be_bytes::<32>().to_vec()));
+}
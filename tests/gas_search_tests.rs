@@ -0,0 +1,32 @@
+use native_vs_evm::evm::{ExecutionResult, Machine};
+use native_vs_evm::gas_search::{search_worst_case, SearchConfig};
+use native_vs_evm::opcode::{decode, Opcode};
+use std::collections::HashMap;
+
+fn small() -> SearchConfig {
+    SearchConfig { iterations: 30, population: 6, gadgets: 4, gas_limit: 20_000, repeats: 1, keep: 3, ..Default::default() }
+}
+
+#[test]
+fn test_findings_are_ranked_loops_that_burn_all_gas() {
+    let report = search_worst_case(small());
+
+    assert_eq!(report.findings.len(), 3);
+    assert!(report.evaluated >= 36);
+    assert!(report.findings.windows(2).all(|pair| pair[0].ns_per_gas >= pair[1].ns_per_gas));
+    for finding in &report.findings {
+        assert_eq!(finding.gas, 20_000);
+        assert!(finding.ns_per_gas > 0.0);
+        let last = decode(&finding.code).last().unwrap();
+        assert_eq!(last.opcode, Some(Opcode::Jump));
+        let mut machine = Machine::new(finding.code.clone(), vec![], HashMap::new(), 20_000);
+        assert_eq!(machine.run(), ExecutionResult::OutOfGas);
+    }
+}
+
+#[test]
+fn test_keep_caps_the_report() {
+    let report = search_worst_case(SearchConfig { keep: 10, ..small() });
+
+    assert_eq!(report.findings.len(), 6);
+}
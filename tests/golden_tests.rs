@@ -0,0 +1,108 @@
+use alloy::primitives::{Address, U256};
+use alloy::sol_types::SolCall;
+use native_vs_evm::evm::{Account, ExecutionResult, Hardfork, Machine};
+use native_vs_evm::opcode::Opcode;
+use native_vs_evm::tokens::{Erc20, IERC20};
+use native_vs_evm::tracer::{opcode_name, StructLog, StructLogger};
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+mod common;
+use common::{assemble, contract, sub};
+
+// Each fixture's trace is compared line by line against tests/golden/<name>.trace. After an
+// intended change in semantics or gas, regenerate them with
+// `UPDATE_GOLDEN=1 cargo test --test golden_tests` and review the diff
+
+// One line per step with what decides semantics and gas, storage writes spelled out, and the
+// result at the end. Stacks and memory are left out so unrelated changes stay quiet
+fn normalize(logs: &[StructLog], result: &ExecutionResult) -> String {
+    let mut trace = String::new();
+    for log in logs {
+        write!(trace, "{} {:>4} {:<14} cost={} gas={}", log.depth, log.pc, opcode_name(log.op), log.gas_cost, log.gas).unwrap();
+        if matches!(Opcode::try_from(log.op), Ok(Opcode::SStore | Opcode::TStore)) && log.stack.len() >= 2 {
+            let key = log.stack[log.stack.len() - 1];
+            let value = log.stack[log.stack.len() - 2];
+            write!(trace, " [{:#x}] = {:#x}", key, value).unwrap();
+        }
+        trace.push('\n');
+    }
+    match result {
+        ExecutionResult::Success(data) => writeln!(trace, "=> Success 0x{}", hex::encode(data)).unwrap(),
+        ExecutionResult::Revert(data) => writeln!(trace, "=> Revert 0x{}", hex::encode(data)).unwrap(),
+        halt => writeln!(trace, "=> {:?}", halt).unwrap(),
+    }
+    trace
+}
+
+#[track_caller]
+fn assert_golden(name: &str, machine: &mut Machine, to: Address, calldata: Vec<u8>) {
+    let mut logger = StructLogger::default();
+    let result = machine.call_with_inspector(Address::ZERO, to, calldata, 1_000_000, &mut logger);
+    let actual = normalize(&logger.logs, &result);
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.trace", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| panic!("no golden trace at {}; run with UPDATE_GOLDEN=1", path.display()));
+    if let Some((line, (expected, actual))) = expected.lines().zip(actual.lines()).enumerate().find(|(_, (e, a))| e != a) {
+        panic!("{} diverges at line {}:\n  golden: {}\n  actual: {}", name, line + 1, expected, actual);
+    }
+    assert_eq!(expected.lines().count(), actual.lines().count(), "{} has a different number of steps", name);
+}
+
+fn machine_with(code: &str) -> Machine {
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract(), Account::with_code(assemble(code)));
+    machine
+}
+
+#[test]
+fn golden_arithmetic() {
+    let mut machine = machine_with("PUSH1 0x05 PUSH1 0x0a ADD PUSH1 0x03 MUL PUSH1 0x04 SWAP1 SUB PUSH1 0x02 SWAP1 DIV PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN");
+    assert_golden("arithmetic", &mut machine, contract(), vec![]);
+}
+
+#[test]
+fn golden_storage_loop() {
+    // writes the counter to slot 0 on every iteration
+    let mut machine = machine_with("PUSH1 0x03 JUMPDEST DUP1 PUSH1 0x00 SSTORE PUSH1 0x01 SUB DUP1 PUSH1 0x02 JUMPI STOP");
+    assert_golden("storage_loop", &mut machine, contract(), vec![]);
+}
+
+#[test]
+fn golden_berlin_storage_access() {
+    let mut machine = machine_with("PUSH1 0x00 SLOAD POP PUSH1 0x07 PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x00 SSTORE PUSH1 0x00 SLOAD STOP");
+    machine.hardfork = Hardfork::Berlin;
+    machine.accounts.get_mut(&contract()).unwrap().storage.insert(U256::ZERO, U256::from(1));
+    assert_golden("berlin_storage_access", &mut machine, contract(), vec![]);
+}
+
+#[test]
+fn golden_nested_call() {
+    let mut machine = machine_with(&format!(
+        "PUSH1 0x20 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 {} PUSH2 0xffff CALL POP \
+         PUSH1 0x20 PUSH1 0x00 PUSH1 0x20 RETURNDATACOPY PUSH1 0x20 PUSH1 0x20 RETURN",
+        sub()
+    ));
+    machine.accounts.insert(sub(), Account::with_code(assemble("PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")));
+    assert_golden("nested_call", &mut machine, contract(), vec![]);
+}
+
+#[test]
+fn golden_revert() {
+    let mut machine = machine_with("PUSH1 0x01 PUSH1 0x00 SSTORE PUSH1 0xff PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 REVERT");
+    assert_golden("revert", &mut machine, contract(), vec![]);
+}
+
+#[test]
+fn golden_erc20_transfer() {
+    let mut machine = Machine::default();
+    let erc20 = Erc20::deploy(&mut machine, contract());
+    erc20.mint(&mut machine, Address::ZERO, U256::from(1000)).unwrap();
+    let calldata = IERC20::transferCall { to: sub(), amount: U256::from(250) }.abi_encode();
+    assert_golden("erc20_transfer", &mut machine, contract(), calldata);
+}
@@ -0,0 +1,115 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, ExecutionResult, Hardfork, Machine, Transaction};
+use ruint::aliases::U256;
+
+mod common;
+use common::{assemble, contract};
+
+fn sender() -> Address {
+    "0x3000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn coinbase() -> Address {
+    "0x4000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn machine(hardfork: Hardfork, code: &str) -> Machine {
+    let mut machine = Machine::default();
+    machine.hardfork = hardfork;
+    machine.block.coinbase = coinbase();
+    machine.accounts.insert(contract(), Account::with_code(assemble(code)));
+    machine
+}
+
+fn tx(access_list: Vec<(Address, Vec<U256>)>) -> Transaction {
+    Transaction { caller: sender(), to: contract(), gas_limit: 100_000, access_list, ..Default::default() }
+}
+
+// gas used by execution alone, without the intrinsic cost
+fn execution_gas(machine: &mut Machine, tx: &Transaction) -> u64 {
+    let outcome = machine.transact(tx).unwrap();
+    assert_eq!(outcome.result, ExecutionResult::Success(vec![]));
+    outcome.gas_used - 21000 - tx.access_list.iter().map(|(_, keys)| 2400 + 1900 * keys.len() as u64).sum::<u64>()
+}
+
+fn call_code(target: Address) -> String {
+    format!("PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 {} PUSH1 0x00 CALL POP STOP", target)
+}
+
+const SLOAD_TWICE: &str = "PUSH1 0x01 SLOAD POP PUSH1 0x01 SLOAD POP STOP";
+
+#[test]
+fn test_sload_is_flat_before_berlin() {
+    let mut machine = machine(Hardfork::Istanbul, SLOAD_TWICE);
+    assert_eq!(execution_gas(&mut machine, &tx(vec![])), 4 * 3 + 2 * 800);
+}
+
+#[test]
+fn test_sload_cold_then_warm() {
+    let mut machine = machine(Hardfork::Berlin, SLOAD_TWICE);
+    assert_eq!(execution_gas(&mut machine, &tx(vec![])), 4 * 3 + 2100 + 100);
+    // access sets start over with every transaction
+    assert_eq!(execution_gas(&mut machine, &tx(vec![])), 4 * 3 + 2100 + 100);
+}
+
+#[test]
+fn test_access_list_prewarms_slots() {
+    let mut machine = machine(Hardfork::Berlin, SLOAD_TWICE);
+    assert_eq!(execution_gas(&mut machine, &tx(vec![(contract(), vec![U256::from(1)])])), 4 * 3 + 100 + 100);
+}
+
+#[test]
+fn test_coinbase_is_warm_from_shanghai() {
+    for (hardfork, access_cost) in [(Hardfork::Berlin, 2600), (Hardfork::London, 2600), (Hardfork::Shanghai, 100), (Hardfork::Cancun, 100)] {
+        let mut machine = machine(hardfork, &call_code(coinbase()));
+        assert_eq!(execution_gas(&mut machine, &tx(vec![])), 8 * 3 + access_cost, "{:?}", hardfork);
+    }
+}
+
+#[test]
+fn test_origin_callee_and_precompiles_start_warm() {
+    for target in [sender(), Address::with_last_byte(1), Address::with_last_byte(9)] {
+        let mut machine = machine(Hardfork::Berlin, &call_code(target));
+        assert_eq!(execution_gas(&mut machine, &tx(vec![])), 8 * 3 + 100, "{}", target);
+        assert!(machine.accessed_addresses.contains(&contract()));
+    }
+
+    // the point evaluation precompile only exists from Cancun
+    let mut machine = machine(Hardfork::Shanghai, &call_code(Address::with_last_byte(10)));
+    assert_eq!(execution_gas(&mut machine, &tx(vec![])), 8 * 3 + 2600);
+    machine.hardfork = Hardfork::Cancun;
+    assert_eq!(execution_gas(&mut machine, &tx(vec![])), 8 * 3 + 100);
+}
+
+#[test]
+fn test_call_warms_its_target() {
+    let target = "0x5000000000000000000000000000000000000000".parse().unwrap();
+    let code = call_code(target).replace(" STOP", " ") + &call_code(target);
+    let mut machine = machine(Hardfork::Berlin, &code);
+    assert_eq!(execution_gas(&mut machine, &tx(vec![])), 16 * 3 + 2600 + 100);
+
+    let mut machine = self::machine(Hardfork::Berlin, &call_code(target));
+    assert_eq!(execution_gas(&mut machine, &tx(vec![(target, vec![])])), 8 * 3 + 100);
+}
+
+#[test]
+fn test_sstore_net_metering() {
+    const STORE_TWICE: &str = "PUSH1 0x07 PUSH1 0x00 SSTORE PUSH1 0x08 PUSH1 0x00 SSTORE STOP";
+
+    let mut machine = machine(Hardfork::Berlin, STORE_TWICE);
+    assert_eq!(execution_gas(&mut machine, &tx(vec![])), 4 * 3 + (2100 + 20000) + 100);
+
+    // slot 0 now holds 8, so the first write resets a nonzero slot and the second one
+    // restores the original value, refunding the reset minus a warm read
+    assert_eq!(execution_gas(&mut machine, &tx(vec![])), 4 * 3 + (2100 + 2900) + 100 - 2800);
+
+    let mut machine = self::machine(Hardfork::Berlin, "PUSH1 0x00 PUSH1 0x00 SSTORE STOP");
+    assert_eq!(execution_gas(&mut machine, &tx(vec![])), 2 * 3 + 2100 + 100);
+}
+
+#[test]
+fn test_sstore_fails_within_the_stipend() {
+    let mut machine = machine(Hardfork::Berlin, "PUSH1 0x07 PUSH1 0x00 SSTORE STOP");
+    let outcome = machine.transact(&Transaction { gas_limit: 21000 + 6 + 2300, ..tx(vec![]) }).unwrap();
+    assert_eq!(outcome.result, ExecutionResult::OutOfGas);
+}
@@ -0,0 +1,96 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, ExecutionResult, Limits, Machine};
+use ruint::aliases::U256;
+use std::time::{Duration, Instant};
+
+mod common;
+use common::assemble;
+
+fn outer() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn middle() -> Address {
+    "0x2100000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn inner() -> Address {
+    "0x2200000000000000000000000000000000000000".parse().unwrap()
+}
+
+// stores the result of calling `to` in storage[0]
+fn call_and_store(to: Address) -> Vec<u8> {
+    assemble(&format!(
+        "PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 0x{} PUSH2 0xffff CALL PUSH1 0x00 SSTORE STOP",
+        to.to_string().strip_prefix("0x").unwrap()
+    ))
+}
+
+// outer -> middle -> inner, where inner sets storage[0] = 1
+fn nested_calls(limits: Limits) -> Machine {
+    let mut machine = Machine::default();
+    machine.limits = limits;
+    machine.accounts.insert(outer(), Account::with_code(call_and_store(middle())));
+    machine.accounts.insert(middle(), Account::with_code(call_and_store(inner())));
+    machine.accounts.insert(inner(), Account::with_code(assemble("PUSH1 0x01 PUSH1 0x00 SSTORE STOP")));
+    machine
+}
+
+#[test]
+fn test_default_limits() {
+    assert_eq!(Limits::default(), Limits { max_call_depth: 1024, max_stack_height: 1024, timeout: None });
+}
+
+#[test]
+fn test_calls_below_the_depth_limit_succeed() {
+    let mut machine = nested_calls(Limits { max_call_depth: 2, ..Default::default() });
+    let result = machine.call(Address::ZERO, outer(), vec![], 1_000_000);
+
+    assert_eq!(result, ExecutionResult::Success(vec![]));
+    assert_eq!(machine.storage(middle(), U256::ZERO), Ok(U256::from(1)));
+    assert_eq!(machine.storage(inner(), U256::ZERO), Ok(U256::from(1)));
+}
+
+#[test]
+fn test_call_past_the_depth_limit_fails_without_halting() {
+    let mut machine = nested_calls(Limits { max_call_depth: 1, ..Default::default() });
+    let result = machine.call(Address::ZERO, outer(), vec![], 1_000_000);
+
+    assert_eq!(result, ExecutionResult::Success(vec![]));
+    assert_eq!(machine.storage(outer(), U256::ZERO), Ok(U256::from(1)));
+    assert_eq!(machine.storage(middle(), U256::ZERO), Ok(U256::ZERO));
+    assert_eq!(machine.storage(inner(), U256::ZERO), Ok(U256::ZERO));
+}
+
+#[test]
+fn test_stack_height_limit() {
+    let mut machine = Machine::default();
+    machine.limits.max_stack_height = 2;
+    machine.accounts.insert(outer(), Account::with_code(assemble("PUSH1 0x01 PUSH1 0x02 ADD PUSH1 0x03 STOP")));
+    assert_eq!(machine.call(Address::ZERO, outer(), vec![], 100_000), ExecutionResult::Success(vec![]));
+
+    machine.accounts.insert(middle(), Account::with_code(assemble("PUSH1 0x01 PUSH1 0x02 PUSH1 0x03 STOP")));
+    assert_eq!(machine.call(Address::ZERO, middle(), vec![], 100_000), ExecutionResult::StackOverflow);
+}
+
+#[test]
+fn test_unbounded_push_loop_overflows_at_default_height() {
+    let mut machine = Machine::default();
+    machine.accounts.insert(outer(), Account::with_code(assemble("JUMPDEST PUSH1 0x01 PUSH1 0x00 JUMP")));
+    assert_eq!(machine.call(Address::ZERO, outer(), vec![], 1_000_000), ExecutionResult::StackOverflow);
+}
+
+#[test]
+fn test_timeout_aborts_an_endless_loop() {
+    let mut machine = Machine::default();
+    machine.limits.timeout = Some(Duration::from_millis(50));
+    machine.accounts.insert(outer(), Account::with_code(assemble("JUMPDEST PUSH1 0x00 JUMP")));
+
+    let started = Instant::now();
+    assert_eq!(machine.call(Address::ZERO, outer(), vec![], u64::MAX), ExecutionResult::Timeout);
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    // the clock starts over with every call
+    machine.accounts.insert(middle(), Account::with_code(assemble("PUSH1 0x01 POP STOP")));
+    assert_eq!(machine.call(Address::ZERO, middle(), vec![], 100_000), ExecutionResult::Success(vec![]));
+}
@@ -0,0 +1,82 @@
+use alloy::primitives::B256;
+use native_vs_evm::artifacts::Artifact;
+use native_vs_evm::metadata::{parse_metadata, strip_metadata, SolcVersion};
+use native_vs_evm::opcode::disassemble;
+use std::collections::HashMap;
+
+mod common;
+use common::assemble;
+
+// {"ipfs": 0x1220 00 01 .. 1f, "solc": 0.8.24}, as solc >= 0.6 appends it
+fn ipfs_trailer() -> Vec<u8> {
+    let mut trailer = hex::decode("a2646970667358221220").unwrap();
+    trailer.extend(0..32u8);
+    trailer.extend(hex::decode("64736f6c6343000818").unwrap());
+    trailer.extend([0x00, 0x33]);
+    trailer
+}
+
+fn code() -> Vec<u8> {
+    assemble("PUSH1 0x2a PUSH1 0x00 SSTORE STOP INVALID")
+}
+
+#[test]
+fn test_parse_ipfs_metadata() {
+    let mut code = code();
+    code.extend(ipfs_trailer());
+    let metadata = parse_metadata(&code).unwrap();
+
+    assert_eq!(metadata.solc, Some(SolcVersion::Release(0, 8, 24)));
+    assert_eq!(metadata.ipfs.as_deref().map(|hash| hash.len()), Some(34));
+    assert_eq!(metadata.ipfs_cid().as_deref(), Some("QmNLfbof5rLekrACjeuLk9JmGZD2HDBHCU4z16iYKmx5SE"));
+    assert_eq!(metadata.size, 53);
+    assert!(!metadata.experimental);
+    assert_eq!(strip_metadata(&code), &code[..7]);
+}
+
+#[test]
+fn test_parse_legacy_bzzr0_metadata() {
+    // {"bzzr0": 0xee..ee}, as solc 0.4 appended it
+    let mut code = code();
+    code.extend(hex::decode("a165627a7a72305820").unwrap());
+    code.extend([0xee; 32]);
+    code.extend([0x00, 0x29]);
+    let metadata = parse_metadata(&code).unwrap();
+
+    assert_eq!(metadata.bzzr0, Some(B256::repeat_byte(0xee)));
+    assert_eq!(metadata.solc, None);
+    assert_eq!(strip_metadata(&code), &code[..7]);
+}
+
+#[test]
+fn test_code_without_metadata_is_left_alone() {
+    let code = code();
+    assert_eq!(parse_metadata(&code), None);
+    assert_eq!(strip_metadata(&code), code.as_slice());
+    assert_eq!(parse_metadata(&[]), None);
+
+    // the length fits, but what it points at is not a map of known keys
+    let mut code = code.clone();
+    code.extend([0xa1, 0x61, b'x', 0x01, 0x00, 0x04]);
+    assert_eq!(parse_metadata(&code), None);
+}
+
+#[test]
+fn test_disassemble_shows_metadata_as_one_line() {
+    let mut code = code();
+    code.extend(ipfs_trailer());
+    assert_eq!(
+        disassemble(&code),
+        "0: PUSH1 0x2a\n2: PUSH1 0x00\n4: SSTORE\n5: STOP\n6: INVALID\n7: metadata solc 0.8.24, ipfs QmNLfbof5rLekrACjeuLk9JmGZD2HDBHCU4z16iYKmx5SE\n"
+    );
+}
+
+#[test]
+fn test_artifact_without_metadata() {
+    let mut code = code();
+    code.extend(ipfs_trailer());
+    let artifact = Artifact { name: "Store".into(), source: None, deployed_bytecode: hex::encode(&code) };
+
+    assert_eq!(artifact.link_stripped(&HashMap::new()).unwrap(), code[..7]);
+    assert_eq!(artifact.metadata().unwrap().and_then(|metadata| metadata.solc), Some(SolcVersion::Release(0, 8, 24)));
+}
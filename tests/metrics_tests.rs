@@ -0,0 +1,45 @@
+#![cfg(feature = "metrics")]
+
+use alloy::primitives::Address;
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+
+mod common;
+use common::assemble;
+
+#[test]
+fn test_executions_are_recorded() {
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+    let sub: Address = "0x2100000000000000000000000000000000000000".parse().unwrap();
+    let mut machine = Machine::default();
+    machine.accounts.insert(sub, Account::with_code(assemble("STOP")));
+    machine.accounts.insert(contract, Account::with_code(assemble(&format!(
+        "PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 {} PUSH2 0xffff CALL STOP",
+        sub
+    ))));
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        assert_eq!(machine.call(Address::ZERO, contract, vec![], 100_000), ExecutionResult::Success(vec![]));
+        assert_eq!(machine.call(Address::ZERO, sub, vec![], 0), ExecutionResult::Success(vec![]));
+    });
+
+    let metrics: Vec<_> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| (key.key().name().to_string(), key.key().labels().map(|label| label.value().to_string()).collect::<Vec<_>>(), value))
+        .collect();
+    let find = |name: &str| metrics.iter().find(|(key, _, _)| key == name).unwrap();
+
+    assert_eq!(find("evm_executions_total").1, vec!["success"]);
+    assert_eq!(find("evm_executions_total").2, DebugValue::Counter(2));
+    // 9 instructions in the caller, STOP in the callee, then STOP on its own
+    assert_eq!(find("evm_opcodes_executed_total").2, DebugValue::Counter(11));
+    assert_eq!(find("evm_gas_consumed_total").2, DebugValue::Counter(7 * 3));
+    let DebugValue::Histogram(depths) = &find("evm_frame_depth").2 else { panic!() };
+    let mut depths: Vec<f64> = depths.iter().map(|depth| depth.into_inner()).collect();
+    depths.sort_by(f64::total_cmp);
+    assert_eq!(depths, vec![1.0, 1.0, 2.0]);
+}
@@ -0,0 +1,55 @@
+use native_vs_evm::opcode::{decode, disassemble, Opcode};
+
+mod common;
+use common::assemble;
+
+#[test]
+fn test_every_opcode_round_trips() {
+    for &opcode in Opcode::ALL {
+        assert_eq!(Opcode::try_from(opcode as u8), Ok(opcode));
+        assert_eq!(Opcode::from_mnemonic(opcode.mnemonic()), Some(opcode));
+    }
+    assert_eq!(Opcode::try_from(0x0c), Err(0x0c));
+    assert_eq!(Opcode::from_mnemonic("push0"), None);
+    assert_eq!(Opcode::from_mnemonic("sstore"), Some(Opcode::SStore));
+}
+
+#[test]
+fn test_metadata() {
+    assert_eq!(Opcode::Push1.immediate_size(), 1);
+    assert_eq!(Opcode::Push32.immediate_size(), 32);
+    assert_eq!(Opcode::Add.immediate_size(), 0);
+    assert_eq!(Opcode::Dup3.stack_io(), (3, 4));
+    assert_eq!(Opcode::Swap2.stack_io(), (3, 3));
+    assert_eq!(Opcode::Log2.stack_io(), (4, 0));
+    assert_eq!(Opcode::Log2.base_gas(), 1125);
+    assert_eq!(Opcode::Swap16.mnemonic(), "SWAP16");
+    assert!(Opcode::Revert.is_terminator() && !Opcode::JumpI.is_terminator());
+}
+
+#[test]
+fn test_decode_steps_over_push_data() {
+    let code = assemble("PUSH2 0x5b5b JUMPDEST PUSH1 0x01");
+    let instructions: Vec<_> = decode(&code).map(|instruction| (instruction.pc, instruction.opcode)).collect();
+
+    assert_eq!(instructions, vec![(0, Some(Opcode::Push2)), (3, Some(Opcode::JumpDest)), (4, Some(Opcode::Push1))]);
+}
+
+#[test]
+fn test_truncated_push_and_undefined_bytes() {
+    let code = [0x0c, 0x62, 0xaa];
+    let instructions: Vec<_> = decode(&code).collect();
+
+    assert_eq!(instructions.len(), 2);
+    assert_eq!(instructions[0].opcode, None);
+    assert!(instructions[1].is_truncated());
+    assert_eq!(instructions[1].immediate, &[0xaa]);
+    assert_eq!(disassemble(&code), "0: opcode 0x0c not defined\n1: PUSH3 0xaa\n");
+}
+
+#[test]
+fn test_disassemble() {
+    let code = assemble("PUSH1 0x2a PUSH1 0x00 SSTORE STOP");
+
+    assert_eq!(disassemble(&code), "0: PUSH1 0x2a\n2: PUSH1 0x00\n4: SSTORE\n5: STOP\n");
+}
@@ -0,0 +1,106 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, ExecutionResult, Host, Machine, Transaction, TransactionError};
+use native_vs_evm::overrides::{AccountOverride, StateOverride};
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+mod common;
+use common::{assemble, contract};
+
+fn sender() -> Address {
+    "0x3000000000000000000000000000000000000000".parse().unwrap()
+}
+
+// returns storage[1] + storage[2]
+fn sum_code() -> Vec<u8> {
+    assemble("PUSH1 0x01 SLOAD PUSH1 0x02 SLOAD ADD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")
+}
+
+// every slot holds 10 and the contract account has a balance of 3
+#[derive(Debug)]
+struct TensHost;
+
+impl Host for TensHost {
+    fn basic(&mut self, address: Address) -> Result<Account, String> {
+        match address {
+            a if a == contract() => Ok(Account { balance: U256::from(3), ..Account::with_code(sum_code()) }),
+            _ => Ok(Account::default()),
+        }
+    }
+
+    fn storage(&mut self, _address: Address, _key: U256) -> Result<U256, String> {
+        Ok(U256::from(10))
+    }
+}
+
+fn word(value: u64) -> Vec<u8> {
+    U256::from(value).to_be_bytes::<32>().to_vec()
+}
+
+fn call(machine: &mut Machine, overrides: &StateOverride) -> ExecutionResult {
+    machine.call_with_overrides(Address::ZERO, contract(), vec![], 100_000, overrides)
+}
+
+#[test]
+fn test_state_diff_patches_listed_slots() {
+    let mut machine = Machine::with_host(TensHost);
+    let overrides = StateOverride::from([(contract(), AccountOverride {
+        state_diff: HashMap::from([(U256::from(1), U256::from(5))]),
+        ..Default::default()
+    })]);
+
+    assert_eq!(call(&mut machine, &overrides), ExecutionResult::Success(word(15)));
+    assert_eq!(call(&mut machine, &StateOverride::new()), ExecutionResult::Success(word(20)));
+}
+
+#[test]
+fn test_state_replaces_the_whole_storage() {
+    let mut machine = Machine::with_host(TensHost);
+    let overrides = StateOverride::from([(contract(), AccountOverride {
+        state: Some(HashMap::from([(U256::from(1), U256::from(5))])),
+        ..Default::default()
+    })]);
+
+    assert_eq!(call(&mut machine, &overrides), ExecutionResult::Success(word(5)));
+    assert_eq!(machine.storage(contract(), U256::from(2)), Ok(U256::from(10)));
+}
+
+#[test]
+fn test_code_override_keeps_balance() {
+    let mut machine = Machine::with_host(TensHost);
+    // returns its own storage[1] - 3, where storage[1] gets overridden
+    let overrides = StateOverride::from([(contract(), AccountOverride {
+        code: Some(assemble("PUSH1 0x01 SLOAD PUSH1 0x03 SUB PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")),
+        state_diff: HashMap::from([(U256::from(1), U256::from(4))]),
+        ..Default::default()
+    })]);
+
+    assert_eq!(call(&mut machine, &overrides), ExecutionResult::Success(word(1)));
+    assert_eq!(machine.account(contract()).unwrap().balance, U256::from(3));
+    assert_eq!(*machine.code(contract()).unwrap(), sum_code());
+}
+
+#[test]
+fn test_balance_and_nonce_overrides_apply_to_simulate() {
+    let mut machine = Machine::with_host(TensHost);
+    let tx = Transaction {
+        caller: sender(),
+        to: contract(),
+        gas_limit: 100_000,
+        gas_price: U256::from(1),
+        nonce: Some(7),
+        ..Default::default()
+    };
+    assert_eq!(machine.simulate(&tx).unwrap_err(), TransactionError::NonceMismatch { expected: 0, got: 7 });
+
+    let overrides = StateOverride::from([(sender(), AccountOverride {
+        balance: Some(U256::from(1_000_000)),
+        nonce: Some(7),
+        ..Default::default()
+    })]);
+    let outcome = machine.simulate_with_overrides(&tx, &overrides).unwrap();
+    assert_eq!(outcome.result, ExecutionResult::Success(word(20)));
+
+    let sender = machine.account(sender()).unwrap();
+    assert_eq!((sender.balance, sender.nonce), (U256::ZERO, 0));
+}
@@ -0,0 +1,78 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+use native_vs_evm::profiler::{GasFlamegraph, PcCost, PcProfiler};
+
+mod common;
+use common::{assemble, sub};
+
+fn outer() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+// calls sub with selector 0x12345678, which writes a slot
+fn machine() -> Machine {
+    let mut machine = Machine::default();
+    machine.accounts.insert(sub(), Account::with_code(assemble("PUSH1 0x01 PUSH1 0x00 SSTORE STOP")));
+    machine.accounts.insert(outer(), Account::with_code(assemble(&format!(
+        "PUSH32 0x1234567800000000000000000000000000000000000000000000000000000000 PUSH1 0x00 MSTORE \
+         PUSH1 0x00 PUSH1 0x00 PUSH1 0x04 PUSH1 0x00 PUSH1 0x00 PUSH20 {} PUSH2 0xffff CALL POP STOP",
+        sub()
+    ))));
+    machine
+}
+
+#[test]
+fn test_gas_is_attributed_to_call_paths() {
+    let mut flamegraph = GasFlamegraph::default();
+    let result = machine().call_with_inspector(Address::ZERO, outer(), vec![0xde, 0xad, 0xbe, 0xef], 100_000, &mut flamegraph);
+    assert_eq!(result, ExecutionResult::Success(vec![]));
+
+    let outer_path = format!("{}:0xdeadbeef", outer());
+    let sub_path = format!("{};{}:0x12345678", outer_path, sub());
+    let folded: Vec<_> = flamegraph.folded().iter().map(|(path, gas)| (path.clone(), *gas)).collect();
+    // PUSH32, PUSH1, MSTORE with one word of memory, seven pushes and POP; the CALL forwards the rest
+    assert_eq!(folded, vec![(outer_path.clone(), 3 + 3 + 6 + 7 * 3 + 3), (sub_path.clone(), 3 + 3 + 20000)]);
+
+    let mut output = Vec::new();
+    flamegraph.write_folded(&mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), format!("{} 36\n{} 20006\n", outer_path, sub_path));
+}
+
+#[test]
+fn test_frames_without_a_selector_use_the_address() {
+    let mut flamegraph = GasFlamegraph::default();
+    machine().call_with_inspector(Address::ZERO, sub(), vec![], 100_000, &mut flamegraph);
+    assert_eq!(flamegraph.folded()[&sub().to_string()], 20006);
+}
+
+#[test]
+fn test_pc_profile_counts_loop_iterations() {
+    let mut machine = machine();
+    machine.accounts.insert(outer(), Account::with_code(assemble("PUSH1 0x03 JUMPDEST PUSH1 0x01 SUB DUP1 PUSH1 0x02 JUMPI STOP")));
+    let mut profiler = PcProfiler::default();
+    machine.call_with_inspector(Address::ZERO, outer(), vec![], 100_000, &mut profiler);
+
+    let costs = profiler.costs(outer()).unwrap();
+    let hits_and_gas: Vec<_> = costs.iter().map(|(pc, cost)| (*pc, cost.hits, cost.gas)).collect();
+    assert_eq!(hits_and_gas, vec![(0, 1, 3), (2, 3, 0), (3, 3, 9), (5, 3, 9), (6, 3, 9), (7, 3, 9), (9, 3, 30), (10, 1, 0)]);
+    assert!(profiler.costs(sub()).is_none());
+
+    let mut output = Vec::new();
+    profiler.write_annotated(&mut output).unwrap();
+    let listing = String::from_utf8(output).unwrap();
+    let jumpi = listing.lines().find(|line| line.contains("JUMPI")).unwrap();
+    let columns: Vec<_> = jumpi.split_whitespace().collect();
+    assert_eq!(&columns[..4], &["9", "JUMPI", "3", "30"]);
+    assert!(listing.contains("PUSH1 0x03"));
+}
+
+#[test]
+fn test_pc_profile_charges_forwarded_gas_to_the_callee() {
+    let mut profiler = PcProfiler::default();
+    machine().call_with_inspector(Address::ZERO, outer(), vec![], 100_000, &mut profiler);
+
+    let call_pc = *profiler.costs(outer()).unwrap().keys().rev().nth(2).unwrap();
+    assert_eq!(profiler.costs(outer()).unwrap()[&call_pc].gas, 0);
+    let sstore = profiler.costs(sub()).unwrap()[&4];
+    assert_eq!(PcCost { nanos: 0, ..sstore }, PcCost { hits: 1, gas: 20000, nanos: 0 });
+}
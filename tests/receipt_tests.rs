@@ -0,0 +1,52 @@
+use alloy::primitives::{Address, Bloom, BloomInput, B256};
+use native_vs_evm::evm::{Account, Machine, Transaction};
+use native_vs_evm::receipt::logs_bloom;
+use ruint::aliases::U256;
+
+mod common;
+use common::assemble;
+
+fn contract_address() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn machine_with(code: &str) -> Machine {
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract_address(), Account::with_code(assemble(code)));
+    machine
+}
+
+#[test]
+fn test_receipt_from_transact() {
+    let mut machine = machine_with("PUSH1 0xaa PUSH1 0x00 MSTORE PUSH1 0x02 PUSH1 0x01 PUSH1 0x20 PUSH1 0x00 LOG2 PUSH1 0x03 PUSH1 0x00 PUSH1 0x00 LOG1 STOP");
+    let tx = Transaction { to: contract_address(), gas_limit: 100_000, ..Default::default() };
+
+    let outcome = machine.transact(&tx).unwrap();
+    let receipt = outcome.receipt(50_000);
+
+    assert!(receipt.status);
+    assert_eq!(receipt.cumulative_gas_used, 50_000 + outcome.gas_used);
+    assert_eq!(receipt.logs.len(), 2);
+    assert_eq!(receipt.logs_bloom, alloy::primitives::logs_bloom(receipt.logs.iter()));
+    assert!(receipt.logs_bloom.contains_input(BloomInput::Raw(contract_address().as_slice())));
+    assert!(receipt.logs_bloom.contains_input(BloomInput::Raw(B256::from(U256::from(3)).as_slice())));
+}
+
+#[test]
+fn test_reverted_receipt_drops_logs() {
+    let mut machine = machine_with("PUSH1 0x01 PUSH1 0x00 PUSH1 0x00 LOG1 PUSH1 0x00 PUSH1 0x00 REVERT");
+    let tx = Transaction { to: contract_address(), gas_limit: 100_000, ..Default::default() };
+
+    let receipt = machine.transact(&tx).unwrap().receipt(0);
+    assert!(!receipt.status);
+    assert!(receipt.logs.is_empty());
+    assert_eq!(receipt.logs_bloom, Bloom::ZERO);
+}
+
+#[test]
+fn test_logs_bloom_of_empty_topics() {
+    let log = alloy::primitives::Log::new_unchecked(contract_address(), vec![], vec![1, 2, 3].into());
+    let bloom = logs_bloom(std::slice::from_ref(&log));
+    assert_eq!(bloom, alloy::primitives::logs_bloom([&log]));
+    assert_eq!(bloom.iter().map(|byte| byte.count_ones()).sum::<u32>(), 3);
+}
@@ -0,0 +1,67 @@
+use alloy::primitives::Address;
+use native_vs_evm::binary_trace::{BinaryTracer, TraceReader};
+use native_vs_evm::evm::{Account, ExecutionResult, Hardfork, Machine};
+use native_vs_evm::tracer::{StructLog, StructLogger};
+
+mod common;
+use common::{assemble, contract};
+
+// loads a slot in a loop and returns the last value read
+fn machine() -> Machine {
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract(), Account::with_code(assemble(
+        "PUSH1 0x03 JUMPDEST PUSH1 0x00 SLOAD POP PUSH1 0x01 SUB DUP1 PUSH1 0x02 JUMPI PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN",
+    )));
+    machine
+}
+
+fn record(machine: &mut Machine) -> Vec<StructLog> {
+    let mut logger = StructLogger::default();
+    machine.call_with_inspector(Address::ZERO, contract(), vec![], 100_000, &mut logger);
+    logger.logs
+}
+
+#[test]
+fn test_identical_run_replays_cleanly() {
+    let recorded = record(&mut machine());
+    let result = machine().replay(Address::ZERO, contract(), vec![], 100_000, recorded).unwrap();
+    assert_eq!(result, ExecutionResult::Success(vec![0; 32]));
+}
+
+#[test]
+fn test_gas_change_is_flagged_at_the_first_differing_step() {
+    let recorded = record(&mut machine());
+    let mut berlin = machine();
+    berlin.hardfork = Hardfork::Berlin;
+
+    let divergence = berlin.replay(Address::ZERO, contract(), vec![], 100_000, recorded).unwrap_err();
+    assert_eq!(divergence.step, 3);
+    assert_eq!(divergence.fields, vec!["gas_cost"]);
+    assert_eq!(divergence.expected.unwrap().gas_cost, 800);
+    assert_eq!(divergence.actual.unwrap().gas_cost, 2100);
+}
+
+#[test]
+fn test_replay_from_a_binary_trace() {
+    let mut tracer = BinaryTracer::new(Vec::new()).unwrap();
+    machine().call_with_inspector(Address::ZERO, contract(), vec![], 100_000, &mut tracer);
+    let trace = tracer.finish().unwrap();
+
+    let recorded: Vec<_> = TraceReader::new(trace.as_slice()).unwrap().collect::<Result<_, _>>().unwrap();
+    assert!(machine().replay(Address::ZERO, contract(), vec![], 100_000, recorded).is_ok());
+}
+
+#[test]
+fn test_length_mismatches() {
+    let mut recorded = record(&mut machine());
+    let steps = recorded.len();
+    let last = recorded.pop().unwrap();
+    let divergence = machine().replay(Address::ZERO, contract(), vec![], 100_000, recorded.clone()).unwrap_err();
+    assert_eq!((divergence.step, divergence.expected, divergence.actual), (steps - 1, None, Some(last.clone())));
+
+    recorded.push(last.clone());
+    recorded.push(last);
+    let divergence = machine().replay(Address::ZERO, contract(), vec![], 100_000, recorded).unwrap_err();
+    assert_eq!(divergence.step, steps);
+    assert!(divergence.actual.is_none() && divergence.fields.is_empty());
+}
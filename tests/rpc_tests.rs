@@ -0,0 +1,162 @@
+#![cfg(feature = "rpc")]
+
+use alloy::consensus::{SignableTransaction, TxEnvelope, TxLegacy};
+use alloy::eips::eip2718::Encodable2718;
+use alloy::primitives::{Address, TxKind};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use native_vs_evm::evm::{Account, Machine};
+use native_vs_evm::rpc::RpcServer;
+use ruint::aliases::U256;
+use serde_json::{json, Value};
+use std::rc::Rc;
+
+mod common;
+use common::assemble;
+
+const CHAIN_ID: u64 = 31337;
+
+fn contract_address() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn server() -> RpcServer {
+    let mut machine = Machine::default();
+    // stores calldata[0..32] at slot 1 and returns the previous value
+    machine.accounts.insert(contract_address(), Account {
+        code: Rc::new(assemble("PUSH1 0x01 SLOAD PUSH1 0x00 MSTORE PUSH1 0x00 CALLDATALOAD PUSH1 0x01 SSTORE PUSH1 0x20 PUSH1 0x00 RETURN")),
+        ..Default::default()
+    });
+    RpcServer::new(machine, CHAIN_ID)
+}
+
+fn request(server: &mut RpcServer, method: &str, params: Value) -> Value {
+    let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    server.handle_request(body.to_string().as_bytes())
+}
+
+fn word(value: u64) -> String {
+    format!("0x{}", hex::encode(U256::from(value).to_be_bytes::<32>()))
+}
+
+#[test]
+fn test_eth_call_does_not_persist_state() {
+    let mut server = server();
+    let call = json!({ "to": contract_address(), "input": word(7) });
+
+    let response = request(&mut server, "eth_call", json!([call, "latest"]));
+    assert_eq!(response["result"], json!(word(0)));
+
+    let response = request(&mut server, "eth_getStorageAt", json!([contract_address(), "0x1", "latest"]));
+    assert_eq!(response["result"], json!(word(0)));
+}
+
+#[test]
+fn test_send_raw_transaction_updates_state() {
+    let mut server = server();
+    let signer = PrivateKeySigner::random();
+    server.machine.accounts.insert(signer.address(), Account { balance: U256::from(10u64.pow(18)), ..Default::default() });
+
+    let tx = TxLegacy {
+        chain_id: Some(CHAIN_ID),
+        nonce: 0,
+        gas_price: 1,
+        gas_limit: 100_000,
+        to: TxKind::Call(contract_address()),
+        value: U256::from(5),
+        input: U256::from(9).to_be_bytes::<32>().to_vec().into(),
+    };
+    let signature = signer.sign_hash_sync(&tx.signature_hash()).unwrap();
+    let envelope: TxEnvelope = tx.into_signed(signature).into();
+    let raw = format!("0x{}", hex::encode(envelope.encoded_2718()));
+
+    let response = request(&mut server, "eth_sendRawTransaction", json!([raw]));
+    assert_eq!(response["result"], json!(envelope.tx_hash()));
+
+    let response = request(&mut server, "eth_getStorageAt", json!([contract_address(), "0x1", "latest"]));
+    assert_eq!(response["result"], json!(word(9)));
+    let response = request(&mut server, "eth_getBalance", json!([contract_address(), "latest"]));
+    assert_eq!(response["result"], json!("0x5"));
+    let response = request(&mut server, "eth_getTransactionCount", json!([signer.address(), "latest"]));
+    assert_eq!(response["result"], json!("0x1"));
+
+    let response = request(&mut server, "eth_sendRawTransaction", json!([raw]));
+    assert_eq!(response["error"]["code"], json!(-32000));
+}
+
+#[test]
+fn test_debug_trace_call_returns_struct_logs() {
+    let mut server = server();
+    let call = json!({ "to": contract_address(), "input": word(7) });
+
+    let response = request(&mut server, "debug_traceCall", json!([call, "latest", {}]));
+    let trace = &response["result"];
+    assert_eq!(trace["failed"], json!(false));
+    assert_eq!(trace["returnValue"], json!(hex::encode(U256::ZERO.to_be_bytes::<32>())));
+
+    let logs = trace["structLogs"].as_array().unwrap();
+    assert_eq!(logs.len(), 11);
+    assert_eq!(logs[0], json!({ "pc": 0, "op": "PUSH1", "gas": 30_000_000 - 21_000 - 31 * 4 - 16, "gasCost": 3, "depth": 1, "stack": [] }));
+    assert_eq!(logs[1]["op"], json!("SLOAD"));
+    assert_eq!(logs[1]["stack"], json!(["0x1"]));
+    assert_eq!(logs[1]["gasCost"], json!(800));
+    assert_eq!(logs[10]["op"], json!("RETURN"));
+}
+
+#[test]
+fn test_reverts_and_unknown_methods() {
+    let mut server = server();
+    let reverting: Address = "0x4000000000000000000000000000000000000000".parse().unwrap();
+    server.machine.accounts.insert(reverting, Account {
+        code: Rc::new(assemble("PUSH1 0xde PUSH1 0x00 MSTORE PUSH1 0x01 PUSH1 0x1f REVERT")),
+        ..Default::default()
+    });
+
+    let response = request(&mut server, "eth_call", json!([{ "to": reverting }, "latest"]));
+    assert_eq!(response["error"], json!({ "code": 3, "message": "execution reverted", "data": "0xde" }));
+
+    let response = request(&mut server, "eth_mining", json!([]));
+    assert_eq!(response["error"]["code"], json!(-32601));
+}
+
+#[test]
+fn test_batch_request() {
+    let mut server = server();
+    let body = json!([
+        { "jsonrpc": "2.0", "id": 1, "method": "eth_chainId", "params": [] },
+        { "jsonrpc": "2.0", "id": 2, "method": "eth_blockNumber", "params": [] },
+    ]);
+
+    let response = server.handle_request(body.to_string().as_bytes());
+    assert_eq!(response[0]["result"], json!("0x7a69"));
+    assert_eq!(response[1]["result"], json!("0x0"));
+}
+
+#[test]
+fn test_eth_call_applies_state_overrides() {
+    let mut server = server();
+    let call = json!({ "to": contract_address(), "input": word(7) });
+    let overrides = json!({ contract_address().to_string(): { "stateDiff": { word(1): word(5) } } });
+
+    let response = request(&mut server, "eth_call", json!([call, "latest", overrides]));
+    assert_eq!(response["result"], json!(word(5)));
+
+    // returns 0x2a regardless of storage
+    let code = format!("0x{}", hex::encode(assemble("PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN")));
+    let overrides = json!({ contract_address().to_string(): { "code": code } });
+    let response = request(&mut server, "eth_call", json!([call, "latest", overrides]));
+    assert_eq!(response["result"], json!(word(42)));
+
+    let response = request(&mut server, "eth_getStorageAt", json!([contract_address(), "0x1", "latest"]));
+    assert_eq!(response["result"], json!(word(0)));
+}
+
+#[test]
+fn test_eth_create_access_list() {
+    let mut server = server();
+    let call = json!({ "to": contract_address(), "input": word(7) });
+
+    let response = request(&mut server, "eth_createAccessList", json!([call, "latest"]));
+    assert_eq!(response["result"]["accessList"], json!([{ "address": contract_address(), "storageKeys": [word(1)] }]));
+    assert!(response["result"].get("error").is_none());
+}
@@ -0,0 +1,57 @@
+use native_vs_evm::evm::ExecutionResult;
+use native_vs_evm::evm_asm;
+use native_vs_evm::scenario::{self, Mismatch, Scenario};
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+const RETURN_SUM: [u8; 13] = evm_asm! { push 5; push 10; add; push 0; mstore; push 0x20; push 0; return };
+
+#[test]
+fn test_matching_sides_verify() {
+    let scenario = Scenario::new("sum", RETURN_SUM.to_vec(), || U256::from(15));
+
+    assert_eq!(scenario.verify(), Ok(()));
+}
+
+#[test]
+fn test_output_and_failure_mismatches() {
+    let wrong = Scenario::new("sum", RETURN_SUM.to_vec(), || U256::from(16));
+    assert_eq!(wrong.verify(), Err(Mismatch::Output { native: U256::from(16), evm: U256::from(15).to_be_bytes::<32>().to_vec() }));
+
+    // leaves the sum on the stack instead of returning it
+    let silent = Scenario::new("sum", evm_asm! { push 5; push 10; add }.to_vec(), || U256::from(15));
+    assert_eq!(silent.verify(), Err(Mismatch::Output { native: U256::from(15), evm: vec![] }));
+
+    let halting = Scenario::new("sum", evm_asm! { push 1; jump }.to_vec(), || U256::from(15));
+    assert_eq!(halting.verify(), Err(Mismatch::EvmFailed(ExecutionResult::InvalidJump)));
+}
+
+#[test]
+fn test_stateful_scenarios_compare_storage() {
+    // doubles slot 1 and returns the old value
+    let code = evm_asm! { push 1; sload; dup1; dup1; add; push 1; sstore; push 0; mstore; push 0x20; push 0; return }.to_vec();
+    let prestate = HashMap::from([(U256::from(1), U256::from(21))]);
+
+    let doubling = Scenario::stateful("double", code.clone(), |storage| {
+        let old = storage[&U256::from(1)];
+        storage.insert(U256::from(1), old * U256::from(2));
+        old
+    });
+    assert_eq!(doubling.with_storage(prestate.clone()).verify(), Ok(()));
+
+    let forgetful = Scenario::stateful("double", code, |storage| storage[&U256::from(1)]);
+    assert_eq!(
+        forgetful.with_storage(prestate).verify(),
+        Err(Mismatch::Storage { slot: U256::from(1), native: U256::from(21), evm: U256::from(42) })
+    );
+}
+
+#[test]
+fn test_builtin_scenarios_agree() {
+    let scenarios = scenario::builtin();
+
+    assert!(scenarios.iter().all(|scenario| !scenario.description.is_empty()));
+    for scenario in &scenarios {
+        assert_eq!(scenario.verify(), Ok(()), "{}", scenario.name);
+    }
+}
@@ -0,0 +1,91 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, ExecutionResult, Hardfork, Machine, Transaction};
+use ruint::aliases::U256;
+
+mod common;
+use common::assemble;
+
+fn sender() -> Address {
+    "0x3000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn counter() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn caller_contract() -> Address {
+    "0x2100000000000000000000000000000000000000".parse().unwrap()
+}
+
+// transient[0] += 1, then storage[0] = transient[0]
+const TRANSIENT_COUNTER: &str = "PUSH1 0x00 TLOAD PUSH1 0x01 ADD DUP1 PUSH1 0x00 TSTORE PUSH1 0x00 SSTORE STOP";
+
+fn machine(hardfork: Hardfork) -> Machine {
+    let mut machine = Machine::default();
+    machine.hardfork = hardfork;
+    machine.accounts.insert(sender(), Account { balance: U256::from(1_000_000_000), ..Default::default() });
+    machine.accounts.insert(counter(), Account::with_code(assemble(TRANSIENT_COUNTER)));
+    machine
+}
+
+fn tx(to: Address) -> Transaction {
+    Transaction { caller: sender(), to, gas_limit: 200_000, ..Default::default() }
+}
+
+#[test]
+fn test_transient_storage_is_shared_within_a_transaction() {
+    let mut machine = machine(Hardfork::Cancun);
+    let call = format!(
+        "PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 {} PUSH3 0x0fffff CALL POP",
+        counter()
+    );
+    machine.accounts.insert(caller_contract(), Account::with_code(assemble(&format!("{call} {call} STOP"))));
+
+    let outcome = machine.transact(&tx(caller_contract())).unwrap();
+    assert_eq!(outcome.result, ExecutionResult::Success(vec![]));
+    assert_eq!(machine.storage(counter(), U256::ZERO), Ok(U256::from(2)));
+}
+
+#[test]
+fn test_transient_storage_resets_between_transactions() {
+    let mut machine = machine(Hardfork::Cancun);
+    for nonce in 1..=2 {
+        let outcome = machine.transact(&tx(counter())).unwrap();
+        assert_eq!(outcome.result, ExecutionResult::Success(vec![]));
+        assert_eq!(machine.storage(counter(), U256::ZERO), Ok(U256::from(1)));
+        // accounts, and with them nonces, carry over
+        assert_eq!(machine.account(sender()).unwrap().nonce, nonce);
+    }
+}
+
+#[test]
+fn test_transient_opcodes_need_cancun() {
+    let mut machine = machine(Hardfork::Shanghai);
+    assert_eq!(machine.transact(&tx(counter())).unwrap().result, ExecutionResult::InvalidOpcode);
+}
+
+#[test]
+fn test_clearing_a_slot_refunds_gas() {
+    // 21000 intrinsic, two pushes, cold SSTORE resetting a non-zero slot
+    let execution = 21000 + 2 * 3 + 2100 + 2900;
+    for (hardfork, refund) in [(Hardfork::Berlin, execution / 2), (Hardfork::London, 4800)] {
+        let mut machine = machine(hardfork);
+        let mut account = Account::with_code(assemble("PUSH1 0x00 PUSH1 0x00 SSTORE STOP"));
+        account.storage.insert(U256::ZERO, U256::from(1));
+        machine.accounts.insert(counter(), account);
+
+        let outcome = machine.transact(&tx(counter())).unwrap();
+        assert_eq!(outcome.gas_used, execution - refund, "{:?}", hardfork);
+    }
+}
+
+#[test]
+fn test_restoring_the_original_value_refunds_the_set_cost() {
+    let mut machine = machine(Hardfork::London);
+    machine.accounts.insert(counter(), Account::with_code(assemble("PUSH1 0x01 PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x00 SSTORE STOP")));
+
+    // the 19900 refund is capped at a fifth of the gas used
+    let execution = 21000 + 4 * 3 + 2100 + 20000 + 100;
+    let outcome = machine.transact(&tx(counter())).unwrap();
+    assert_eq!(outcome.gas_used, execution - execution / 5);
+}
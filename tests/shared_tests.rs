@@ -0,0 +1,75 @@
+use alloy::primitives::{Address, U256};
+use native_vs_evm::evm::{Account, Machine};
+use native_vs_evm::shared::BaseState;
+use native_vs_evm::tokens::{Erc20, ERC20_CODE};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+
+fn token() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+// a token with 1000 holders of 100 each
+fn base() -> Arc<BaseState> {
+    let mut machine = Machine::default();
+    let erc20 = Erc20::deploy(&mut machine, token());
+    for holder in 1..=1000u64 {
+        erc20.mint(&mut machine, Address::left_padding_from(&holder.to_be_bytes()), U256::from(100)).unwrap();
+    }
+    Arc::new(BaseState::from_accounts(std::mem::take(&mut machine.accounts)))
+}
+
+#[test]
+fn test_machines_on_threads_share_one_base() {
+    let base = base();
+    let erc20 = Erc20 { address: token() };
+
+    thread::scope(|scope| {
+        for worker in 1..=4u64 {
+            let base = base.clone();
+            scope.spawn(move || {
+                let mut machine = Machine::with_base(base);
+                let from = Address::left_padding_from(&worker.to_be_bytes());
+                assert!(erc20.transfer(&mut machine, from, Address::ZERO, U256::from(10 * worker)).unwrap());
+                assert_eq!(erc20.balance_of(&mut machine, from).unwrap(), U256::from(100 - 10 * worker));
+                assert_eq!(erc20.balance_of(&mut machine, Address::ZERO).unwrap(), U256::from(10 * worker));
+                // only the two balances touched were pulled into the overlay
+                assert_eq!(machine.accounts[&token()].storage.len(), 2);
+            });
+        }
+    });
+
+    let mut machine = Machine::with_base(base.clone());
+    assert_eq!(erc20.total_supply(&mut machine).unwrap(), U256::from(100_000));
+    assert_eq!(erc20.balance_of(&mut machine, Address::ZERO).unwrap(), U256::ZERO);
+    assert_eq!(Arc::strong_count(&base), 2);
+}
+
+#[test]
+fn test_clearing_the_overlay_restores_the_base() {
+    let base = base();
+    let erc20 = Erc20 { address: token() };
+    let holder = Address::left_padding_from(&7u64.to_be_bytes());
+    let mut machine = Machine::with_base(base);
+
+    erc20.transfer(&mut machine, holder, Address::ZERO, U256::from(100)).unwrap();
+    assert_eq!(erc20.balance_of(&mut machine, holder).unwrap(), U256::ZERO);
+    machine.accounts.clear();
+    assert_eq!(erc20.balance_of(&mut machine, holder).unwrap(), U256::from(100));
+    assert_eq!(machine.code(token()).unwrap().as_slice(), ERC20_CODE);
+}
+
+#[test]
+fn test_base_accounts_keep_balance_and_nonce() {
+    let funded = Address::with_last_byte(0x01);
+    let accounts = HashMap::from([(funded, Account { balance: U256::from(5), nonce: 3, ..Default::default() })]);
+    let base = BaseState::from_accounts(accounts);
+    assert_eq!(base.len(), 1);
+
+    let mut machine = Machine::with_base(Arc::new(base));
+    let account = machine.account(funded).unwrap();
+    assert_eq!((account.balance, account.nonce), (U256::from(5), 3));
+    assert!(machine.code(funded).unwrap().is_empty());
+    assert_eq!(machine.account(Address::with_last_byte(0x02)).unwrap().balance, U256::ZERO);
+}
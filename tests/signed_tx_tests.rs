@@ -0,0 +1,149 @@
+use alloy::consensus::{SignableTransaction, Signed, TxEip1559, TxEip2930, TxEip4844, TxEip7702, TxEnvelope, TxLegacy};
+use alloy::eips::eip2718::Encodable2718;
+use alloy::eips::eip2930::{AccessList, AccessListItem};
+use alloy::primitives::{Address, Signature, TxKind, B256};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+use native_vs_evm::signed_tx::{SignedTransaction, SignedTransactionError};
+use ruint::aliases::U256;
+
+mod common;
+use common::assemble;
+
+const CHAIN_ID: u64 = 1;
+
+fn contract_address() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn encode<T: SignableTransaction<Signature>>(tx: T, signature: Signature) -> Vec<u8>
+where
+    Signed<T>: Into<TxEnvelope>,
+{
+    let envelope: TxEnvelope = tx.into_signed(signature).into();
+    envelope.encoded_2718()
+}
+
+fn sign<T: SignableTransaction<Signature>>(tx: T, signer: &PrivateKeySigner) -> Vec<u8>
+where
+    Signed<T>: Into<TxEnvelope>,
+{
+    let signature = signer.sign_hash_sync(&tx.signature_hash()).unwrap();
+    encode(tx, signature)
+}
+
+fn legacy_tx() -> TxLegacy {
+    TxLegacy {
+        chain_id: Some(CHAIN_ID),
+        nonce: 0,
+        gas_price: 10,
+        gas_limit: 100_000,
+        to: TxKind::Call(contract_address()),
+        value: U256::from(7),
+        input: vec![0x01, 0x00].into(),
+    }
+}
+
+#[test]
+fn test_legacy_transaction_is_replayed_through_transact() {
+    let signer = PrivateKeySigner::random();
+    let signed = SignedTransaction::decode(&sign(legacy_tx(), &signer)).unwrap();
+
+    assert_eq!(signed.sender, signer.address());
+    assert_eq!(signed.chain_id, Some(CHAIN_ID));
+    assert_eq!(signed.check_chain_id(5), Err(SignedTransactionError::ChainIdMismatch { expected: 5, got: CHAIN_ID }));
+    assert_eq!(signed.transaction.gas_price, U256::from(10));
+    assert_eq!(signed.transaction.gas_priority_fee, None);
+
+    let mut machine = Machine::default();
+    machine.accounts.insert(signer.address(), Account { balance: U256::from(10_000_000), ..Default::default() });
+    machine.accounts.insert(contract_address(), Account::with_code(assemble("PUSH1 0x01 PUSH1 0x01 SSTORE STOP")));
+
+    let outcome = machine.transact(&signed.transaction).unwrap();
+    assert_eq!(outcome.result, ExecutionResult::Success(vec![]));
+    assert_eq!(outcome.gas_used, 21000 + 16 + 4 + 3 + 3 + 20000);
+    assert_eq!(machine.accounts[&contract_address()].balance, U256::from(7));
+    assert_eq!(machine.accounts[&signer.address()].nonce, 1);
+}
+
+#[test]
+fn test_typed_transactions() {
+    let signer = PrivateKeySigner::random();
+    let access_list = AccessList(vec![AccessListItem { address: contract_address(), storage_keys: vec![B256::with_last_byte(1)] }]);
+
+    let eip2930 = TxEip2930 {
+        chain_id: CHAIN_ID,
+        gas_price: 3,
+        gas_limit: 50_000,
+        to: TxKind::Call(contract_address()),
+        access_list: access_list.clone(),
+        ..Default::default()
+    };
+    let signed = SignedTransaction::decode(&sign(eip2930, &signer)).unwrap();
+    assert_eq!(signed.sender, signer.address());
+    assert_eq!(signed.transaction.access_list, vec![(contract_address(), vec![U256::from(1)])]);
+
+    let mut machine = Machine::default();
+    machine.accounts.insert(signer.address(), Account { balance: U256::from(1_000_000), ..Default::default() });
+    let outcome = machine.transact(&signed.transaction).unwrap();
+    assert_eq!(outcome.gas_used, 21000 + 2400 + 1900);
+
+    let eip1559 = TxEip1559 {
+        chain_id: CHAIN_ID,
+        nonce: 4,
+        max_fee_per_gas: 100,
+        max_priority_fee_per_gas: 2,
+        gas_limit: 50_000,
+        to: TxKind::Call(contract_address()),
+        ..Default::default()
+    };
+    let signed = SignedTransaction::decode(&sign(eip1559, &signer)).unwrap();
+    assert_eq!(signed.sender, signer.address());
+    assert_eq!(signed.transaction.nonce, Some(4));
+    assert_eq!(signed.transaction.gas_price, U256::from(100));
+    assert_eq!(signed.transaction.gas_priority_fee, Some(U256::from(2)));
+}
+
+#[test]
+fn test_decodes_blob_transaction() {
+    let signer = PrivateKeySigner::random();
+    let mut hash = B256::repeat_byte(0xab);
+    hash[0] = 0x01;
+    let blob = TxEip4844 {
+        chain_id: CHAIN_ID,
+        max_fee_per_gas: 100,
+        gas_limit: 50_000,
+        to: contract_address(),
+        blob_versioned_hashes: vec![hash],
+        max_fee_per_blob_gas: 7,
+        ..Default::default()
+    };
+
+    let signed = SignedTransaction::decode(&sign(blob, &signer)).unwrap();
+    assert_eq!(signed.sender, signer.address());
+    assert_eq!(signed.transaction.blob_versioned_hashes, vec![hash]);
+    assert_eq!(signed.transaction.max_fee_per_blob_gas, U256::from(7));
+}
+
+#[test]
+fn test_rejects_invalid_transactions() {
+    let signer = PrivateKeySigner::random();
+    let tx = legacy_tx();
+    let signature = signer.sign_hash_sync(&tx.signature_hash()).unwrap();
+
+    // the malleable twin of a valid signature recovers the same key but must be rejected
+    let curve_order = ruint::uint!(0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141_U256);
+    let high_s = Signature::new(signature.r(), curve_order - signature.s(), !signature.v());
+    assert!(matches!(SignedTransaction::decode(&encode(tx.clone(), high_s)), Err(SignedTransactionError::InvalidSignature(_))));
+
+    let mut raw = sign(tx, &signer);
+    raw.push(0x00);
+    assert!(matches!(SignedTransaction::decode(&raw), Err(SignedTransactionError::Decode(_))));
+
+    let creation = TxLegacy { to: TxKind::Create, ..legacy_tx() };
+    assert_eq!(SignedTransaction::decode(&sign(creation, &signer)), Err(SignedTransactionError::ContractCreation));
+
+    let set_code = TxEip7702 { chain_id: CHAIN_ID, gas_limit: 50_000, to: contract_address(), ..Default::default() };
+    assert_eq!(SignedTransaction::decode(&sign(set_code, &signer)), Err(SignedTransactionError::UnsupportedType(4)));
+}
@@ -0,0 +1,62 @@
+use alloy::primitives::{Address, U256};
+use alloy::sol;
+use alloy::sol_types::{SolEvent, SolError};
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+use native_vs_evm::sol::SolCallError;
+use std::rc::Rc;
+
+mod common;
+use common::assemble;
+
+sol! {
+    function add(uint256 a, uint256 b) external returns (uint256 sum);
+    event Added(uint256 indexed a, uint256 sum);
+}
+
+fn adder_address() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn machine_with(code: Vec<u8>) -> Machine {
+    let mut machine = Machine::default();
+    machine.accounts.insert(adder_address(), Account { code: Rc::new(code), ..Default::default() });
+    machine
+}
+
+#[test]
+fn test_call_sol_encodes_and_decodes() {
+    // ignores the selector, returns a + b and emits Added(a, a + b)
+    let code = assemble(&format!(
+        "PUSH1 0x04 CALLDATALOAD PUSH1 0x24 CALLDATALOAD ADD PUSH1 0x00 MSTORE \
+         PUSH1 0x04 CALLDATALOAD PUSH32 {} PUSH1 0x20 PUSH1 0x00 LOG2 \
+         PUSH1 0x20 PUSH1 0x00 RETURN",
+        Added::SIGNATURE_HASH
+    ));
+    let mut machine = machine_with(code);
+
+    let call = addCall { a: U256::from(40), b: U256::from(2) };
+    let sum = machine.call_sol(Address::ZERO, adder_address(), &call, 100_000).unwrap();
+    assert_eq!(sum, U256::from(42));
+
+    let events = machine.events::<Added>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].address, adder_address());
+    assert_eq!(events[0].data.a, U256::from(40));
+    assert_eq!(events[0].data.sum, U256::from(42));
+}
+
+#[test]
+fn test_call_sol_errors() {
+    let mut machine = machine_with(assemble("PUSH1 0x00 PUSH1 0x00 REVERT"));
+    let call = addCall { a: U256::from(1), b: U256::from(2) };
+    assert_eq!(machine.call_sol(Address::ZERO, adder_address(), &call, 100_000), Err(SolCallError::Revert(vec![])));
+
+    let mut machine = machine_with(assemble("PUSH1 0x01 PUSH1 0x00 RETURN"));
+    assert!(matches!(machine.call_sol(Address::ZERO, adder_address(), &call, 100_000), Err(SolCallError::Decode(_))));
+
+    let mut machine = machine_with(assemble("PUSH1 0x05 JUMP"));
+    assert_eq!(machine.call_sol(Address::ZERO, adder_address(), &call, 100_000), Err(SolCallError::Halt(ExecutionResult::InvalidJump)));
+
+    let revert = SolCallError::Revert(alloy::sol_types::Revert::from("nope").abi_encode());
+    assert_eq!(revert.revert_reason(), Some("revert: nope".to_string()));
+}
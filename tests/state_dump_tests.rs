@@ -0,0 +1,79 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, Machine};
+use native_vs_evm::state_dump::{AccountDump, StateDump};
+use ruint::aliases::U256;
+use std::collections::BTreeMap;
+
+mod common;
+use common::assemble;
+
+fn address(byte: u8) -> Address {
+    Address::new([byte; 20])
+}
+
+// the same state, built in the order given
+fn machine(order: &[u8]) -> Machine {
+    let mut machine = Machine::default();
+    for &byte in order {
+        let mut account = Account::with_code(if byte == 1 { assemble("PUSH1 0x01 STOP") } else { vec![] });
+        account.balance = U256::from(byte) * U256::from(1000);
+        account.nonce = byte as u64;
+        for key in (0..20u64).rev().chain(0..20) {
+            account.storage.insert(U256::from(key * byte as u64), U256::from(key));
+        }
+        machine.accounts.insert(address(byte), account);
+    }
+    machine
+}
+
+#[test]
+fn test_sorted_storage_skips_zero_slots() {
+    let mut account = Account::default();
+    account.storage.insert(U256::from(9), U256::from(1));
+    account.storage.insert(U256::from(2), U256::ZERO);
+    account.storage.insert(U256::from(3), U256::from(5));
+    assert_eq!(account.sorted_storage(), vec![(U256::from(3), U256::from(5)), (U256::from(9), U256::from(1))]);
+}
+
+#[test]
+fn test_sorted_accounts() {
+    let machine = machine(&[3, 1, 2]);
+    let addresses: Vec<Address> = machine.sorted_accounts().into_iter().map(|(address, _)| address).collect();
+    assert_eq!(addresses, vec![address(1), address(2), address(3)]);
+}
+
+#[test]
+fn test_dump_does_not_depend_on_insertion_order() {
+    let (a, b) = (machine(&[1, 2, 3]), machine(&[3, 2, 1]));
+    assert_eq!(a.dump_state(), b.dump_state());
+    assert_eq!(a.dump_state().to_json(), b.dump_state().to_json());
+    assert_eq!(format!("{:?}", a.accounts[&address(2)]), format!("{:?}", b.accounts[&address(2)]));
+    assert_eq!(format!("{:?}", a), format!("{:?}", b));
+}
+
+#[test]
+fn test_canonical_json() {
+    let mut storage = BTreeMap::new();
+    storage.insert(U256::from(1), U256::from(7));
+    let mut dump = StateDump::default();
+    dump.accounts.insert(address(0xab), AccountDump { balance: U256::from(255), nonce: 2, code: vec![0x60, 0x01], storage });
+
+    let slot = |value: u8| format!("0x{}{:02x}", "00".repeat(31), value);
+    let expected = format!(
+        r#"{{
+  "accounts": {{
+    "0xabababababababababababababababababababab": {{
+      "balance": "0xff",
+      "code": "0x6001",
+      "nonce": 2,
+      "storage": {{
+        "{}": "{}"
+      }}
+    }}
+  }}
+}}"#,
+        slot(1),
+        slot(7)
+    );
+    assert_eq!(dump.to_json(), expected);
+}
@@ -0,0 +1,61 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, ExecutionStats, Machine};
+use std::collections::HashMap;
+
+mod common;
+use common::assemble;
+
+fn outer() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn inner() -> Address {
+    "0x2100000000000000000000000000000000000000".parse().unwrap()
+}
+
+// outer calls inner, which returns 0x80 bytes of memory it never wrote
+fn machine() -> Machine {
+    let mut machine = Machine::default();
+    let call = format!(
+        "PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH20 0x{} PUSH2 0xffff CALL STOP",
+        inner().to_string().strip_prefix("0x").unwrap()
+    );
+    machine.accounts.insert(outer(), Account::with_code(assemble(&call)));
+    machine.accounts.insert(inner(), Account::with_code(assemble("PUSH1 0x80 PUSH1 0x00 RETURN")));
+    machine
+}
+
+#[test]
+fn test_stats_of_a_single_frame() {
+    let bytecode = assemble("PUSH1 0x01 PUSH1 0x02 PUSH1 0x03 POP POP POP PUSH1 0x40 MLOAD STOP");
+    let mut machine = Machine::new(bytecode, vec![], HashMap::new(), 1_000_000);
+    machine.run();
+
+    assert_eq!(machine.stats(), ExecutionStats { steps: 9, max_stack_height: 3, max_memory: 0x60, frames: 1, max_call_depth: 1 });
+}
+
+#[test]
+fn test_stats_cover_nested_frames() {
+    let mut machine = machine();
+    machine.call(Address::ZERO, outer(), vec![], 1_000_000);
+
+    // outer's stack peaks at the 7 CALL operands; memory only grows in inner's RETURN
+    assert_eq!(machine.stats(), ExecutionStats { steps: 12, max_stack_height: 7, max_memory: 0x80, frames: 2, max_call_depth: 2 });
+}
+
+#[test]
+fn test_stats_start_over_with_each_call() {
+    let mut machine = machine();
+    machine.call(Address::ZERO, outer(), vec![], 1_000_000);
+    machine.call(Address::ZERO, inner(), vec![], 1_000_000);
+
+    assert_eq!(machine.stats(), ExecutionStats { steps: 3, max_stack_height: 2, max_memory: 0x80, frames: 1, max_call_depth: 1 });
+}
+
+#[test]
+fn test_outcome_carries_stats() {
+    let mut machine = machine();
+    let outcome = machine.expect_call(Address::ZERO, outer(), vec![], 1_000_000).outcome;
+    assert_eq!(outcome.stats.frames, 2);
+    assert_eq!(outcome.stats, machine.stats());
+}
@@ -0,0 +1,156 @@
+use alloy::primitives::{keccak256, Address};
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+use native_vs_evm::storage_layout::{LayoutError, StorageLayout};
+use ruint::aliases::U256;
+use std::collections::HashMap;
+
+mod common;
+use common::assemble;
+
+// contract Token {
+//     address owner; bool paused; uint64 nonce;
+//     mapping(address => uint256) balances;
+//     uint256[] values; uint16[] small;
+//     string name;
+//     mapping(address => mapping(address => uint256)) allowances;
+// }
+const LAYOUT: &str = r#"{"storageLayout": {
+    "storage": [
+        {"astId": 3, "contract": "src/Token.sol:Token", "label": "owner", "offset": 0, "slot": "0", "type": "t_address"},
+        {"astId": 5, "contract": "src/Token.sol:Token", "label": "paused", "offset": 20, "slot": "0", "type": "t_bool"},
+        {"astId": 7, "contract": "src/Token.sol:Token", "label": "nonce", "offset": 21, "slot": "0", "type": "t_uint64"},
+        {"astId": 11, "contract": "src/Token.sol:Token", "label": "balances", "offset": 0, "slot": "1", "type": "t_mapping(t_address,t_uint256)"},
+        {"astId": 14, "contract": "src/Token.sol:Token", "label": "values", "offset": 0, "slot": "2", "type": "t_array(t_uint256)dyn_storage"},
+        {"astId": 17, "contract": "src/Token.sol:Token", "label": "small", "offset": 0, "slot": "3", "type": "t_array(t_uint16)dyn_storage"},
+        {"astId": 19, "contract": "src/Token.sol:Token", "label": "name", "offset": 0, "slot": "4", "type": "t_string_storage"},
+        {"astId": 25, "contract": "src/Token.sol:Token", "label": "allowances", "offset": 0, "slot": "5", "type": "t_mapping(t_address,t_mapping(t_address,t_uint256))"}
+    ],
+    "types": {
+        "t_address": {"encoding": "inplace", "label": "address", "numberOfBytes": "20"},
+        "t_bool": {"encoding": "inplace", "label": "bool", "numberOfBytes": "1"},
+        "t_uint16": {"encoding": "inplace", "label": "uint16", "numberOfBytes": "2"},
+        "t_uint64": {"encoding": "inplace", "label": "uint64", "numberOfBytes": "8"},
+        "t_uint256": {"encoding": "inplace", "label": "uint256", "numberOfBytes": "32"},
+        "t_string_storage": {"encoding": "bytes", "label": "string", "numberOfBytes": "32"},
+        "t_array(t_uint256)dyn_storage": {"base": "t_uint256", "encoding": "dynamic_array", "label": "uint256[]", "numberOfBytes": "32"},
+        "t_array(t_uint16)dyn_storage": {"base": "t_uint16", "encoding": "dynamic_array", "label": "uint16[]", "numberOfBytes": "32"},
+        "t_mapping(t_address,t_uint256)": {"encoding": "mapping", "key": "t_address", "label": "mapping(address => uint256)", "numberOfBytes": "32", "value": "t_uint256"},
+        "t_mapping(t_address,t_mapping(t_address,t_uint256))": {"encoding": "mapping", "key": "t_address", "label": "mapping(address => mapping(address => uint256))", "numberOfBytes": "32", "value": "t_mapping(t_address,t_uint256)"}
+    }
+}}"#;
+
+fn holder() -> Address {
+    "0x00000000000000000000000000000000000000aa".parse().unwrap()
+}
+
+fn keccak_slot(preimage: &[u8]) -> U256 {
+    U256::from_be_bytes(keccak256(preimage).0)
+}
+
+#[test]
+fn test_packed_value_types() {
+    let layout = StorageLayout::parse(LAYOUT).unwrap();
+    let owner: Address = "0x1111111111111111111111111111111111111111".parse().unwrap();
+    let mut packed = U256::from_be_slice(owner.as_slice());
+    packed |= U256::from(1) << 160;
+    packed |= U256::from(7) << 168;
+    let storage = HashMap::from([(U256::ZERO, packed)]);
+
+    assert_eq!(layout.variable("owner").unwrap().address(&storage).unwrap(), owner);
+    assert!(layout.variable("paused").unwrap().boolean(&storage).unwrap());
+    assert_eq!(layout.variable("nonce").unwrap().uint(&storage).unwrap(), U256::from(7));
+}
+
+#[test]
+fn test_mapping_slot_matches_what_the_evm_computes() {
+    // balances[holder] = 42, hashing holder . 1 the way solc does
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract, Account::with_code(assemble(&format!(
+        "PUSH20 {} PUSH1 0x00 MSTORE PUSH1 0x01 PUSH1 0x20 MSTORE PUSH1 0x40 PUSH1 0x00 SHA3 PUSH1 0x2a SWAP1 SSTORE STOP",
+        holder()
+    ))));
+    assert_eq!(machine.call(Address::ZERO, contract, vec![], 100_000), ExecutionResult::Success(vec![]));
+
+    let layout = StorageLayout::parse(LAYOUT).unwrap();
+    let balance = layout.variable("balances").unwrap().key(holder()).unwrap();
+    assert_eq!(balance.uint(&machine.accounts[&contract].storage).unwrap(), U256::from(42));
+}
+
+#[test]
+fn test_nested_mappings_and_arrays() {
+    let layout = StorageLayout::parse(LAYOUT).unwrap();
+    let spender = Address::with_last_byte(0xbb);
+    let inner = keccak_slot(&[holder().into_word().as_slice(), &U256::from(5).to_be_bytes::<32>()].concat());
+    let allowance = keccak_slot(&[spender.into_word().as_slice(), &inner.to_be_bytes::<32>()].concat());
+
+    let values = keccak_slot(&U256::from(2).to_be_bytes::<32>());
+    let small = keccak_slot(&U256::from(3).to_be_bytes::<32>());
+    let storage = HashMap::from([
+        (allowance, U256::from(500)),
+        (U256::from(2), U256::from(2)),
+        (values + U256::from(1), U256::from(9)),
+        (U256::from(3), U256::from(17)),
+        // sixteen uint16s per slot: element 17 is the second one in the second slot
+        (small + U256::from(1), U256::from(0xbeef) << 16),
+    ]);
+
+    let allowances = layout.variable("allowances").unwrap();
+    assert_eq!(allowances.key(holder()).unwrap().key(spender).unwrap().uint(&storage).unwrap(), U256::from(500));
+    let values = layout.variable("values").unwrap();
+    assert_eq!(values.len(&storage).unwrap(), 2);
+    assert_eq!(values.index(1).unwrap().uint(&storage).unwrap(), U256::from(9));
+    assert_eq!(layout.variable("small").unwrap().index(17).unwrap().uint(&storage).unwrap(), U256::from(0xbeef));
+}
+
+#[test]
+fn test_short_and_long_strings() {
+    let layout = StorageLayout::parse(LAYOUT).unwrap();
+    let name = layout.variable("name").unwrap();
+
+    let mut short = [0u8; 32];
+    short[..5].copy_from_slice(b"Token");
+    short[31] = 10;
+    let storage = HashMap::from([(U256::from(4), U256::from_be_bytes(short))]);
+    assert_eq!(name.string(&storage).unwrap(), "Token");
+
+    let long = "a string that does not fit in a single storage slot";
+    let data = keccak_slot(&U256::from(4).to_be_bytes::<32>());
+    let mut storage = HashMap::from([(U256::from(4), U256::from(long.len() * 2 + 1))]);
+    for (i, chunk) in long.as_bytes().chunks(32).enumerate() {
+        let mut word = [0u8; 32];
+        word[..chunk.len()].copy_from_slice(chunk);
+        storage.insert(data + U256::from(i), U256::from_be_bytes(word));
+    }
+    assert_eq!(name.string(&storage).unwrap(), long);
+}
+
+#[test]
+fn test_misuse_is_reported() {
+    let layout = StorageLayout::parse(LAYOUT).unwrap();
+    let storage = HashMap::new();
+    assert_eq!(layout.variable("missing").unwrap_err(), LayoutError::UnknownVariable("missing".into()));
+    assert_eq!(
+        layout.variable("balances").unwrap().uint(&storage).unwrap_err(),
+        LayoutError::TypeMismatch { expected: "value type", found: "mapping(address => uint256)".into() }
+    );
+    assert!(layout.variable("owner").unwrap().key(holder()).is_err());
+    assert!(StorageLayout::parse("{}").is_err());
+}
+
+#[test]
+fn test_corrupt_layouts_and_lengths_are_errors() {
+    let layout = StorageLayout::parse(&LAYOUT.replace(r#""label": "uint16", "numberOfBytes": "2""#, r#""label": "uint16", "numberOfBytes": "0""#)).unwrap();
+    assert_eq!(layout.variable("small").unwrap().index(3).unwrap_err(), LayoutError::ZeroSize("t_uint16".into()));
+
+    let layout = StorageLayout::parse(LAYOUT).unwrap();
+    let storage = HashMap::from([(U256::from(2), U256::MAX), (U256::from(4), U256::MAX)]);
+    assert_eq!(layout.variable("values").unwrap().len(&storage).unwrap_err(), LayoutError::LengthOutOfRange(U256::MAX));
+    assert_eq!(layout.variable("name").unwrap().bytes(&storage).unwrap_err(), LayoutError::LengthOutOfRange(U256::MAX >> 1));
+
+    // two slots per element, so the slot offset doesn't fit a usize
+    let layout = StorageLayout::parse(&LAYOUT.replace(r#""label": "uint256", "numberOfBytes": "32""#, r#""label": "uint256", "numberOfBytes": "64""#)).unwrap();
+    let values = keccak_slot(&U256::from(2).to_be_bytes::<32>());
+    let element = layout.variable("values").unwrap().index(usize::MAX).unwrap();
+    assert_eq!(element.slot, values + U256::from(usize::MAX) * U256::from(2));
+}
@@ -0,0 +1,105 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+use native_vs_evm::symbolic::{PathEnd, SymbolicConfig};
+use ruint::aliases::U256;
+
+mod common;
+use common::{assemble, contract};
+
+fn machine(code: &str) -> Machine {
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract(), Account::with_code(assemble(code)));
+    machine
+}
+
+#[test]
+fn test_finds_the_selector_that_reverts() {
+    // reverts when the selector is 0xdeadbeef, returns otherwise
+    let mut machine = machine(
+        "PUSH1 0x00 CALLDATALOAD PUSH29 0x0100000000000000000000000000000000000000000000000000000000 DIV \
+         PUSH4 0xdeadbeef EQ PUSH1 0x30 JUMPI PUSH1 0x00 PUSH1 0x00 RETURN JUMPDEST PUSH1 0x00 PUSH1 0x00 REVERT",
+    );
+    let report = machine.explore(contract(), SymbolicConfig::default()).unwrap();
+
+    assert_eq!(report.paths.len(), 2);
+    assert!(!report.truncated);
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!(failures.len(), 1);
+    let witness = failures[0].witness.as_ref().unwrap();
+    assert_eq!(&witness.calldata[..4], &[0xde, 0xad, 0xbe, 0xef]);
+
+    let success = report.paths.iter().find(|path| path.end == PathEnd::Success).unwrap();
+    assert!(success.witness.is_some());
+}
+
+#[test]
+fn test_reports_a_reachable_assert() {
+    // INVALID once the argument exceeds 1000
+    let mut machine = machine("PUSH1 0x04 CALLDATALOAD PUSH2 0x03e8 GT PUSH1 0x0b JUMPI STOP JUMPDEST INVALID");
+    let report = machine.explore(contract(), SymbolicConfig::default()).unwrap();
+
+    let failure = report.failures().next().unwrap();
+    assert_eq!(failure.end, PathEnd::Halt(ExecutionResult::InvalidOpcode));
+    let witness = failure.witness.as_ref().unwrap();
+    assert_eq!(U256::from_be_slice(&witness.calldata[4..36]), U256::from(1001));
+    assert_eq!(machine.call(Address::ZERO, contract(), witness.calldata.clone(), 100_000), ExecutionResult::InvalidOpcode);
+}
+
+#[test]
+fn test_symbolic_storage() {
+    const CODE: &str = "PUSH1 0x00 SLOAD PUSH1 0x07 JUMPI STOP JUMPDEST PUSH1 0x00 PUSH1 0x00 REVERT";
+
+    // against the machine's storage the slot is zero, so there is nothing to branch on
+    let report = machine(CODE).explore(contract(), SymbolicConfig::default()).unwrap();
+    assert_eq!(report.paths.len(), 1);
+    assert_eq!(report.paths[0].end, PathEnd::Success);
+
+    let mut machine = machine(CODE);
+    let report = machine.explore(contract(), SymbolicConfig { symbolic_storage: true, ..Default::default() }).unwrap();
+    let witness = report.failures().next().unwrap().witness.as_ref().unwrap();
+    assert_eq!(witness.storage[&U256::ZERO], U256::from(1));
+    // the confirming run did not leak its storage
+    assert_eq!(machine.storage(contract(), U256::ZERO), Ok(U256::ZERO));
+}
+
+#[test]
+fn test_symbolic_loops_are_bounded() {
+    // for (i = 1; i < calldata[0]; i++) {}
+    let mut machine = machine("PUSH1 0x00 JUMPDEST PUSH1 0x01 ADD DUP1 PUSH1 0x00 CALLDATALOAD LT PUSH1 0x02 JUMPI STOP");
+    let report = machine.explore(contract(), SymbolicConfig { max_paths: 8, max_steps: 1_000, ..Default::default() }).unwrap();
+
+    assert!(report.truncated);
+    assert_eq!(report.paths.len(), 8);
+    assert!(report.paths.iter().any(|path| path.end == PathEnd::StepLimit));
+    assert!(report.paths.iter().filter(|path| path.end == PathEnd::Success).all(|path| path.witness.is_some()));
+}
+
+#[test]
+fn test_external_calls_are_unsupported() {
+    let mut machine = machine("PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 PUSH1 0x00 CALL STOP");
+    let report = machine.explore(contract(), SymbolicConfig::default()).unwrap();
+    assert_eq!(report.paths[0].end, PathEnd::Unsupported("external call".into()));
+    assert_eq!(report.paths[0].witness, None);
+}
+
+#[test]
+fn test_huge_sha3_sizes_end_the_path() {
+    let mut hashing = machine("PUSH6 0x010000000000 PUSH1 0x00 SHA3 STOP");
+    let report = hashing.explore(contract(), SymbolicConfig::default()).unwrap();
+    assert_eq!(report.paths[0].end, PathEnd::Unsupported("SHA3 size out of range".into()));
+
+    // 2^64 + 32 is not read as 32
+    let mut aliased = machine("PUSH9 0x010000000000000020 MLOAD STOP");
+    let report = aliased.explore(contract(), SymbolicConfig::default()).unwrap();
+    assert_eq!(report.paths[0].end, PathEnd::Unsupported("memory offset out of range".into()));
+}
+
+#[test]
+fn test_huge_calldata_offsets_end_the_path() {
+    for offset in ["0xffffffffffffffe0", "0x0000010000000000"] {
+        let mut huge = machine(&format!("PUSH8 {} CALLDATALOAD STOP", offset));
+        let report = huge.explore(contract(), SymbolicConfig::default()).unwrap();
+        assert_eq!(report.paths[0].end, PathEnd::Unsupported("calldata offset out of range".into()));
+        assert_eq!(report.paths.len(), 1);
+    }
+}
@@ -0,0 +1,97 @@
+use alloy::primitives::{keccak256, Address, U256};
+use native_vs_evm::evm::Machine;
+use native_vs_evm::sol::SolCallError;
+use native_vs_evm::tokens::{Erc20, Erc721, IERC20, IERC721, ERC20_CODE, ERC721_CODE};
+use native_vs_evm::validate::validate_bytecode;
+
+mod common;
+use common::alice;
+
+fn token() -> Address {
+    "0x2000000000000000000000000000000000000000".parse().unwrap()
+}
+
+fn bob() -> Address {
+    Address::with_last_byte(0xb0)
+}
+
+#[test]
+fn test_bundled_code_validates() {
+    assert!(validate_bytecode(ERC20_CODE).is_empty());
+    assert!(validate_bytecode(ERC721_CODE).is_empty());
+}
+
+#[test]
+fn test_erc20_mint_and_transfer() {
+    let mut machine = Machine::default();
+    let erc20 = Erc20::deploy(&mut machine, token());
+    erc20.mint(&mut machine, alice(), U256::from(1000)).unwrap();
+
+    assert!(erc20.transfer(&mut machine, alice(), bob(), U256::from(300)).unwrap());
+    let transfers = machine.events::<IERC20::Transfer>();
+    assert_eq!((transfers[0].data.from, transfers[0].data.to, transfers[0].data.value), (alice(), bob(), U256::from(300)));
+
+    assert_eq!(erc20.balance_of(&mut machine, alice()).unwrap(), U256::from(700));
+    assert_eq!(erc20.balance_of(&mut machine, bob()).unwrap(), U256::from(300));
+    assert_eq!(erc20.total_supply(&mut machine).unwrap(), U256::from(1000));
+
+    // balances live where solc puts mapping(address => uint256) at slot 1
+    let slot = keccak256([alice().into_word().as_slice(), &U256::from(1).to_be_bytes::<32>()].concat());
+    assert_eq!(machine.accounts[&token()].storage[&U256::from_be_bytes(slot.0)], U256::from(700));
+
+    assert_eq!(erc20.transfer(&mut machine, bob(), alice(), U256::from(301)), Err(SolCallError::Revert(vec![])));
+    assert!(erc20.transfer(&mut machine, bob(), bob(), U256::from(300)).unwrap());
+    assert_eq!(erc20.balance_of(&mut machine, bob()).unwrap(), U256::from(300));
+}
+
+#[test]
+fn test_erc20_allowances() {
+    let mut machine = Machine::default();
+    let erc20 = Erc20::deploy(&mut machine, token());
+    erc20.mint(&mut machine, alice(), U256::from(1000)).unwrap();
+
+    assert!(erc20.approve(&mut machine, alice(), bob(), U256::from(500)).unwrap());
+    assert_eq!(machine.events::<IERC20::Approval>()[0].data.spender, bob());
+    assert!(erc20.transfer_from(&mut machine, bob(), alice(), bob(), U256::from(200)).unwrap());
+
+    assert_eq!(erc20.allowance(&mut machine, alice(), bob()).unwrap(), U256::from(300));
+    assert_eq!(erc20.balance_of(&mut machine, bob()).unwrap(), U256::from(200));
+    assert!(erc20.transfer_from(&mut machine, bob(), alice(), bob(), U256::from(301)).is_err());
+    assert!(erc20.transfer_from(&mut machine, alice(), alice(), bob(), U256::from(1)).is_err());
+}
+
+#[test]
+fn test_erc721_ownership() {
+    let mut machine = Machine::default();
+    let erc721 = Erc721::deploy(&mut machine, token());
+    let id = U256::from(7);
+    erc721.mint(&mut machine, alice(), id).unwrap();
+
+    assert_eq!(erc721.owner_of(&mut machine, id).unwrap(), alice());
+    assert_eq!(erc721.balance_of(&mut machine, alice()).unwrap(), U256::from(1));
+    assert!(erc721.mint(&mut machine, bob(), id).is_err());
+    assert!(erc721.owner_of(&mut machine, U256::from(8)).is_err());
+
+    assert!(erc721.transfer_from(&mut machine, bob(), alice(), bob(), id).is_err());
+    erc721.transfer_from(&mut machine, alice(), alice(), bob(), id).unwrap();
+    let transfer = &machine.events::<IERC721::Transfer>()[0].data;
+    assert_eq!((transfer.from, transfer.to, transfer.tokenId), (alice(), bob(), id));
+    assert_eq!(erc721.owner_of(&mut machine, id).unwrap(), bob());
+    assert_eq!(erc721.balance_of(&mut machine, alice()).unwrap(), U256::ZERO);
+}
+
+#[test]
+fn test_erc721_approval_is_single_use() {
+    let mut machine = Machine::default();
+    let erc721 = Erc721::deploy(&mut machine, token());
+    let id = U256::from(1);
+    erc721.mint(&mut machine, alice(), id).unwrap();
+
+    assert!(erc721.approve(&mut machine, bob(), bob(), id).is_err());
+    erc721.approve(&mut machine, alice(), bob(), id).unwrap();
+    assert_eq!(erc721.get_approved(&mut machine, id).unwrap(), bob());
+
+    erc721.transfer_from(&mut machine, bob(), alice(), alice(), id).unwrap();
+    assert_eq!(erc721.get_approved(&mut machine, id).unwrap(), Address::ZERO);
+    assert!(erc721.transfer_from(&mut machine, bob(), alice(), bob(), id).is_err());
+}
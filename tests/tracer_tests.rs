@@ -0,0 +1,71 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::{Account, ExecutionResult, Machine};
+use native_vs_evm::tracer::{StreamingTracer, TraceFormat};
+use serde_json::Value;
+use std::io::{self, Write};
+
+mod common;
+use common::assemble;
+
+fn machine(code: &str) -> (Machine, Address) {
+    let contract: Address = "0x2000000000000000000000000000000000000000".parse().unwrap();
+    let mut machine = Machine::default();
+    machine.accounts.insert(contract, Account::with_code(assemble(code)));
+    (machine, contract)
+}
+
+#[test]
+fn test_compact_trace() {
+    let (mut machine, contract) = machine("PUSH1 0x05 PUSH1 0x0a ADD STOP");
+    let mut tracer = StreamingTracer::new(Vec::new(), TraceFormat::Compact);
+    let result = machine.call_with_inspector(Address::ZERO, contract, vec![], 100, &mut tracer);
+    assert_eq!(result, ExecutionResult::Success(vec![]));
+
+    let trace = String::from_utf8(tracer.finish().unwrap()).unwrap();
+    assert_eq!(trace, "0 PUSH1 100 3 1\n2 PUSH1 97 3 1 0x5\n4 ADD 94 3 1 0x5 0xa\n5 STOP 91 0 1 0xf\n");
+}
+
+#[test]
+fn test_json_lines_trace() {
+    let (mut machine, contract) = machine("PUSH1 0x05 PUSH1 0x0a ADD STOP");
+    let mut tracer = StreamingTracer::new(Vec::new(), TraceFormat::JsonLines);
+    machine.call_with_inspector(Address::ZERO, contract, vec![], 100, &mut tracer);
+
+    let trace = tracer.finish().unwrap();
+    let lines: Vec<Value> = trace.split(|byte| *byte == b'\n').filter(|line| !line.is_empty()).map(|line| serde_json::from_slice(line).unwrap()).collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[2]["op"], "ADD");
+    assert_eq!(lines[2]["gas"], 94);
+    assert_eq!(lines[2]["gasCost"], 3);
+    assert_eq!(lines[2]["stack"], serde_json::json!(["0x5", "0xa"]));
+}
+
+// accepts `limit` bytes, then fails every write
+#[derive(Debug)]
+struct FailingWriter {
+    limit: usize,
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.limit < buf.len() {
+            return Err(io::Error::other("disk full"));
+        }
+        self.limit -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_write_errors_are_reported_without_stopping_execution() {
+    let (mut machine, contract) = machine("PUSH1 0x05 PUSH1 0x0a ADD STOP");
+    let mut tracer = StreamingTracer::new(FailingWriter { limit: 20 }, TraceFormat::Compact);
+    let result = machine.call_with_inspector(Address::ZERO, contract, vec![], 100, &mut tracer);
+
+    assert_eq!(result, ExecutionResult::Success(vec![]));
+    assert_eq!(tracer.finish().unwrap_err().to_string(), "disk full");
+}
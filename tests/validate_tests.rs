@@ -0,0 +1,44 @@
+use alloy::primitives::Address;
+use native_vs_evm::evm::Machine;
+use native_vs_evm::validate::{validate_bytecode, Diagnostic};
+
+mod common;
+use common::assemble;
+
+#[test]
+fn test_well_formed_code_passes() {
+    assert!(validate_bytecode(&assemble("PUSH1 0x03 JUMPDEST PUSH1 0x01 SUB DUP1 PUSH1 0x02 JUMPI STOP")).is_empty());
+    assert!(validate_bytecode(&[]).is_empty());
+}
+
+#[test]
+fn test_underflow_is_found_on_a_static_path() {
+    // the jump skips the PUSH that would feed ADD
+    let code = assemble("PUSH1 0x01 PUSH1 0x07 JUMP PUSH1 0x02 JUMPDEST ADD STOP");
+    assert_eq!(validate_bytecode(&code), vec![Diagnostic::StackUnderflow { pc: 8, required: 2, available: 1 }]);
+}
+
+#[test]
+fn test_growing_loop_overflows() {
+    let code = assemble("JUMPDEST PUSH1 0x01 PUSH1 0x00 JUMP");
+    assert_eq!(validate_bytecode(&code), vec![Diagnostic::StackOverflow { pc: 3, height: 1025 }]);
+}
+
+#[test]
+fn test_truncated_push_and_unreachable_blocks() {
+    let mut code = assemble("STOP JUMPDEST ADD");
+    code.extend([0x62, 0xaa]);
+    // the block holding ADD is only reachable through a computed jump, so it is not checked
+    assert_eq!(validate_bytecode(&code), vec![Diagnostic::TruncatedPush { pc: 3, missing: 2 }]);
+}
+
+#[test]
+fn test_deploy_validated_rejects_bad_code() {
+    let mut machine = Machine::default();
+    let address = Address::with_last_byte(0x42);
+    assert!(machine.deploy_validated(address, assemble("POP STOP")).is_err());
+    assert!(!machine.accounts.contains_key(&address));
+
+    machine.deploy_validated(address, assemble("PUSH1 0x01 POP STOP")).unwrap();
+    assert_eq!(machine.code(address).unwrap().len(), 4);
+}